@@ -0,0 +1,159 @@
+use chrono::{DateTime, Days, FixedOffset, Local, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::screentime::ScreenTime;
+
+/// Heartbeats with the same settings this close together (seconds) merge into one event
+const PULSETIME: u32 = 15 * 60;
+/// Bucket type of aw-watcher-afk
+const AFK_BUCKET_TYPE: &str = "afkstatus";
+
+/// `[activitywatch]` config section: share data with a local ActivityWatch server
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ActivityWatchConfig {
+    pub url: String,
+    /// Take active time, longest stretch and breaks in reports from aw-watcher-afk
+    pub screen_time: bool,
+    /// Record the applied brightness and color temperature in a `healthy-monitor_<host>` bucket
+    pub publish: bool,
+}
+
+impl Default for ActivityWatchConfig {
+    fn default() -> Self {
+        ActivityWatchConfig { url: "http://localhost:5600".to_string(), screen_time: true, publish: true }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Bucket {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    hostname: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Event {
+    timestamp: DateTime<FixedOffset>,
+    /// Seconds
+    duration: f64,
+    data: Value,
+}
+
+/// Replaces the activity figures of `first..=last` in `history` with ActivityWatch's AFK data,
+/// keeping healthy-monitor's own numbers when the server can't be reached
+pub async fn merge(config: &ActivityWatchConfig, history: &mut ScreenTime, first: NaiveDate, last: NaiveDate) {
+    if let Err(e) = try_merge(config, history, first, last).await {
+        eprintln!("ActivityWatch unavailable ({}), using healthy-monitor's own screen time", e);
+    }
+}
+
+async fn try_merge(
+    config: &ActivityWatchConfig,
+    history: &mut ScreenTime,
+    first: NaiveDate,
+    last: NaiveDate,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let client = reqwest::Client::new();
+    let host = hostname();
+    let buckets: std::collections::HashMap<String, Bucket> =
+        client.get(format!("{}/api/0/buckets/", config.url)).send().await?.error_for_status()?.json().await?;
+    let bucket = buckets
+        .into_values()
+        .filter(|bucket| bucket.kind == AFK_BUCKET_TYPE)
+        .max_by_key(|bucket| bucket.hostname == host)
+        .ok_or("no aw-watcher-afk bucket")?;
+
+    let mut day = first;
+    while day <= last {
+        let start = midnight(day);
+        let end = midnight(day + Days::new(1));
+        let events: Vec<Event> = client
+            .get(format!("{}/api/0/buckets/{}/events", config.url, bucket.id))
+            .query(&[("start", start.to_rfc3339()), ("end", end.to_rfc3339()), ("limit", "-1".to_string())])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let stats = history.days.entry(day).or_default();
+        stats.active_seconds = 0.0;
+        stats.longest_stretch_seconds = 0.0;
+        stats.breaks = 0;
+        for event in &events {
+            // Events at the edges of the day only count with their part inside it
+            let from = event.timestamp.with_timezone(&Utc).max(start);
+            let to = (event.timestamp.with_timezone(&Utc) + chrono::Duration::milliseconds((event.duration * 1000.0) as i64)).min(end);
+            let seconds = (to - from).num_milliseconds().max(0) as f64 / 1000.0;
+
+            match event.data.get("status").and_then(Value::as_str) {
+                Some("not-afk") => {
+                    stats.active_seconds += seconds;
+                    stats.longest_stretch_seconds = stats.longest_stretch_seconds.max(seconds);
+                }
+                Some("afk") if seconds > 0.0 => stats.breaks += 1,
+                _ => {}
+            }
+        }
+
+        day = day + Days::new(1);
+    }
+
+    Ok(())
+}
+
+/// Records the applied settings as a heartbeat; failures are only reported
+pub async fn publish(config: &ActivityWatchConfig, brightness: f64, color_temp: f64, period: &str) {
+    if let Err(e) = try_publish(config, brightness, color_temp, period).await {
+        eprintln!("Failed to publish to ActivityWatch: {}", e);
+    }
+}
+
+async fn try_publish(
+    config: &ActivityWatchConfig,
+    brightness: f64,
+    color_temp: f64,
+    period: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let client = reqwest::Client::new();
+    let host = hostname();
+    let bucket = format!("healthy-monitor_{}", host);
+
+    // Creating an existing bucket is answered with 304, which is fine
+    client
+        .post(format!("{}/api/0/buckets/{}", config.url, bucket))
+        .json(&json!({ "client": "healthy-monitor", "type": "healthy-monitor.settings", "hostname": host }))
+        .send()
+        .await?;
+
+    let event = json!({
+        "timestamp": Utc::now().to_rfc3339(),
+        "duration": 0,
+        "data": {
+            "brightness": (brightness * 100.0).round() / 100.0,
+            "color_temp": color_temp.round(),
+            "period": period,
+        },
+    });
+    client
+        .post(format!("{}/api/0/buckets/{}/heartbeat", config.url, bucket))
+        .query(&[("pulsetime", PULSETIME)])
+        .json(&event)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Start of a local day, in UTC
+fn midnight(day: NaiveDate) -> DateTime<Utc> {
+    let naive = day.and_hms_opt(0, 0, 0).unwrap();
+    Local.from_local_datetime(&naive).earliest().map_or_else(|| Utc.from_utc_datetime(&naive), |t| t.with_timezone(&Utc))
+}
+
+fn hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname").map_or_else(|_| "unknown".to_string(), |name| name.trim().to_string())
+}
@@ -0,0 +1,36 @@
+use std::path::{Path, PathBuf};
+
+/// IIO sysfs directory where ambient light sensors show up
+const IIO_DEVICES: &str = "/sys/bus/iio/devices";
+
+/// Illuminance taken as a fully lit room (lux); the level rises logarithmically up to it
+const FULL_LUX: f64 = 1000.0;
+
+/// The first IIO device with an illuminance channel
+pub fn find() -> Option<PathBuf> {
+    let mut devices: Vec<PathBuf> = std::fs::read_dir(IIO_DEVICES)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| ["in_illuminance_input", "in_illuminance_raw"].iter().any(|file| path.join(file).exists()))
+        .collect();
+    devices.sort();
+    devices.into_iter().next()
+}
+
+/// Ambient level [0.0..1.0] from the light sensor, `None` without a sensor or a reading
+pub fn read() -> Option<f64> {
+    let lux = lux(&find()?)?;
+    Some(((lux.max(0.0) + 1.0).log10() / (FULL_LUX + 1.0).log10()).clamp(0.0, 1.0))
+}
+
+/// Illuminance in lux: processed where the driver offers it, scaled from the raw count otherwise
+fn lux(device: &Path) -> Option<f64> {
+    let value = |file: &str| -> Option<f64> { std::fs::read_to_string(device.join(file)).ok()?.trim().parse().ok() };
+
+    if let Some(lux) = value("in_illuminance_input") {
+        return Some(lux);
+    }
+    let raw = value("in_illuminance_raw")?;
+    Some((raw + value("in_illuminance_offset").unwrap_or(0.0)) * value("in_illuminance_scale").unwrap_or(1.0))
+}
@@ -0,0 +1,71 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::notify::{self, Urgency};
+use crate::schedule::Period;
+use crate::state::State;
+
+/// `[announce]` config section: explain major changes with a notification
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AnnounceConfig {
+    /// When night mode starts
+    pub night: bool,
+    /// When a snooze or bedtime override runs out
+    pub overrides: bool,
+    /// When the webcam stops working
+    pub sensors: bool,
+}
+
+impl Default for AnnounceConfig {
+    fn default() -> Self {
+        AnnounceConfig { night: true, overrides: true, sensors: true }
+    }
+}
+
+/// Announces the start of night mode
+pub fn period_changed(config: &Config, new: Period, color_temp: f64) {
+    if new != Period::Night || !config.announce.as_ref().map_or(false, |a| a.night) {
+        return;
+    }
+
+    let body = format!("Colors warmed to {:.0}K for the evening", color_temp);
+    notify::send_with_category("Night mode", &body, Urgency::Low, "x-healthy-monitor.transition");
+}
+
+/// Clears overrides that have run out, announcing each
+pub fn expire_overrides(config: &Config, state: &mut State) {
+    let now = Utc::now().timestamp();
+    let enabled = config.announce.as_ref().map_or(false, |a| a.overrides);
+    let expired = |until: &mut Option<i64>, summary: &str, body: &str| {
+        if until.map_or(false, |until| now >= until) {
+            *until = None;
+            if enabled {
+                notify::send_with_category(summary, body, Urgency::Low, "x-healthy-monitor.override");
+            }
+        }
+    };
+
+    expired(&mut state.bedtime_override_until, "Bedtime override ended", "Bedtime enforcement is active again");
+    expired(&mut state.breaks_snoozed_until, "Snooze ended", "Break reminders are back on");
+    expired(&mut state.manual_until, "Manual override ended", "Brightness and color are automatic again");
+
+    if state.paused_until.map_or(false, |until| now >= until) {
+        state.paused = false;
+    }
+    expired(&mut state.paused_until, "Pause ended", "Automatic adjustments are back on");
+}
+
+/// Announces the webcam failing, once until it works again
+pub fn webcam_failed(config: &Config, state: &mut State, error: &str) {
+    if state.webcam_failed {
+        return;
+    }
+    state.webcam_failed = true;
+
+    if config.announce.as_ref().map_or(false, |a| a.sensors) {
+        let body = format!("Falling back to weather data ({})", error);
+        notify::send_with_category("Webcam unavailable", &body, Urgency::Normal, "device.error");
+    }
+}
@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::time::sleep;
+
+use crate::config::Config;
+use crate::ipc::Subscription;
+use crate::state::State;
+use crate::status::Status;
+
+/// One line of a Waybar custom module with `"return-type": "json"`
+#[derive(Debug, Serialize)]
+struct WaybarLine {
+    text: String,
+    tooltip: String,
+    /// CSS class: daytime, transition, night, darkroom or paused
+    class: &'static str,
+    /// Brightness in percent, for `format-icons`
+    percentage: u32,
+}
+
+/// Prints a Waybar JSON line now and after every change, or every `interval` when no
+/// daemon is running to announce changes
+pub async fn waybar(interval: Duration, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    watch(interval, || {
+        let state = State::load();
+        let status = Status::from_state(&state, config);
+        let line = WaybarLine {
+            text: format!("{} {}", percent(status.brightness), kelvin(status.color_temp)),
+            tooltip: tooltip(&status, &state),
+            class: class(&status, &state),
+            percentage: status.brightness.map_or(0, |b| (b * 100.0).round() as u32),
+        };
+        serde_json::to_string(&line).map_err(Into::into)
+    })
+    .await
+}
+
+/// Prints `format` with its placeholders filled in, now and after every change. Placeholders:
+/// `{brightness}`, `{kelvin}`, `{period}`, `{source}`, `{ambient}` and `{paused}`
+pub async fn line(format: &str, interval: Duration, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    watch(interval, || {
+        let state = State::load();
+        let status = Status::from_state(&state, config);
+        Ok(format
+            .replace("{brightness}", &percent(status.brightness))
+            .replace("{kelvin}", &kelvin(status.color_temp))
+            .replace("{period}", status.period.map_or("–", |p| p.name()))
+            .replace("{source}", state.source.map_or("–", |s| s.name()))
+            .replace("{ambient}", &percent(state.ambient_level))
+            .replace("{paused}", if state.paused { "paused" } else { "" })
+            .trim_end()
+            .to_string())
+    })
+    .await
+}
+
+/// Calls `line` and prints the result now and after every change
+async fn watch(
+    interval: Duration,
+    line: impl Fn() -> Result<String, Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut subscription = Subscription::connect().await;
+
+    loop {
+        println!("{}", line()?);
+
+        match subscription.as_mut() {
+            Some(changes) => {
+                tokio::select! {
+                    alive = changes.changed() => {
+                        if !alive {
+                            subscription = None;
+                        }
+                    }
+                    _ = sleep(interval) => {}
+                }
+            }
+            None => {
+                sleep(interval).await;
+                subscription = Subscription::connect().await;
+            }
+        }
+    }
+}
+
+fn class(status: &Status, state: &State) -> &'static str {
+    if state.paused {
+        "paused"
+    } else if status.darkroom {
+        "darkroom"
+    } else {
+        status.period.map_or("daytime", |p| p.name())
+    }
+}
+
+fn tooltip(status: &Status, state: &State) -> String {
+    let mut lines = vec![
+        format!("Period: {}", status.period.map_or("unknown", |p| p.name())),
+        format!("Brightness: {}", percent(status.brightness)),
+        format!("Temperature: {}", kelvin(status.color_temp)),
+    ];
+    if let Some(source) = state.source {
+        lines.push(format!("Source: {}", source.name()));
+    }
+    if state.paused {
+        lines.push("Paused".into());
+    }
+    lines.join("\n")
+}
+
+fn percent(value: Option<f64>) -> String {
+    value.map_or_else(|| "–".into(), |v| format!("{:.0}%", v * 100.0))
+}
+
+fn kelvin(value: Option<f64>) -> String {
+    value.map_or_else(|| "–".into(), |t| format!("{:.0}K", t))
+}
@@ -0,0 +1,158 @@
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use zbus::{proxy, Connection};
+
+use crate::notify::{self, Urgency};
+use crate::state::State;
+use crate::Args;
+
+/// `[battery]` config section overriding the brightness mapping while running on battery
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BatteryConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_brightness: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub night_min_brightness: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_brightness: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness_exponent: Option<f64>,
+
+    /// Battery percentage below which `low_max_brightness` caps the brightness until charging
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub low_percent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub low_max_brightness: Option<f64>,
+}
+
+/// Low-battery ceiling unless configured otherwise
+const DEFAULT_LOW_MAX_BRIGHTNESS: f64 = 0.5;
+
+/// Power source as reported by UPower
+#[derive(Debug, Clone, Copy)]
+pub struct Power {
+    pub on_battery: bool,
+    /// Charge of the combined batteries [0..100]
+    pub percentage: f64,
+}
+
+impl BatteryConfig {
+    /// Copy of `args` with the battery overrides applied
+    pub fn apply(&self, args: &Args) -> Args {
+        let mut args = args.clone();
+
+        if let Some(min_brightness) = self.min_brightness {
+            args.min_brightness = min_brightness;
+        }
+        if let Some(night_min_brightness) = self.night_min_brightness {
+            args.night_min_brightness = Some(night_min_brightness);
+        }
+        if let Some(max_brightness) = self.max_brightness {
+            args.max_brightness = max_brightness;
+        }
+        if let Some(exponent) = self.brightness_exponent {
+            args.brightness_exponent = exponent;
+        }
+
+        args
+    }
+}
+
+#[proxy(
+    interface = "org.freedesktop.UPower",
+    default_service = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower"
+)]
+trait UPower {
+    #[zbus(property)]
+    fn on_battery(&self) -> zbus::Result<bool>;
+}
+
+/// UPower's combination of all batteries
+#[proxy(
+    interface = "org.freedesktop.UPower.Device",
+    default_service = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower/devices/DisplayDevice"
+)]
+trait Device {
+    #[zbus(property)]
+    fn percentage(&self) -> zbus::Result<f64>;
+}
+
+/// The power source, `None` without UPower
+pub async fn power() -> Option<Power> {
+    let result: zbus::Result<Power> = async {
+        let connection = Connection::system().await?;
+        let on_battery = UPowerProxy::new(&connection).await?.on_battery().await?;
+        let percentage = DeviceProxy::new(&connection).await?.percentage().await?;
+        Ok(Power { on_battery, percentage })
+    }
+    .await;
+    result.ok()
+}
+
+/// Follows the power source in the background through UPower's property change signals,
+/// so plugging in or unplugging is acted on right away
+pub async fn watch() -> watch::Receiver<Option<Power>> {
+    let (sender, receiver) = watch::channel(power().await);
+
+    tokio::spawn(async move {
+        let result: zbus::Result<()> = async {
+            let connection = Connection::system().await?;
+            let upower = UPowerProxy::new(&connection).await?;
+            let device = DeviceProxy::new(&connection).await?;
+            let mut on_battery_changes = upower.receive_on_battery_changed().await;
+            let mut percentage_changes = device.receive_percentage_changed().await;
+
+            let mut power = Power { on_battery: upower.on_battery().await?, percentage: device.percentage().await? };
+            loop {
+                tokio::select! {
+                    Some(change) = on_battery_changes.next() => power.on_battery = change.get().await?,
+                    Some(change) = percentage_changes.next() => power.percentage = change.get().await?,
+                    else => return Ok(()),
+                }
+                sender.send_replace(Some(power));
+            }
+        }
+        .await;
+
+        if let Err(e) = result {
+            eprintln!("Power source unavailable: {}", e);
+        }
+    });
+
+    receiver
+}
+
+/// Whether `power` calls for the low-battery cap
+fn low(config: &BatteryConfig, power: Option<Power>) -> bool {
+    match (config.low_percent, power) {
+        (Some(threshold), Some(power)) => power.on_battery && power.percentage < threshold,
+        _ => false,
+    }
+}
+
+/// Which of the battery policies apply: on battery, and low
+pub fn policy(config: &BatteryConfig, power: Option<Power>) -> (bool, bool) {
+    (power.map_or(false, |p| p.on_battery), low(config, power))
+}
+
+/// Ceiling for the brightness while the battery is low and discharging, notifying once
+/// each time the cap kicks in. Only ever lowers the brightness, so the desktop's own
+/// dimming still has the last word.
+pub fn low_battery_cap(config: &BatteryConfig, power: Option<Power>, state: &mut State) -> Option<f64> {
+    if !low(config, power) {
+        state.low_battery_capped = false;
+        return None;
+    }
+
+    let cap = config.low_max_brightness.unwrap_or(DEFAULT_LOW_MAX_BRIGHTNESS);
+    if !state.low_battery_capped {
+        state.low_battery_capped = true;
+        let body = format!("Screen brightness is limited to {:.0}% until the laptop is charging", cap * 100.0);
+        notify::send("Low battery", &body, Urgency::Normal);
+    }
+    Some(cap)
+}
@@ -0,0 +1,161 @@
+use chrono::{Local, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::notify::{self, Urgency};
+use crate::push::{self, PushConfig};
+use crate::schedule::{self, TimeOfDay};
+use crate::state::State;
+
+/// Minutes before bedtime at which a reminder is shown, with its urgency
+const NOTICES: [(f64, Urgency); 3] = [(30.0, Urgency::Low), (15.0, Urgency::Normal), (5.0, Urgency::Critical)];
+
+/// `[bedtime]` config section; the bedtime itself is set in `[schedule]`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BedtimeConfig {
+    /// Minutes before bedtime over which to wind down
+    pub wind_down_minutes: f64,
+    /// Temperature reached at bedtime (Kelvin)
+    pub temp: f64,
+    /// Brightness reached at bedtime
+    pub brightness: f64,
+    /// Post reminders as bedtime approaches
+    pub notify: bool,
+    /// Keep dimming and warming past bedtime until use becomes uncomfortable
+    pub enforce: bool,
+    /// Minutes after bedtime over which enforcement reaches its final values
+    pub enforce_minutes: f64,
+    /// Brightness enforcement ends at
+    pub enforce_brightness: f64,
+    /// Temperature enforcement ends at (Kelvin)
+    pub enforce_temp: f64,
+}
+
+impl Default for BedtimeConfig {
+    fn default() -> Self {
+        BedtimeConfig {
+            wind_down_minutes: 60.0,
+            temp: 2700.0,
+            brightness: 0.3,
+            notify: false,
+            enforce: false,
+            enforce_minutes: 60.0,
+            enforce_brightness: 0.1,
+            enforce_temp: 1900.0,
+        }
+    }
+}
+
+/// How far the wind-down has progressed at `hour`: 0.0 before it starts,
+/// rising to 1.0 at bedtime and staying there until night ends
+pub fn progress(config: &BedtimeConfig, bedtime: f64, night_end: f64, hour: f64) -> f64 {
+    let wind_down_hours = config.wind_down_minutes / 60.0;
+    let until_bedtime = (bedtime - hour).rem_euclid(24.0);
+
+    if hours_past(bedtime, night_end, hour).is_some() {
+        1.0
+    } else if until_bedtime > 0.0 && until_bedtime <= wind_down_hours {
+        1.0 - until_bedtime / wind_down_hours
+    } else {
+        0.0
+    }
+}
+
+/// Pulls brightness and temperature toward the bedtime values, never raising them
+pub fn wind_down(config: &BedtimeConfig, progress: f64, brightness: f64, color_temp: f64) -> (f64, f64) {
+    let lerp = |from: f64, to: f64| from + (to - from) * progress;
+    (
+        lerp(brightness, brightness.min(config.brightness)),
+        lerp(color_temp, color_temp.min(config.temp)),
+    )
+}
+
+/// Hours since bedtime at `hour`, or `None` outside the night after bedtime
+fn hours_past(bedtime: f64, night_end: f64, hour: f64) -> Option<f64> {
+    let after_bedtime = if bedtime <= night_end {
+        hour >= bedtime && hour < night_end
+    } else {
+        hour >= bedtime || hour < night_end
+    };
+
+    after_bedtime.then(|| (hour - bedtime).rem_euclid(24.0))
+}
+
+/// Past bedtime, keeps pulling brightness and temperature down toward the enforcement values
+/// unless the user overrode enforcement for tonight
+pub fn enforce(
+    config: &BedtimeConfig,
+    state: &State,
+    bedtime: f64,
+    night_end: f64,
+    hour: f64,
+    brightness: f64,
+    color_temp: f64,
+) -> (f64, f64) {
+    let overridden = state.bedtime_override_until.map_or(false, |until| Utc::now().timestamp() < until);
+    let Some(hours) = hours_past(bedtime, night_end, hour).filter(|_| config.enforce && !overridden) else {
+        return (brightness, color_temp);
+    };
+
+    let progress = (hours * 60.0 / config.enforce_minutes.max(1.0)).min(1.0);
+    let lerp = |from: f64, to: f64| from + (to - from) * progress;
+    (
+        lerp(brightness, brightness.min(config.enforce_brightness)),
+        lerp(color_temp, color_temp.min(config.enforce_temp)),
+    )
+}
+
+/// Lifts enforcement until tonight's night ends, returning that time
+pub fn override_tonight(config: &Config, transition_hours: f64, state: &mut State) -> TimeOfDay {
+    let now = Local::now();
+    let night_end = config.schedule.times(now, transition_hours).night_end;
+    let hours_left = (night_end - schedule::hour_of(now)).rem_euclid(24.0);
+
+    state.bedtime_override_until = Some(now.timestamp() + (hours_left * 3600.0) as i64);
+    TimeOfDay(night_end)
+}
+
+/// Posts the next escalating reminder once its threshold is reached,
+/// pushing it to a phone too when configured
+pub async fn remind(
+    config: &BedtimeConfig,
+    push: Option<&PushConfig>,
+    state: &mut State,
+    bedtime: f64,
+    hour: f64,
+    progress: f64,
+) {
+    if !config.notify || progress == 0.0 {
+        state.bedtime_notice = None;
+        return;
+    }
+
+    if progress >= 1.0 {
+        if state.bedtime_notice != Some(0.0) {
+            notify::send("Bedtime", "Time to put the screen away", Urgency::Critical);
+            if let Some(push) = push {
+                push::send(push, "Bedtime", "Time to put the screen away").await;
+            }
+            state.bedtime_notice = Some(0.0);
+        }
+        return;
+    }
+
+    let minutes_left = (bedtime - hour).rem_euclid(24.0) * 60.0;
+    let due = NOTICES
+        .iter()
+        .filter(|(minutes, _)| minutes_left <= *minutes)
+        .last();
+
+    if let Some(&(minutes, urgency)) = due {
+        if state.bedtime_notice.map_or(true, |sent| sent > minutes) {
+            let body = format!("{:.0} minutes to bedtime", minutes_left.ceil());
+            notify::send("Bedtime is coming up", &body, urgency);
+            if let Some(push) = push {
+                push::send(push, "Bedtime is coming up", &body).await;
+            }
+            state.bedtime_notice = Some(minutes);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::time::{sleep, Instant};
+
+use crate::config::Config;
+use crate::notify::{self, Urgency};
+use crate::state::State;
+use crate::Args;
+
+/// How long a dim pulse lasts
+const PULSE: Duration = Duration::from_secs(1);
+
+/// `[blink]` config section enabling blink reminders during long focus sessions
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BlinkConfig {
+    /// Minutes of uninterrupted use before reminders start
+    pub after_minutes: f64,
+    /// Minutes between reminders once they've started
+    pub every_minutes: f64,
+    pub style: BlinkStyle,
+    /// Brightness factor applied during a pulse
+    pub pulse_dim: f64,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlinkStyle {
+    /// A low-urgency desktop notification
+    Notification,
+    /// A one-second slight dim of the screen
+    Pulse,
+}
+
+impl Default for BlinkConfig {
+    fn default() -> Self {
+        BlinkConfig {
+            after_minutes: 30.0,
+            every_minutes: 10.0,
+            style: BlinkStyle::Notification,
+            pulse_dim: 0.85,
+        }
+    }
+}
+
+/// Decides when the next blink reminder is due
+#[derive(Debug, Default)]
+pub struct BlinkReminder {
+    last: Option<Instant>,
+}
+
+impl BlinkReminder {
+    /// Whether a reminder is due given the current uninterrupted stretch (seconds). It stays due
+    /// until `delivered`, so one held back during quiet hours comes once they're over.
+    pub fn due(&mut self, config: &BlinkConfig, stretch_seconds: f64) -> bool {
+        if stretch_seconds < config.after_minutes * 60.0 {
+            self.last = None;
+            return false;
+        }
+
+        let every = Duration::from_secs_f64(config.every_minutes.max(0.0) * 60.0);
+        self.last.map_or(true, |last| last.elapsed() >= every)
+    }
+
+    /// Starts the wait for the next reminder
+    pub fn delivered(&mut self) {
+        self.last = Some(Instant::now());
+    }
+}
+
+/// Nudges the user to blink in the configured style
+pub async fn remind(blink: &BlinkConfig, args: &Args, config: &Config) {
+    match blink.style {
+        BlinkStyle::Notification => {
+            notify::send("Remember to blink", "Blink slowly a few times to rewet your eyes", Urgency::Low);
+        }
+        BlinkStyle::Pulse => {
+            let state = State::load();
+            let brightness = state.brightness.unwrap_or(1.0);
+            let color_temp = state.color_temp.unwrap_or(args.day_temp);
+
+            if let Err(e) = crate::set_monitor_brightness(brightness * blink.pulse_dim, color_temp, args, config) {
+                eprintln!("Failed to pulse brightness: {}", e);
+                return;
+            }
+            sleep(PULSE).await;
+            if let Err(e) = crate::set_monitor_brightness(brightness, color_temp, args, config) {
+                eprintln!("Failed to restore brightness: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use chrono::{DateTime, Datelike, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::schedule::{self, ScheduleConfig, TimeOfDay};
+
+/// Start of the boost when neither it nor a wake-up time is configured
+const DEFAULT_START: f64 = 7.0;
+/// Length of the boost when no end is configured (hours)
+const DEFAULT_HOURS: f64 = 2.0;
+
+/// `[morning_boost]` config section: bright, cool light on late-sunrise mornings
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BoostConfig {
+    /// Start of the boost [default: wake-up time from the schedule, or 07:00]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<TimeOfDay>,
+    /// End of the boost [default: two hours after the start]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<TimeOfDay>,
+    /// Months (1-12) in which the boost is active
+    pub months: Vec<u32>,
+    /// Temperature held during the boost (Kelvin)
+    pub temp: f64,
+}
+
+impl Default for BoostConfig {
+    fn default() -> Self {
+        BoostConfig {
+            start: None,
+            end: None,
+            months: vec![11, 12, 1, 2],
+            temp: 6500.0,
+        }
+    }
+}
+
+/// Whether `now` falls in the boost window in one of the configured months.
+/// Without explicit times the window follows the wake-up time of the schedule.
+pub fn active(config: &BoostConfig, schedule: &ScheduleConfig, now: DateTime<Local>) -> bool {
+    let start = config
+        .start
+        .or_else(|| schedule.lookup(now.weekday(), |d| d.wake))
+        .unwrap_or(TimeOfDay(DEFAULT_START));
+    let end = config.end.unwrap_or(TimeOfDay((start.0 + DEFAULT_HOURS).rem_euclid(24.0)));
+
+    config.months.contains(&now.month()) && TimeOfDay::window_contains(start, end, schedule::hour_of(now))
+}
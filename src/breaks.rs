@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::notify::{self, Urgency};
+use crate::push::{self, PushConfig};
+use crate::screentime;
+use crate::state::State;
+
+/// `[breaks]` config section enabling 20-20-20 reminders in daemon mode
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BreaksConfig {
+    /// Minutes of active screen time between reminders
+    pub interval_minutes: f64,
+    /// Length of the break (seconds)
+    pub break_seconds: f64,
+    /// Briefly dim the screen for the duration of the break
+    pub dim: bool,
+    /// Brightness used while dimmed
+    pub dim_brightness: f64,
+    /// Minutes away from the desk after which the count starts over
+    pub reset_after_minutes: f64,
+}
+
+impl Default for BreaksConfig {
+    fn default() -> Self {
+        BreaksConfig {
+            interval_minutes: 20.0,
+            break_seconds: 20.0,
+            dim: false,
+            dim_brightness: 0.3,
+            reset_after_minutes: 5.0,
+        }
+    }
+}
+
+impl BreaksConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs_f64(self.interval_minutes.max(0.0) * 60.0)
+    }
+
+    pub fn reset_after(&self) -> Duration {
+        Duration::from_secs_f64(self.reset_after_minutes.max(0.0) * 60.0)
+    }
+
+    pub fn break_length(&self) -> Duration {
+        Duration::from_secs_f64(self.break_seconds.max(0.0))
+    }
+}
+
+/// Accumulates active screen time until the next reminder is due
+#[derive(Debug, Default)]
+pub struct BreakTimer {
+    active: Duration,
+}
+
+impl BreakTimer {
+    /// Adds `elapsed` screen time while the user is active, given how long it's been since
+    /// their last input, and starts over once they've been away for `reset_after`.
+    /// Returns whether `interval` has been reached and the user is there to see a reminder.
+    pub fn tick(&mut self, interval: Duration, elapsed: Duration, idle_for: Duration, reset_after: Duration) -> bool {
+        if idle_for >= reset_after {
+            self.reset();
+            return false;
+        }
+
+        let active = idle_for.as_secs_f64() < screentime::ACTIVE_TIMEOUT_SECONDS;
+        if active {
+            self.active += elapsed;
+        }
+        active && self.active >= interval
+    }
+
+    pub fn reset(&mut self) {
+        self.active = Duration::ZERO;
+    }
+}
+
+/// Whether reminders are snoozed at the moment
+pub fn snoozed(state: &State) -> bool {
+    state.breaks_snoozed_until.map_or(false, |until| Utc::now().timestamp() < until)
+}
+
+/// Posts the 20-20-20 reminder, pushing it to a phone too when configured
+pub async fn remind(config: &BreaksConfig, push: Option<&PushConfig>) {
+    let body = format!(
+        "Look at something 20 feet (6 m) away for {:.0} seconds",
+        config.break_seconds
+    );
+    notify::send("Time for an eye break", &body, Urgency::Normal);
+
+    if let Some(push) = push {
+        push::send(push, "Time for an eye break", &body).await;
+    }
+}
+
+/// Holds off reminders for `duration`
+pub fn snooze(duration: Duration) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = State::load();
+    state.breaks_snoozed_until = Some(Utc::now().timestamp() + duration.as_secs() as i64);
+    state.save()?;
+
+    println!("Break reminders snoozed for {} minutes", duration.as_secs() / 60);
+    Ok(())
+}
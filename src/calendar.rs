@@ -0,0 +1,391 @@
+use std::collections::HashSet;
+
+use chrono::{Datelike, Days, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::state::State;
+
+/// How far ahead busy periods are worked out on each refresh (seconds)
+const LOOKAHEAD: i64 = 24 * 60 * 60;
+/// Upper bound on the repeats walked through for one recurring event
+const MAX_REPEATS: usize = 20_000;
+
+/// `[calendar]` config section: hold the settings and defer breaks during busy events
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CalendarConfig {
+    /// Path of an ICS file, or an http(s)/webcal URL serving one (e.g. a CalDAV calendar export)
+    pub source: String,
+    /// User name for HTTP basic authentication
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Minutes between reloads of the calendar
+    pub refresh_minutes: f64,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        CalendarConfig { source: String::new(), username: None, password: None, refresh_minutes: 15.0 }
+    }
+}
+
+/// Whether a busy event is going on, reloading the calendar when the last load is too old.
+/// A calendar that can't be loaded leaves the previously known events in place.
+pub async fn busy(config: &CalendarConfig, state: &mut State) -> bool {
+    let now = Utc::now().timestamp();
+    let stale = state
+        .calendar_checked_at
+        .map_or(true, |at| (now - at) as f64 >= config.refresh_minutes * 60.0);
+
+    if stale {
+        match load(config).await {
+            Ok(ics) => {
+                state.calendar_busy = busy_periods(&ics, now, now + LOOKAHEAD);
+                state.calendar_checked_at = Some(now);
+            }
+            Err(e) => eprintln!("Failed to load the calendar: {}", e),
+        }
+    }
+
+    in_event(state)
+}
+
+/// Whether a busy event known from the last calendar load is going on
+pub fn in_event(state: &State) -> bool {
+    let now = Utc::now().timestamp();
+    state.calendar_busy.iter().any(|&[start, end]| start <= now && now < end)
+}
+
+async fn load(config: &CalendarConfig) -> Result<String, Box<dyn std::error::Error>> {
+    let url = match config.source.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => config.source.clone(),
+    };
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Ok(std::fs::read_to_string(&config.source)?);
+    }
+
+    let mut request = reqwest::Client::new().get(&url);
+    if let Some(username) = &config.username {
+        request = request.basic_auth(username, config.password.as_ref());
+    }
+    Ok(request.send().await?.error_for_status()?.text().await?)
+}
+
+/// A busy VEVENT, with its start in local time so repeats keep their wall-clock time across DST
+struct Event {
+    start: NaiveDateTime,
+    /// Length (seconds)
+    duration: i64,
+    rule: Option<Rule>,
+    excluded: HashSet<NaiveDateTime>,
+}
+
+/// The supported part of an RRULE: daily or weekly repeats
+struct Rule {
+    weekly: bool,
+    interval: u64,
+    count: Option<usize>,
+    until: Option<NaiveDateTime>,
+    by_day: Vec<Weekday>,
+}
+
+/// Start and end of every busy event overlapping `from..to`, as timestamps
+fn busy_periods(ics: &str, from: i64, to: i64) -> Vec<[i64; 2]> {
+    events(ics).iter().flat_map(|event| occurrences(event, from, to)).collect()
+}
+
+/// Busy events of a calendar. All-day, free (`TRANSP:TRANSPARENT`) and cancelled events are left out.
+/// Times with a `TZID` are taken as local time.
+fn events(ics: &str) -> Vec<Event> {
+    // Long lines are folded onto continuation lines starting with a space or tab
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut events = Vec::new();
+    let mut current: Option<Vec<(String, String, String)>> = None;
+    for line in lines {
+        match line.as_str() {
+            "BEGIN:VEVENT" => current = Some(Vec::new()),
+            "END:VEVENT" => {
+                if let Some(event) = current.take().and_then(|properties| event(&properties)) {
+                    events.push(event);
+                }
+            }
+            _ => {
+                let (Some(properties), Some((key, value))) = (current.as_mut(), line.split_once(':')) else {
+                    continue;
+                };
+                let (name, params) = key.split_once(';').unwrap_or((key, ""));
+                properties.push((name.to_ascii_uppercase(), params.to_ascii_uppercase(), value.trim().to_string()));
+            }
+        }
+    }
+    events
+}
+
+fn event(properties: &[(String, String, String)]) -> Option<Event> {
+    let get = |name: &str| properties.iter().find(|(n, _, _)| n == name);
+
+    if get("TRANSP").map_or(false, |(_, _, v)| v.eq_ignore_ascii_case("TRANSPARENT"))
+        || get("STATUS").map_or(false, |(_, _, v)| v.eq_ignore_ascii_case("CANCELLED"))
+    {
+        return None;
+    }
+
+    let start = get("DTSTART").and_then(|(_, params, value)| time(params, value))?;
+    let duration = match (get("DTEND"), get("DURATION")) {
+        (Some((_, params, value)), _) => (time(params, value)? - start).num_seconds(),
+        (None, Some((_, _, value))) => duration(value)?,
+        (None, None) => 0,
+    };
+
+    let excluded = properties
+        .iter()
+        .filter(|(name, _, _)| name == "EXDATE")
+        .flat_map(|(_, params, value)| value.split(',').filter_map(|v| time(params, v)))
+        .collect();
+
+    Some(Event { start, duration, rule: get("RRULE").and_then(|(_, _, value)| rule(value)), excluded })
+}
+
+/// A DATE-TIME value in local time; `None` for dates, which mark all-day events
+fn time(params: &str, value: &str) -> Option<NaiveDateTime> {
+    if params.contains("VALUE=DATE") && !params.contains("VALUE=DATE-TIME") {
+        return None;
+    }
+    match value.strip_suffix('Z') {
+        Some(utc) => {
+            let utc = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+            Some(Utc.from_utc_datetime(&utc).with_timezone(&Local).naive_local())
+        }
+        None => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok(),
+    }
+}
+
+/// A DURATION value such as `PT1H30M` in seconds
+fn duration(value: &str) -> Option<i64> {
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+
+    let mut seconds = 0;
+    let mut number = 0;
+    for c in value.strip_prefix('P')?.chars() {
+        match c {
+            '0'..='9' => number = number * 10 + c.to_digit(10)? as i64,
+            'T' => {}
+            'W' => seconds += number * 7 * 86400,
+            'D' => seconds += number * 86400,
+            'H' => seconds += number * 3600,
+            'M' => seconds += number * 60,
+            'S' => seconds += number,
+            _ => return None,
+        }
+        if !c.is_ascii_digit() {
+            number = 0;
+        }
+    }
+    Some(sign * seconds)
+}
+
+/// Daily and weekly rules; other frequencies aren't expanded, so only the first occurrence counts
+fn rule(value: &str) -> Option<Rule> {
+    let parts: Vec<(&str, &str)> = value.split(';').filter_map(|part| part.split_once('=')).collect();
+    let get = |name: &str| parts.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| *v);
+
+    let weekly = match get("FREQ")? {
+        "DAILY" => false,
+        "WEEKLY" => true,
+        _ => return None,
+    };
+    let until = get("UNTIL").and_then(|until| match NaiveDate::parse_from_str(until, "%Y%m%d") {
+        Ok(date) => date.and_hms_opt(23, 59, 59),
+        Err(_) => time("", until),
+    });
+    let by_day = get("BYDAY")
+        .map(|days| days.split(',').filter_map(weekday).collect())
+        .unwrap_or_default();
+
+    Some(Rule {
+        weekly,
+        interval: get("INTERVAL").and_then(|i| i.parse().ok()).unwrap_or(1).max(1),
+        count: get("COUNT").and_then(|c| c.parse().ok()),
+        until,
+        by_day,
+    })
+}
+
+/// A BYDAY entry such as `MO`; ordinals like `1MO` only apply to monthly rules
+fn weekday(day: &str) -> Option<Weekday> {
+    match day.trim() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn occurrences(event: &Event, from: i64, to: i64) -> Vec<[i64; 2]> {
+    let timestamp = |at: NaiveDateTime| Local.from_local_datetime(&at).earliest().map(|t| t.timestamp());
+    let overlapping = |start: i64| (start < to && start + event.duration > from).then_some([start, start + event.duration]);
+
+    let Some(rule) = &event.rule else {
+        return timestamp(event.start).and_then(overlapping).into_iter().collect();
+    };
+
+    // Weekly rules with BYDAY repeat on those days of each week, counted from Monday
+    let date = event.start.date();
+    let (base, mut offsets) = if rule.weekly && !rule.by_day.is_empty() {
+        let monday = date - Days::new(date.weekday().num_days_from_monday() as u64);
+        (monday, rule.by_day.iter().map(|day| day.num_days_from_monday() as u64).collect())
+    } else {
+        (date, vec![0])
+    };
+    offsets.sort_unstable();
+    offsets.dedup();
+    let step = rule.interval * if rule.weekly { 7 } else { 1 };
+
+    let mut found = Vec::new();
+    let mut seen = 0;
+    for period in 0..MAX_REPEATS as u64 {
+        for offset in &offsets {
+            let at = (base + Days::new(period * step + offset)).and_time(event.start.time());
+            if at < event.start {
+                continue;
+            }
+            if rule.until.map_or(false, |until| at > until) || rule.count.map_or(false, |count| seen >= count) {
+                return found;
+            }
+            seen += 1;
+
+            let Some(start) = timestamp(at) else { continue };
+            if start >= to {
+                return found;
+            }
+            if !event.excluded.contains(&at) {
+                found.extend(overlapping(start));
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").unwrap()
+    }
+
+    /// Busy periods of a calendar with one event made of `properties`, as local start times and lengths
+    fn busy(properties: &str) -> Vec<(NaiveDateTime, i64)> {
+        let ics = format!("BEGIN:VCALENDAR\nBEGIN:VEVENT\n{}\nEND:VEVENT\nEND:VCALENDAR\n", properties);
+        let timestamp = |value: &str| Local.from_local_datetime(&at(value)).earliest().unwrap().timestamp();
+        busy_periods(&ics, timestamp("20260101T000000"), timestamp("20270101T000000"))
+            .into_iter()
+            .map(|[start, end]| (Local.timestamp_opt(start, 0).unwrap().naive_local(), end - start))
+            .collect()
+    }
+
+    fn starts(properties: &str) -> Vec<NaiveDateTime> {
+        busy(properties).into_iter().map(|(start, _)| start).collect()
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(duration("PT1H30M"), Some(5400));
+        assert_eq!(duration("P1DT2H"), Some(93600));
+        assert_eq!(duration("P1W"), Some(604800));
+        assert_eq!(duration("+PT45S"), Some(45));
+        assert_eq!(duration("-PT15M"), Some(-900));
+        assert_eq!(duration("1H"), None);
+        assert_eq!(duration("PT1X"), None);
+    }
+
+    #[test]
+    fn rules() {
+        let weekly = rule("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE,1FR;COUNT=4").unwrap();
+        assert!(weekly.weekly);
+        assert_eq!(weekly.interval, 2);
+        assert_eq!(weekly.count, Some(4));
+        assert_eq!(weekly.by_day, [Weekday::Mon, Weekday::Wed]);
+
+        let daily = rule("FREQ=DAILY;INTERVAL=0;UNTIL=20260310").unwrap();
+        assert!(!daily.weekly);
+        assert_eq!(daily.interval, 1);
+        assert_eq!(daily.until, Some(at("20260310T235959")));
+        assert_eq!(rule("FREQ=DAILY;UNTIL=20260310T120000").unwrap().until, Some(at("20260310T120000")));
+
+        assert!(rule("FREQ=MONTHLY;BYDAY=1MO").is_none());
+        assert!(rule("INTERVAL=2").is_none());
+    }
+
+    #[test]
+    fn single_event() {
+        let events = busy("DTSTART:20260302T090000\nDTEND:20260302T100000");
+        assert_eq!(events, [(at("20260302T090000"), 3600)]);
+        assert_eq!(busy("DTSTART:20260302T090000\nDURATION:PT30M"), [(at("20260302T090000"), 1800)]);
+    }
+
+    #[test]
+    fn weekly_by_day_with_count() {
+        // Wednesday the 4th, then the Monday and Wednesday of the following week
+        let starts = starts("DTSTART:20260304T090000\nDTEND:20260304T093000\nRRULE:FREQ=WEEKLY;BYDAY=MO,WE;COUNT=3");
+        assert_eq!(starts, [at("20260304T090000"), at("20260309T090000"), at("20260311T090000")]);
+    }
+
+    #[test]
+    fn every_other_week() {
+        let starts = starts("DTSTART:20260302T090000\nDTEND:20260302T093000\nRRULE:FREQ=WEEKLY;INTERVAL=2;COUNT=3");
+        assert_eq!(starts, [at("20260302T090000"), at("20260316T090000"), at("20260330T090000")]);
+    }
+
+    #[test]
+    fn daily_until_a_date() {
+        let starts = starts("DTSTART:20260302T090000\nDTEND:20260302T093000\nRRULE:FREQ=DAILY;UNTIL=20260304");
+        assert_eq!(starts, [at("20260302T090000"), at("20260303T090000"), at("20260304T090000")]);
+    }
+
+    #[test]
+    fn excluded_dates_still_count() {
+        let starts = starts(
+            "DTSTART;TZID=Europe/Prague:20260302T090000\nDTEND;TZID=Europe/Prague:20260302T093000\n\
+             RRULE:FREQ=DAILY;COUNT=3\nEXDATE;TZID=Europe/Prague:20260303T090000",
+        );
+        assert_eq!(starts, [at("20260302T090000"), at("20260304T090000")]);
+    }
+
+    #[test]
+    fn repeats_keep_their_wall_clock_time_across_dst() {
+        // Spans the March changes in both Europe and North America
+        let events = busy("DTSTART:20260301T090000\nDTEND:20260301T100000\nRRULE:FREQ=DAILY;COUNT=40");
+        assert_eq!(events.len(), 40);
+        for (start, length) in events {
+            assert_eq!(start.time(), at("20260301T090000").time());
+            assert_eq!(length, 3600);
+        }
+    }
+
+    #[test]
+    fn skipped_events() {
+        let ics = "BEGIN:VEVENT\nDTSTART;VALUE=DATE:20260302\nEND:VEVENT\n\
+                   BEGIN:VEVENT\nDTSTART:20260302T090000\nTRANSP:TRANSPARENT\nEND:VEVENT\n\
+                   BEGIN:VEVENT\nDTSTART:20260302T090000\nSTATUS:CANCELLED\nEND:VEVENT\n\
+                   BEGIN:VEVENT\nDTSTART:20260302T090000\nDESCRIPTION:a long\n  folded line\nEND:VEVENT\n";
+        assert_eq!(events(ics).len(), 1);
+    }
+}
@@ -0,0 +1,148 @@
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use crate::config::Config;
+use crate::{webcam, Args};
+
+/// Brightness at an ambient level interpolated between calibrated `[ambient, brightness]` anchors.
+/// Below the first anchor its brightness holds; above the last, brightness rises linearly
+/// to full at an ambient level of 1.0.
+pub fn anchored(anchors: &[[f64; 2]], ambient: f64) -> f64 {
+    let mut points = anchors.to_vec();
+    points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    if let Some(&[last_ambient, last_brightness]) = points.last() {
+        if last_ambient < 1.0 {
+            points.push([1.0, last_brightness.max(1.0)]);
+        }
+    }
+
+    let Some(first) = points.first() else {
+        return 1.0;
+    };
+    if ambient <= first[0] {
+        return first[1];
+    }
+
+    points
+        .windows(2)
+        .find(|pair| ambient <= pair[1][0])
+        .map(|pair| {
+            let [(x0, y0), (x1, y1)] = [(pair[0][0], pair[0][1]), (pair[1][0], pair[1][1])];
+            if x1 - x0 < 1e-9 { y1 } else { y0 + (y1 - y0) * (ambient - x0) / (x1 - x0) }
+        })
+        .unwrap_or(points[points.len() - 1][1])
+}
+
+/// Walks through dark and room-light readings with a preferred brightness for each,
+/// then writes the resulting anchors into the config at `config_path`
+pub async fn wizard(args: &Args, config: &Config, config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut prompt = |text: &str| -> Result<String, Box<dyn std::error::Error>> {
+        print!("{}", text);
+        std::io::stdout().flush()?;
+        Ok(lines.next().ok_or("Calibration cancelled")??.trim().to_string())
+    };
+
+    println!("Step 1/4: dark reference");
+    prompt("Cover the webcam completely, then press Enter ")?;
+    let dark = webcam::capture(config.capture_resolution.unwrap_or_default(), false).await?.ambient;
+    println!("  Dark reading: {:.3}", dark);
+
+    println!("Step 2/4: brightness for a dark room");
+    let dark_brightness = choose_brightness(&mut prompt, 0.4, args, config, "Dim the room lights as at night.")?;
+
+    println!("Step 3/4: room reference");
+    prompt("Uncover the webcam with your usual room lighting, then press Enter ")?;
+    let room = webcam::capture(config.capture_resolution.unwrap_or_default(), false).await?.ambient;
+    println!("  Room reading: {:.3}", room);
+
+    if room <= dark {
+        return Err("The room reading isn't brighter than the dark reference; check the webcam and try again".into());
+    }
+
+    println!("Step 4/4: brightness for normal room light");
+    let room_brightness = choose_brightness(&mut prompt, 0.8, args, config, "Switch the room lights back on.")?;
+
+    let mut updated = Config::load_for_update(config_path)?;
+    updated.curve_anchors = vec![[dark, dark_brightness], [room, room_brightness]];
+    updated.save(config_path)?;
+    println!("Wrote curve anchors to {}", config_path.display());
+
+    crate::run(args, config).await
+}
+
+/// Applies brightness values typed by the user until one is accepted with an empty line
+fn choose_brightness(
+    prompt: &mut impl FnMut(&str) -> Result<String, Box<dyn std::error::Error>>,
+    start: f64,
+    args: &Args,
+    config: &Config,
+    hint: &str,
+) -> Result<f64, Box<dyn std::error::Error>> {
+    println!("  {} Type a brightness (0.0 to 1.0) to try it, Enter to keep the current one.", hint);
+
+    let color_temp = args.day_temp;
+    let mut brightness = start;
+    loop {
+        crate::set_monitor_brightness(brightness, color_temp, args, config)?;
+        let answer = prompt(&format!("  Brightness [{:.2}]: ", brightness))?;
+        if answer.is_empty() {
+            return Ok(brightness);
+        }
+
+        match answer.parse::<f64>() {
+            Ok(value) if (0.0..=1.0).contains(&value) => brightness = value,
+            _ => println!("  Enter a number between 0.0 and 1.0"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn no_anchors() {
+        assert_eq!(anchored(&[], 0.0), 1.0);
+        assert_eq!(anchored(&[], 0.5), 1.0);
+    }
+
+    #[test]
+    fn single_anchor() {
+        let anchors = [[0.3, 0.4]];
+        assert!(close(anchored(&anchors, 0.0), 0.4));
+        assert!(close(anchored(&anchors, 0.3), 0.4));
+        assert!(close(anchored(&anchors, 0.65), 0.7));
+        assert!(close(anchored(&anchors, 1.0), 1.0));
+        assert!(close(anchored(&anchors, 1.5), 1.0));
+    }
+
+    #[test]
+    fn unsorted_anchors() {
+        let anchors = [[0.5, 0.6], [0.1, 0.2]];
+        assert!(close(anchored(&anchors, 0.0), 0.2));
+        assert!(close(anchored(&anchors, 0.3), 0.4));
+        assert!(close(anchored(&anchors, 0.75), 0.8));
+    }
+
+    #[test]
+    fn duplicate_anchors() {
+        let anchors = [[0.2, 0.3], [0.6, 0.7], [0.2, 0.5]];
+        assert!(close(anchored(&anchors, 0.2), 0.3));
+        assert!(close(anchored(&anchors, 0.4), 0.6));
+        assert!(close(anchored(&anchors, 0.6), 0.7));
+    }
+
+    #[test]
+    fn anchor_at_full_light() {
+        let anchors = [[0.0, 0.2], [1.0, 0.8]];
+        assert!(close(anchored(&anchors, 0.5), 0.5));
+        assert!(close(anchored(&anchors, 1.0), 0.8));
+        assert!(close(anchored(&anchors, 1.2), 0.8));
+    }
+}
@@ -0,0 +1,124 @@
+use std::path::Path;
+
+use chrono::{Duration, Local};
+
+use crate::config::Config;
+use crate::schedule::TimeOfDay;
+use crate::screentime::{self, DayStats, ScreenTime};
+
+/// How far back activity history is considered
+const HISTORY_DAYS: i64 = 28;
+/// Fewer recorded days than this are too little to go on
+const MIN_DAYS: usize = 7;
+/// Less morning bright light than this on average is worth pointing out (seconds)
+const LOW_MORNING_LIGHT: f64 = 20.0 * 60.0;
+
+/// Sleep timing inferred from recorded activity
+#[derive(Debug)]
+pub struct Estimate {
+    pub days: usize,
+    /// Typical end of screen use, past 24 after midnight
+    pub sleep_onset: f64,
+    /// Typical start of screen use
+    pub wake: f64,
+    /// Average bright light before noon per day (seconds)
+    pub morning_light: f64,
+}
+
+impl Estimate {
+    /// Middle of the sleep window, a common chronotype marker
+    pub fn mid_sleep(&self) -> f64 {
+        (self.sleep_onset + self.wake + 24.0) / 2.0
+    }
+
+    pub fn chronotype(&self) -> &'static str {
+        match self.mid_sleep() - 24.0 {
+            h if h < 3.0 => "early",
+            h if h > 5.0 => "late",
+            _ => "intermediate",
+        }
+    }
+}
+
+/// Estimates sleep timing from the daemon's screen time history
+pub fn estimate() -> Result<Estimate, Box<dyn std::error::Error>> {
+    let history = ScreenTime::load();
+    let since = Local::now().date_naive() - Duration::days(HISTORY_DAYS);
+
+    let days: Vec<&DayStats> = history
+        .days
+        .range(since..)
+        .map(|(_, stats)| stats)
+        .filter(|stats| stats.first_active_hour.is_some() && stats.last_active_hour.is_some())
+        .collect();
+
+    if days.len() < MIN_DAYS {
+        return Err(format!(
+            "Only {} day(s) of activity recorded, at least {} are needed; keep the daemon running",
+            days.len(),
+            MIN_DAYS
+        )
+        .into());
+    }
+
+    Ok(Estimate {
+        days: days.len(),
+        sleep_onset: median(days.iter().filter_map(|d| d.last_active_hour).collect()),
+        wake: median(days.iter().filter_map(|d| d.first_active_hour).collect()),
+        morning_light: days.iter().map(|d| d.morning_bright_seconds).sum::<f64>() / days.len() as f64,
+    })
+}
+
+/// Prints the estimate with a schedule fitted to it, writing that schedule
+/// into the config at `config_path` when `apply` is set
+pub fn suggest(apply: bool, config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let estimate = estimate()?;
+
+    let wake = clock(estimate.wake);
+    let bedtime = clock(estimate.sleep_onset);
+
+    println!("Based on {} days of activity:", estimate.days);
+    println!("  Usual last screen use:  {}", clock(estimate.sleep_onset));
+    println!("  Usual first screen use: {}", clock(estimate.wake));
+    println!("  Mid-sleep:              {} ({} chronotype)", clock(estimate.mid_sleep()), estimate.chronotype());
+    println!("  Morning bright light:   {}", screentime::format_duration(estimate.morning_light));
+    println!();
+    println!("Suggested schedule:");
+    println!("  [schedule]");
+    println!("  wake = \"{}\"", wake);
+    println!("  bedtime = \"{}\"", bedtime);
+
+    if estimate.morning_light < LOW_MORNING_LIGHT {
+        println!();
+        println!("Little bright light in the mornings; daylight soon after waking helps an earlier, steadier rhythm.");
+    }
+
+    if apply {
+        let mut config = Config::load_for_update(config_path)?;
+        // Night boundaries follow the sleep schedule once it's set
+        config.schedule.night_start = None;
+        config.schedule.night_end = None;
+        config.schedule.wake = Some(wake);
+        config.schedule.bedtime = Some(bedtime);
+        config.save(config_path)?;
+        println!();
+        println!("Updated {}", config_path.display());
+    }
+
+    Ok(())
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+/// Wraps fractional hours onto the clock, rounded to a quarter hour
+fn clock(hours: f64) -> TimeOfDay {
+    TimeOfDay(((hours * 4.0).round() / 4.0).rem_euclid(24.0))
+}
@@ -0,0 +1,117 @@
+use std::path::Path;
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::config::Config;
+use crate::Args;
+
+/// Rounds spent narrowing down each setting
+const ROUNDS: usize = 4;
+/// Temperature search range (Kelvin)
+const TEMP_RANGE: (f64, f64) = (1900.0, 5500.0);
+/// Brightness search range
+const BRIGHTNESS_RANGE: (f64, f64) = (0.2, 0.9);
+
+/// Which setting a round varies
+#[derive(Debug, Clone, Copy)]
+enum Setting {
+    Temp,
+    Brightness,
+}
+
+/// Narrows down a preferred night temperature and brightness by showing two candidates
+/// in turn and asking which is better, halving the gap each round. The result is saved as
+/// the night values of profile `profile` in the config at `config_path`.
+pub async fn run(
+    profile: &str,
+    every: Duration,
+    args: &Args,
+    config: &Config,
+    config_path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut temp = (TEMP_RANGE.0 + TEMP_RANGE.1) / 2.0;
+    let mut brightness = (BRIGHTNESS_RANGE.0 + BRIGHTNESS_RANGE.1) / 2.0;
+    let mut input = BufReader::new(tokio::io::stdin()).lines();
+
+    println!("Two candidates alternate every {}s. Type a or b and press Enter to pick.", every.as_secs());
+
+    for setting in [Setting::Temp, Setting::Brightness] {
+        let (low, high) = match setting {
+            Setting::Temp => TEMP_RANGE,
+            Setting::Brightness => BRIGHTNESS_RANGE,
+        };
+        let mut gap = (high - low) / 4.0;
+
+        for round in 1..=ROUNDS {
+            let value = match setting {
+                Setting::Temp => temp,
+                Setting::Brightness => brightness,
+            };
+            let candidates = [(value - gap).max(low), (value + gap).min(high)];
+            let pair = |v: f64| match setting {
+                Setting::Temp => (brightness, v),
+                Setting::Brightness => (v, temp),
+            };
+
+            println!(
+                "{} round {}/{}: A = {}, B = {}",
+                match setting {
+                    Setting::Temp => "Temperature",
+                    Setting::Brightness => "Brightness",
+                },
+                round,
+                ROUNDS,
+                describe(pair(candidates[0])),
+                describe(pair(candidates[1])),
+            );
+
+            let chosen = loop {
+                let mut showing = 0;
+                let mut ticker = tokio::time::interval(every);
+                let answer = loop {
+                    tokio::select! {
+                        _ = ticker.tick() => {
+                            let (b, t) = pair(candidates[showing]);
+                            crate::set_monitor_brightness(b, t, args, config)?;
+                            println!("  showing {}", if showing == 0 { "A" } else { "B" });
+                            showing = 1 - showing;
+                        }
+                        line = input.next_line() => break line?.ok_or("Comparison cancelled")?,
+                    }
+                };
+
+                match answer.trim().to_lowercase().as_str() {
+                    "a" => break candidates[0],
+                    "b" => break candidates[1],
+                    _ => println!("  Type a or b"),
+                }
+            };
+
+            match setting {
+                Setting::Temp => temp = chosen,
+                Setting::Brightness => brightness = chosen,
+            }
+            gap /= 2.0;
+        }
+    }
+
+    let mut updated = Config::load_for_update(config_path)?;
+    let entry = updated.profile.entry(profile.to_string()).or_default();
+    entry.night_temp = Some(temp.round());
+    entry.night_min_brightness = Some((brightness * 100.0).round() / 100.0);
+    updated.save(config_path)?;
+
+    println!(
+        "Preferred night settings: {}, saved to [profile.{}] in {}",
+        describe((brightness, temp)),
+        profile,
+        config_path.display()
+    );
+
+    crate::run(args, config).await
+}
+
+fn describe((brightness, temp): (f64, f64)) -> String {
+    format!("{:.0}K at brightness {:.2}", temp, brightness)
+}
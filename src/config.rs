@@ -0,0 +1,438 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use toml_edit::{DocumentMut, Item, Table, Value};
+
+use crate::activitywatch::ActivityWatchConfig;
+use crate::announce::AnnounceConfig;
+use crate::battery::BatteryConfig;
+use crate::bedtime::BedtimeConfig;
+use crate::blink::BlinkConfig;
+use crate::boost::BoostConfig;
+use crate::breaks::BreaksConfig;
+use crate::calendar::CalendarConfig;
+use crate::content::ContentConfig;
+use crate::contrast::ContrastConfig;
+use crate::daylight::DaylightConfig;
+use crate::exercises::ExercisesConfig;
+use crate::goals::GoalsConfig;
+use crate::grayscale::GrayscaleConfig;
+use crate::hooks::EventHookConfig;
+use crate::hotkeys::HotkeysConfig;
+use crate::hue::{HueConfig, LegacyHueConfig};
+use crate::http::HttpConfig;
+use crate::midi::MidiConfig;
+use crate::mqtt::MqttConfig;
+use crate::notify::QuietConfig;
+use crate::pomodoro::PomodoroConfig;
+use crate::posture::PostureConfig;
+use crate::powerprofiles::PowerSaverConfig;
+use crate::profile::ProfileConfig;
+use crate::push::PushConfig;
+use crate::reminders::ReminderConfig;
+use crate::roomlight::RoomLightsConfig;
+use crate::schedule::ScheduleConfig;
+use crate::stretch::StretchConfig;
+use crate::sunrise::SunriseConfig;
+use crate::telemetry::TelemetryConfig;
+use crate::webcam::Resolution;
+use crate::weekly::WeeklyConfig;
+
+/// Settings read from the TOML config file
+///
+/// Top-level values act as defaults for the matching command line options,
+/// which always take precedence when given explicitly.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_brightness: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub night_min_brightness: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness_exponent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_brightness: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub day_temp: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub night_temp: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transition_hours: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_step: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitors: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_only: Option<bool>,
+
+    /// `[ambient, brightness]` pairs written by `calibrate`, replacing the
+    /// min_brightness/brightness_exponent mapping when set
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub curve_anchors: Vec<[f64; 2]>,
+
+    /// Seconds over which each change is faded in [default: 0, instant]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fade_seconds: Option<f64>,
+
+    /// Webcam frame size, e.g. "160x120", or "smallest" [default: 640x480]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capture_resolution: Option<Resolution>,
+
+    /// Bits per channel of the panel, enabling temporal dithering of fades (e.g. 6)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub panel_bits: Option<u32>,
+
+    /// Illuminance at the eye (lux) from a neutral white screen at full brightness,
+    /// used for the melanopic estimate [default: 100]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub screen_lux: Option<f64>,
+
+    /// Ambient level from which time counts as bright-light exposure in the report [default: 0.6]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bright_ambient: Option<f64>,
+
+    /// Days of update cycles and daily screen time kept in the history database, 0 to disable [default: 30]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history_days: Option<u32>,
+
+    /// Minutes brightness or temperature set by hand are held before automatic control resumes [default: 60]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manual_hold_minutes: Option<f64>,
+
+    /// Brightness used in darkroom mode [default: 0.3]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub darkroom_brightness: Option<f64>,
+
+    /// Window classes that get neutral colors and full brightness while focused
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exceptions: Vec<String>,
+
+    /// Hold settings steady while a fullscreen window is focused or media is playing
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub pause_during_playback: bool,
+
+    /// Drop to the minimum brightness and stop reading the webcam while the session is locked (daemon mode)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dim_on_lock: bool,
+
+    /// Hold settings steady and leave the webcam off while Feral GameMode reports a running game
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub pause_during_games: bool,
+
+    /// Apply neutral gamma while the screen is being shared or recorded
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub neutral_while_sharing: bool,
+
+    /// Process names that count as screen sharing when running (e.g. "obs")
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sharing_processes: Vec<String>,
+
+    /// Pause sensing and adjustments after this many minutes without input
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_minutes: Option<f64>,
+
+    /// Fixed location used instead of IP geolocation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+
+    /// Directory of period-changed hook executables [default: ~/.config/healthy-monitor/hooks]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hooks_dir: Option<PathBuf>,
+
+    /// Executables run on lifecycle events (`[[event_hooks]]`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub event_hooks: Vec<EventHookConfig>,
+
+    /// Night boundaries, optionally per day of the week
+    pub schedule: ScheduleConfig,
+
+    /// Wind-down toward the bedtime set in the schedule
+    pub bedtime: BedtimeConfig,
+
+    /// Gradual wake-up ramp after night ends
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sunrise: Option<SunriseConfig>,
+
+    /// Bright-light boost on winter mornings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub morning_boost: Option<BoostConfig>,
+
+    /// 20-20-20 break reminders in daemon mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breaks: Option<BreaksConfig>,
+
+    /// Notifications explaining night mode, expired overrides and sensor failures
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub announce: Option<AnnounceConfig>,
+
+    /// Quiet hours and Do-Not-Disturb handling for reminders
+    pub quiet: QuietConfig,
+
+    /// Phone delivery of break and bedtime reminders
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub push: Option<PushConfig>,
+
+    /// Custom reminders in daemon mode (`[[reminders]]`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reminders: Vec<ReminderConfig>,
+
+    /// Weekly summary notification in daemon mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weekly_summary: Option<WeeklyConfig>,
+
+    /// Guided eye-exercise routine
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eye_exercises: Option<ExercisesConfig>,
+
+    /// Daily break and screen-time goal tracked as a streak
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goals: Option<GoalsConfig>,
+
+    /// Stand-up reminders during long sitting stretches in daemon mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stretch: Option<StretchConfig>,
+
+    /// Blink reminders during long uninterrupted use in daemon mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blink: Option<BlinkConfig>,
+
+    /// Screen settings during pomodoro breaks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pomodoro: Option<PomodoroConfig>,
+
+    /// Warning when the screen stays much brighter than the room
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contrast_warning: Option<ContrastConfig>,
+
+    /// "Open the blinds" suggestion on dark mornings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daylight_nudge: Option<DaylightConfig>,
+
+    /// Viewing-distance warnings from webcam face detection (opt-in)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub posture: Option<PostureConfig>,
+
+    /// Late-night grayscale window
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grayscale: Option<GrayscaleConfig>,
+
+    /// Brightness mapping while running on battery
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery: Option<BatteryConfig>,
+
+    /// Lower brightness and fewer updates in power-profiles-daemon's power-saver mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub power_saver: Option<PowerSaverConfig>,
+
+    /// Screen time from, and applied settings to, a local ActivityWatch server
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activitywatch: Option<ActivityWatchConfig>,
+
+    /// Calendar whose busy events hold the settings and defer breaks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub calendar: Option<CalendarConfig>,
+
+    /// Extra dimming while the screen shows bright content
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub screen_content: Option<ContentConfig>,
+
+    /// Local REST API in daemon mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpConfig>,
+
+    /// Brightness and temperature from the knobs of a MIDI controller in daemon mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub midi: Option<MidiConfig>,
+
+    /// Publishing of readings, settings and health events to an MQTT broker in daemon mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mqtt: Option<MqttConfig>,
+
+    /// OTLP export of update-cycle traces and metrics
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub telemetry: Option<TelemetryConfig>,
+
+    /// Smart bulbs following the screen's color temperature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub room_lights: Option<RoomLightsConfig>,
+
+    /// Old location of `[room_lights.hue]`, moved there on load and written back in its place
+    #[serde(skip_serializing)]
+    pub hue: Option<LegacyHueConfig>,
+
+    /// Global shortcuts for manual adjustments in daemon mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hotkeys: Option<HotkeysConfig>,
+
+    /// Named profiles keyed by name (e.g. `[profile.movie]`)
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub profile: HashMap<String, ProfileConfig>,
+
+    /// Profiles applied automatically while an app is focused, keyed by window class.
+    /// Ordered so that the match for a window is the same on every update, see `focus::check`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub app_profiles: BTreeMap<String, String>,
+
+    /// Per-monitor settings keyed by output name (e.g. `[monitor.DP-1]`)
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub monitor: HashMap<String, MonitorConfig>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct Location {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MonitorConfig {
+    /// Static RGB gamma multiplier composed with the temperature-derived gamma
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gamma: Option<[f64; 3]>,
+    /// Brightness cap for this monitor, overriding `max_brightness`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_brightness: Option<f64>,
+}
+
+impl Config {
+    /// Loads the config from `path`, or from the default location when none is given.
+    /// A missing file at the default location yields an empty config.
+    pub fn load(path: Option<&Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let (path, explicit) = match path {
+            Some(p) => (p.to_path_buf(), true),
+            None => match default_path() {
+                Some(p) => (p, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound && !explicit => return Ok(Self::default()),
+            Err(e) => return Err(format!("Failed to read config {}: {}", path.display(), e).into()),
+        };
+
+        let mut config: Self =
+            toml::from_str(&contents).map_err(|e| format!("Invalid config {}: {}", path.display(), e))?;
+        config.migrate();
+        Ok(config)
+    }
+
+    /// Moves settings from where older versions kept them, warning so the file gets updated
+    fn migrate(&mut self) {
+        let Some(hue) = self.hue.take() else {
+            return;
+        };
+        eprintln!("Warning: [hue] in the config is now [room_lights.hue], with the brightness and transition settings in [room_lights]");
+
+        // An explicit new section wins over the old one
+        let room_lights = self.room_lights.get_or_insert_with(|| RoomLightsConfig {
+            day_brightness: hue.day_brightness,
+            night_brightness: hue.night_brightness,
+            transition_seconds: hue.transition_seconds,
+            ..RoomLightsConfig::default()
+        });
+        room_lights.hue.get_or_insert_with(|| HueConfig {
+            bridge: hue.bridge,
+            username: hue.username,
+            lights: hue.lights,
+            ..HueConfig::default()
+        });
+    }
+
+    /// Loads the config at `path` for editing, starting empty when it doesn't exist yet
+    pub fn load_for_update(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if path.exists() {
+            Self::load(Some(path))
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Writes the config to `path`, creating parent directories as needed. An existing file is
+    /// edited in place: only changed values are rewritten, so comments and layout survive.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let new: DocumentMut = toml::to_string_pretty(self)?.parse()?;
+        let contents = match std::fs::read_to_string(path) {
+            Ok(existing) => match existing.parse::<DocumentMut>() {
+                Ok(mut document) => {
+                    merge_table(document.as_table_mut(), new.as_table());
+                    document.to_string()
+                }
+                // Nothing worth keeping in a file that doesn't parse
+                Err(_) => new.to_string(),
+            },
+            Err(e) if e.kind() == ErrorKind::NotFound => new.to_string(),
+            Err(e) => return Err(format!("Failed to read config {}: {}", path.display(), e).into()),
+        };
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Settings for a single monitor, falling back to defaults if it isn't configured
+    pub fn monitor(&self, name: &str) -> MonitorConfig {
+        self.monitor.get(name).cloned().unwrap_or_default()
+    }
+}
+
+/// `$XDG_CONFIG_HOME/healthy-monitor/config.toml`, or `~/.config/...` when unset
+pub fn default_path() -> Option<PathBuf> {
+    Some(config_home()?.join("healthy-monitor").join("config.toml"))
+}
+
+/// `$XDG_CONFIG_HOME`, or `~/.config` when unset
+pub fn config_home() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+}
+
+/// Brings `old` in line with `new`, keeping the decoration (comments, whitespace) of
+/// everything that didn't change and dropping keys `new` no longer has
+fn merge_table(old: &mut Table, new: &Table) {
+    let stale: Vec<String> = old.iter().map(|(key, _)| key.to_string()).filter(|key| !new.contains_key(key)).collect();
+    for key in stale {
+        old.remove(&key);
+    }
+
+    for (key, item) in new.iter() {
+        match (old.get_mut(key), item) {
+            (Some(Item::Table(old_table)), Item::Table(new_table)) => merge_table(old_table, new_table),
+            (Some(Item::ArrayOfTables(old_array)), Item::ArrayOfTables(new_array)) if old_array.len() == new_array.len() => {
+                for (old_table, new_table) in old_array.iter_mut().zip(new_array.iter()) {
+                    merge_table(old_table, new_table);
+                }
+            }
+            // Inline tables stay inline
+            (Some(Item::Value(old_value)), Item::Table(new_table)) if old_value.is_inline_table() => {
+                merge_value(old_value, &Value::InlineTable(new_table.clone().into_inline_table()));
+            }
+            (Some(Item::Value(old_value)), Item::Value(new_value)) => merge_value(old_value, new_value),
+            _ => {
+                old.insert(key, item.clone());
+            }
+        }
+    }
+}
+
+/// Replaces `old` unless it already holds the same value, however it is written
+fn merge_value(old: &mut Value, new: &Value) {
+    let parse = |value: &Value| format!("value = {}", value).parse::<toml::Table>().ok();
+    if parse(old).is_some() && parse(old) == parse(new) {
+        return;
+    }
+    let decor = old.decor().clone();
+    *old = new.clone();
+    *old.decor_mut() = decor;
+}
@@ -0,0 +1,100 @@
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::portal::{self, ScreenCapture};
+use crate::state::State;
+use crate::video;
+
+/// The screen cast kept open between updates, so the daemon doesn't start a new one (and show
+/// the sharing indicator again) every time
+static CAST: Mutex<Option<ScreenCapture>> = Mutex::new(None);
+
+/// `[screen_content]` config section: dim further while the screen shows mostly bright content
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ContentConfig {
+    /// Average screen luma above which brightness is lowered [0.0..1.0]
+    pub threshold: f64,
+    /// Share of brightness taken away for an all-white screen [0.0..1.0]
+    pub strength: f64,
+}
+
+impl Default for ContentConfig {
+    fn default() -> Self {
+        ContentConfig { threshold: 0.6, strength: 0.3 }
+    }
+}
+
+/// Average luma of what's on screen [0.0..1.0], captured through the ScreenCast portal.
+/// The permission is kept in the state so later captures don't ask again, and the cast stays
+/// open for the next update until a capture from it fails.
+pub async fn luma(state: &mut State) -> Result<f64, Box<dyn std::error::Error>> {
+    // Frames are read through PipeWire; don't have the desktop ask for a cast that can't be read
+    if !cfg!(feature = "portal") {
+        return Err("healthy-monitor was built without the `portal` feature".into());
+    }
+
+    let taken = CAST.lock().unwrap().take();
+    let capture = match taken {
+        Some(capture) => capture,
+        None => {
+            let capture = portal::open_screen(state.screencast_token.as_deref()).await?;
+            if capture.restore_token.is_some() {
+                state.screencast_token = capture.restore_token.clone();
+            }
+            capture
+        }
+    };
+
+    let fd = capture.fd.try_clone()?;
+    let node = capture.node;
+    let frame = tokio::task::spawn_blocking(move || video::capture_frame(fd, Some(node), 0).map_err(|e| e.to_string()))
+        .await??;
+    *CAST.lock().unwrap() = Some(capture);
+
+    let total: u64 = frame.pixels.iter().map(|&luma| luma as u64).sum();
+    Ok(total as f64 / frame.pixels.len().max(1) as f64 / 255.0)
+}
+
+/// PipeWire node of the cast kept open for measuring the screen, which isn't anyone sharing it
+pub fn own_node() -> Option<u32> {
+    CAST.lock().unwrap().as_ref().map(|capture| capture.node)
+}
+
+/// Lowers `brightness` in proportion to how far the screen content exceeds the threshold
+pub fn compensate(config: &ContentConfig, brightness: f64, luma: f64) -> f64 {
+    let excess = ((luma - config.threshold) / (1.0 - config.threshold).max(f64::EPSILON)).clamp(0.0, 1.0);
+    brightness * (1.0 - config.strength * excess)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn dark_content_is_left_alone() {
+        let config = ContentConfig::default();
+        assert_close(compensate(&config, 0.8, 0.0), 0.8);
+        assert_close(compensate(&config, 0.8, 0.6), 0.8);
+    }
+
+    #[test]
+    fn bright_content_dims_in_proportion() {
+        let config = ContentConfig::default();
+        assert_close(compensate(&config, 0.8, 0.8), 0.8 * 0.85);
+        assert_close(compensate(&config, 0.8, 1.0), 0.8 * 0.7);
+        assert_close(compensate(&config, 0.8, 1.5), 0.8 * 0.7);
+    }
+
+    #[test]
+    fn threshold_at_full_white() {
+        let config = ContentConfig { threshold: 1.0, strength: 0.5 };
+        assert_close(compensate(&config, 0.8, 1.0), 0.8);
+        assert!(compensate(&config, 0.8, 0.99).is_finite());
+    }
+}
@@ -0,0 +1,51 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::notify::{self, Urgency};
+use crate::state::State;
+
+/// Webcam readings older than this aren't trusted to describe the room (seconds)
+const MAX_READING_AGE: i64 = 30 * 60;
+
+/// `[contrast_warning]` config section: warn when the screen is much brighter than the room
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ContrastConfig {
+    /// How far applied brightness may exceed the ambient level [0.0..1.0]
+    pub difference: f64,
+    /// Minutes the gap must last before warning
+    pub minutes: f64,
+}
+
+impl Default for ContrastConfig {
+    fn default() -> Self {
+        ContrastConfig { difference: 0.4, minutes: 15.0 }
+    }
+}
+
+/// Compares the applied brightness with the latest webcam reading and warns
+/// once per episode when the screen stays far brighter than the room
+pub fn update(config: &ContrastConfig, state: &mut State, brightness: f64) {
+    let now = Utc::now().timestamp();
+
+    let ambient = match state.ambient.zip(state.ambient_at) {
+        Some((ambient, at)) if now - at <= MAX_READING_AGE => ambient,
+        _ => return,
+    };
+
+    if brightness - ambient <= config.difference {
+        state.glare_since = None;
+        state.glare_warned = false;
+        return;
+    }
+
+    let since = *state.glare_since.get_or_insert(now);
+    if (now - since) as f64 / 60.0 >= config.minutes && !state.glare_warned {
+        notify::send(
+            "Screen is much brighter than the room",
+            "Consider switching on a lamp or lowering the brightness floor",
+            Urgency::Normal,
+        );
+        state.glare_warned = true;
+    }
+}
@@ -0,0 +1,67 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tokio::signal::unix::{signal, SignalKind};
+
+use crate::config::Config;
+use crate::state::State;
+use crate::Args;
+
+/// Color temperature restored when nothing was ever applied (Kelvin)
+const NEUTRAL_TEMP: f64 = 6500.0;
+
+/// Set once a restore started, so a panic while restoring doesn't try again
+static RESTORING: AtomicBool = AtomicBool::new(false);
+
+/// Restores the last settings an update finished applying when the process panics, so a
+/// crash in the middle of a fade or a preview doesn't leave the screen dim and orange.
+/// Panics in spawned tasks, which tokio would otherwise swallow, restore too.
+pub fn install(args: &Args) {
+    let args = args.clone();
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        restore(&args, "the crash");
+    }));
+}
+
+/// Restores the same way when the daemon is stopped with Ctrl-C or SIGTERM, which would
+/// otherwise end it halfway through a fade, then exits
+pub fn restore_on_signal(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let args = args.clone();
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+        restore(&args, "stopping");
+        std::process::exit(0);
+    });
+    Ok(())
+}
+
+fn restore(args: &Args, after: &str) {
+    if RESTORING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    // Detecting the outputs again would take locks the failing code may still hold; the
+    // outputs settings were last applied to are known without them
+    let Some(monitors) = args.monitors.clone().or_else(crate::last_monitors) else {
+        RESTORING.store(false, Ordering::SeqCst);
+        return;
+    };
+    let mut args = args.clone();
+    args.monitors = Some(monitors);
+
+    // The config is read again rather than shared with the hook; it parsed at startup
+    let config = Config::load(args.config.as_deref()).unwrap_or_default();
+    let state = State::load();
+    let brightness = state.brightness.unwrap_or(1.0);
+    let color_temp = state.color_temp.unwrap_or(NEUTRAL_TEMP);
+    match crate::set_monitor_gamma(brightness, crate::temp_to_gamma(color_temp), &args, &config) {
+        Ok(()) => eprintln!("Restored brightness {:.2} at {:.0}K after {}", brightness, color_temp, after),
+        Err(e) => eprintln!("Failed to restore the display after {}: {}", after, e),
+    }
+    RESTORING.store(false, Ordering::SeqCst);
+}
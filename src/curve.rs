@@ -0,0 +1,147 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::state;
+
+/// Ambient levels are grouped into this many equal bins
+const BINS: usize = 10;
+/// Brightness change per feedback
+pub const STEP: f64 = 0.05;
+/// Largest correction learned for a bin
+const MAX_OFFSET: f64 = 0.5;
+
+/// Learned brightness corrections per ambient level, added on top of the default mapping
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Curve {
+    offsets: Vec<f64>,
+}
+
+impl Curve {
+    pub fn load() -> Self {
+        let Some(path) = path() else {
+            return Self::default();
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("Ignoring invalid curve file {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = path().ok_or("Cannot determine the curve file location")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        state::write_atomic(&path, &serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Correction at `ambient`, interpolated between bin centers
+    pub fn offset(&self, ambient: f64) -> f64 {
+        if self.offsets.len() != BINS {
+            return 0.0;
+        }
+
+        let position = (ambient.clamp(0.0, 1.0) * BINS as f64 - 0.5).clamp(0.0, (BINS - 1) as f64);
+        let lower = position.floor() as usize;
+        let upper = (lower + 1).min(BINS - 1);
+        let t = position - lower as f64;
+
+        self.offsets[lower] * (1.0 - t) + self.offsets[upper] * t
+    }
+
+    /// Shifts the correction at `ambient` by `delta`, and its neighbors by half as much
+    pub fn nudge(&mut self, ambient: f64, delta: f64) {
+        self.offsets.resize(BINS, 0.0);
+
+        let bin = ((ambient.clamp(0.0, 1.0) * BINS as f64) as usize).min(BINS - 1);
+        let mut shift = |i: usize, amount: f64| {
+            self.offsets[i] = (self.offsets[i] + amount).clamp(-MAX_OFFSET, MAX_OFFSET);
+        };
+
+        shift(bin, delta);
+        if bin > 0 {
+            shift(bin - 1, delta / 2.0);
+        }
+        if bin + 1 < BINS {
+            shift(bin + 1, delta / 2.0);
+        }
+    }
+}
+
+/// `$XDG_STATE_HOME/healthy-monitor/curve.json`
+fn path() -> Option<PathBuf> {
+    Some(state::state_dir()?.join("curve.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn nothing_learned() {
+        let curve = Curve::default();
+        assert_eq!(curve.offset(0.0), 0.0);
+        assert_eq!(curve.offset(0.5), 0.0);
+    }
+
+    #[test]
+    fn nudge_spreads_to_neighbors() {
+        let mut curve = Curve::default();
+        curve.nudge(0.45, 0.1);
+        assert!(close(curve.offsets[4], 0.1));
+        assert!(close(curve.offsets[3], 0.05));
+        assert!(close(curve.offsets[5], 0.05));
+        assert_eq!(curve.offsets.iter().filter(|o| **o != 0.0).count(), 3);
+    }
+
+    #[test]
+    fn nudge_at_the_ends() {
+        let mut curve = Curve::default();
+        curve.nudge(0.0, 0.1);
+        curve.nudge(1.0, -0.1);
+        curve.nudge(-3.0, 0.1);
+        curve.nudge(7.0, -0.1);
+        assert!(close(curve.offsets[0], 0.2));
+        assert!(close(curve.offsets[1], 0.1));
+        assert!(close(curve.offsets[BINS - 1], -0.2));
+        assert!(close(curve.offsets[BINS - 2], -0.1));
+    }
+
+    #[test]
+    fn offsets_are_capped() {
+        let mut curve = Curve::default();
+        for _ in 0..20 {
+            curve.nudge(0.5, STEP);
+        }
+        assert_eq!(curve.offsets[5], MAX_OFFSET);
+        assert_eq!(curve.offset(0.55), MAX_OFFSET);
+    }
+
+    #[test]
+    fn offset_between_bin_centers() {
+        let mut curve = Curve { offsets: vec![0.0; BINS] };
+        curve.offsets[0] = 0.2;
+        curve.offsets[1] = 0.4;
+        curve.offsets[BINS - 1] = -0.3;
+
+        assert!(close(curve.offset(0.05), 0.2));
+        assert!(close(curve.offset(0.1), 0.3));
+        assert!(close(curve.offset(0.15), 0.4));
+        // Below the first and above the last bin center the end bins hold
+        assert!(close(curve.offset(0.0), 0.2));
+        assert!(close(curve.offset(-1.0), 0.2));
+        assert!(close(curve.offset(1.0), -0.3));
+        assert!(close(curve.offset(2.0), -0.3));
+    }
+}
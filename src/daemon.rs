@@ -0,0 +1,397 @@
+use std::time::Duration;
+
+use chrono::{Local, Utc};
+use serde_json::json;
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::time::{sleep, Instant};
+
+use crate::battery::{self, Power};
+use crate::blink::{self, BlinkReminder};
+use crate::breaks::{self, BreakTimer};
+use crate::config::Config;
+use crate::events::{Event, Snapshot};
+use crate::exit::{self, Failure};
+use crate::focus::{self, Focus};
+use crate::hooks::HookEvent;
+use crate::lock::{self, LockWatch};
+use crate::network;
+use crate::reminders::Reminders;
+use crate::screentime::{Exposure, Tracker};
+use crate::state::State;
+use crate::status::Status;
+use crate::{calendar, dbus, exercises, gamemode, goals, hooks, hotkeys, http, idle, ipc, light, metrics, midi, mqtt, notify, powerprofiles, profile, sharing, stretch, weekly, Args};
+
+/// Events a slow WebSocket client can fall behind by before missing some
+const EVENT_BACKLOG: usize = 64;
+
+/// How often focus, screen sharing, idleness and reminders are checked between regular updates
+const WATCH_POLL: Duration = Duration::from_secs(2);
+
+/// Ambient level from which the room counts as brightly lit
+const DEFAULT_BRIGHT_AMBIENT: f64 = 0.6;
+/// Older webcam readings don't count toward light exposure (seconds)
+const MAX_READING_AGE: i64 = 30 * 60;
+
+/// Requests from outside the daemon, handled between updates
+#[derive(Debug, Clone, Copy)]
+pub enum Command {
+    SetBrightness(f64),
+    SetTemperature(f64),
+    Pause,
+    Resume,
+    Refresh,
+    /// Forgets the IP-based location and updates with a fresh lookup
+    Relocate,
+}
+
+impl Command {
+    /// Rejects values outside the range the monitors can be set to
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            Command::SetBrightness(brightness) if !(0.0..=1.0).contains(&brightness) => {
+                Err("Brightness must be between 0.0 and 1.0".into())
+            }
+            Command::SetTemperature(temp) if !(1000.0..=10000.0).contains(&temp) => {
+                Err("Temperature must be between 1000 and 10000 K".into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Conditions checked between regular updates; any change triggers an immediate update
+#[derive(Debug, PartialEq)]
+struct Watched {
+    focus: Focus,
+    sharing: bool,
+    idle: bool,
+    /// Only followed with `dim_on_lock`
+    locked: bool,
+    /// Only followed with `pause_during_games`
+    gaming: bool,
+    /// Only followed with `[power_saver]`
+    power_saver: bool,
+    /// On battery, and low on it; only followed with `[battery]`
+    battery: (bool, bool),
+    /// Connectors whose screen is powered down, so outputs are set once they come back on
+    powered_off: Vec<String>,
+}
+
+/// Background watches of D-Bus state, each only set up when its feature is configured
+struct Signals {
+    lock: Option<LockWatch>,
+    gaming: Option<watch::Receiver<bool>>,
+    power_saver: Option<watch::Receiver<bool>>,
+    power: Option<watch::Receiver<Option<Power>>>,
+}
+
+impl Watched {
+    fn check(config: &Config, signals: &Signals) -> Self {
+        Watched {
+            focus: focus::check(config),
+            sharing: config.neutral_while_sharing && sharing::active(config),
+            idle: config.idle_minutes.map_or(false, idle::is_idle),
+            locked: signals.lock.as_ref().map_or(false, LockWatch::locked),
+            gaming: signals.gaming.as_ref().map_or(false, |g| *g.borrow()),
+            power_saver: signals.power_saver.as_ref().map_or(false, |p| *p.borrow()),
+            battery: match (&config.battery, &signals.power) {
+                (Some(battery), Some(power)) => battery::policy(battery, *power.borrow()),
+                _ => (false, false),
+            },
+            powered_off: crate::powered_off_connectors(),
+        }
+    }
+}
+
+/// Applies settings every `interval`, reacting sooner when focus moves to or away from
+/// an exception app or an app with its own profile, when screen sharing starts or stops,
+/// when the user comes back from being idle, when the session is locked or unlocked, when a
+/// game exits, when the power profile or source changes, when the network changes, when a
+/// screen wakes from DPMS, or when asked to over D-Bus
+pub async fn run(args: &Args, config: &Config, interval: Duration) -> Result<(), Box<dyn std::error::Error>> {
+    if ipc::running() {
+        return Err(exit::fail(Failure::AlreadyRunning, "Another healthy-monitor daemon is already running"));
+    }
+
+    let signals = Signals {
+        lock: if config.dim_on_lock { Some(lock::watch().await) } else { None },
+        gaming: if config.pause_during_games { Some(gamemode::watch().await) } else { None },
+        power_saver: if config.power_saver.is_some() { Some(powerprofiles::watch().await) } else { None },
+        power: if config.battery.is_some() { Some(battery::watch().await) } else { None },
+    };
+    let mut watched = Watched::check(config, &signals);
+    let mut break_timer = BreakTimer::default();
+    let mut stretch_timer = BreakTimer::default();
+    let mut screen_time = Tracker::load(config);
+    let mut blink_reminder = BlinkReminder::default();
+    let mut custom_reminders = Reminders::new(&config.reminders);
+    let mut goals_recorded = None;
+    // While a break holds the screen dimmed, when it's over
+    let mut break_ends = None;
+
+    // Remote control over D-Bus and the control socket; the daemon carries on without either
+    let (command_sender, mut commands) = mpsc::unbounded_channel();
+    let state = State::load();
+    let (status, status_receiver) = watch::channel(Status::from_state(&state, config));
+    let (events, _) = broadcast::channel(EVENT_BACKLOG);
+    let bus = match dbus::serve(command_sender.clone()).await {
+        Ok(connection) => Some(connection),
+        Err(e) => {
+            eprintln!("D-Bus service unavailable: {}", e);
+            None
+        }
+    };
+    if let Err(e) = ipc::serve(command_sender.clone(), status_receiver.clone()) {
+        eprintln!("Control socket unavailable: {}", e);
+    }
+    if let Some(http_config) = &config.http {
+        if let Err(e) = http::serve(http_config, command_sender.clone(), status_receiver, events.clone()).await {
+            eprintln!("HTTP API unavailable: {}", e);
+        }
+    }
+    if let Some(hotkeys_config) = &config.hotkeys {
+        let temps = (args.day_temp, args.night_temp);
+        if let Err(e) = hotkeys::serve(hotkeys_config, temps, command_sender.clone(), status_receiver.clone()).await {
+            eprintln!("Global shortcuts unavailable: {}", e);
+        }
+    }
+    if let Some(midi_config) = &config.midi {
+        if let Err(e) = midi::serve(midi_config, command_sender.clone(), status_receiver.clone()).await {
+            eprintln!("MIDI controller unavailable: {}", e);
+        }
+    }
+    // A new network may be somewhere else; the lookup is dropped between updates, never during one
+    if config.location.is_none() {
+        network::relocate_on_change(network::watch().await, command_sender.clone());
+    }
+    if let Some(mqtt_config) = &config.mqtt {
+        mqtt::serve(mqtt_config, events.subscribe(), command_sender);
+    }
+    let mut publisher = Publisher { bus, status, events, snapshot: Snapshot::of(&state) };
+
+    // Something sensible goes on screen at once; the first update refines it
+    if !watched.locked {
+        crate::apply_estimate(args, config);
+    }
+
+    loop {
+        let started = Instant::now();
+        // Nobody can see the screen behind the lock: go dim and leave the camera off until unlocked
+        if watched.locked {
+            dim_for_lock(args, config);
+        } else if break_ends.is_some() {
+            // The break keeps the screen dimmed until it's over
+        } else if let Err(e) = crate::run(args, config).await {
+            eprintln!("Update failed: {}", e);
+        }
+        metrics::record_cycle(started.elapsed());
+        publisher.publish(config).await;
+
+        // Only finished days are recorded, so once a day is enough
+        if let Some(goals_config) = &config.goals {
+            let today = Local::now().date_naive();
+            if goals_recorded != Some(today) {
+                match goals::update(goals_config) {
+                    Ok(()) => goals_recorded = Some(today),
+                    Err(e) => eprintln!("Failed to record goals: {}", e),
+                }
+            }
+        }
+
+        // Light exposure follows the latest webcam reading until the next update
+        let threshold = config.bright_ambient.unwrap_or(DEFAULT_BRIGHT_AMBIENT);
+        let state = State::load();
+        let fresh = state.ambient_at.map_or(false, |at| Utc::now().timestamp() - at <= MAX_READING_AGE);
+        let exposure = Exposure {
+            bright: fresh && state.ambient.map_or(false, |ambient| ambient >= threshold),
+            blue_light: state.brightness.zip(state.color_temp).map_or(0.0, |(b, t)| light::blue_light(b, t)),
+        };
+
+        // Power saving stretches the time between updates
+        let interval = match &config.power_saver {
+            Some(power_saver) if watched.power_saver => interval.mul_f64(power_saver.interval_factor.max(1.0)),
+            _ => interval,
+        };
+        let deadline = Instant::now() + interval;
+        while Instant::now() < deadline {
+            let started = Instant::now();
+            let command = tokio::select! {
+                _ = sleep(WATCH_POLL.min(deadline - started)) => None,
+                Some(command) = commands.recv() => Some(command),
+            };
+            let elapsed = started.elapsed();
+
+            let now_watched = Watched::check(config, &signals);
+            let idle_seconds = idle::idle_seconds();
+            screen_time.tick(elapsed, idle_seconds, now_watched.idle, exposure);
+
+            // Time since the last input, or only whether the session is idle when that isn't available
+            let idle_for = match idle_seconds {
+                Some(seconds) => Duration::from_secs_f64(seconds.max(0.0)),
+                None if now_watched.idle => Duration::MAX,
+                None => Duration::ZERO,
+            };
+
+            if let Some(breaks_config) = &config.breaks {
+                if break_timer.tick(breaks_config.interval(), elapsed, idle_for, breaks_config.reset_after()) {
+                    let state = State::load();
+                    let enabled = profile::active(config, &now_watched.focus, &state)
+                        .and_then(|p| p.breaks)
+                        .unwrap_or(true);
+
+                    if !enabled {
+                        break_timer.reset();
+                    } else if !breaks::snoozed(&state) && !calendar::in_event(&state) {
+                        break_timer.reset();
+                        if !notify::quiet(&config.quiet) {
+                            publisher.emit_reminder("break");
+                            break_ends = take_break(breaks_config, args, config, &state).await;
+                        }
+                    }
+                }
+            }
+
+            if let Some(stretch_config) = &config.stretch {
+                if stretch_timer.tick(stretch_config.interval(), elapsed, idle_for, stretch_config.reset_after()) {
+                    stretch_timer.reset();
+                    if !notify::quiet(&config.quiet) {
+                        publisher.emit_reminder("stretch");
+                        stretch::remind(stretch_config);
+                    }
+                }
+            }
+
+            if let Some(blink_config) = &config.blink {
+                if blink_reminder.due(blink_config, screen_time.stretch_seconds()) && !notify::quiet(&config.quiet) {
+                    publisher.emit_reminder("blink");
+                    blink::remind(blink_config, args, config).await;
+                    blink_reminder.delivered();
+                }
+            }
+
+            custom_reminders.tick(&config.reminders, elapsed, idle_for, &config.quiet);
+
+            if let Some(weekly_config) = &config.weekly_summary {
+                weekly::check(weekly_config, &config.quiet, &config.schedule);
+            }
+
+            let mut refresh = false;
+            if let Some(command) = command {
+                refresh = handle(command, args, config);
+                publisher.publish(config).await;
+            }
+
+            // The break is over: the update brings the screen back
+            let break_over = break_ends.map_or(false, |ends| Instant::now() >= ends);
+            if break_over {
+                break_ends = None;
+            }
+
+            if refresh || break_over || now_watched != watched {
+                watched = now_watched;
+                break;
+            }
+        }
+    }
+}
+
+/// Drops to the lowest configured brightness at the last applied temperature
+fn dim_for_lock(args: &Args, config: &Config) {
+    let floor = args.night_min_brightness.map_or(args.min_brightness, |night| night.min(args.min_brightness));
+    let color_temp = State::load().color_temp.unwrap_or(args.night_temp);
+    if let Err(e) = crate::set_monitor_brightness(floor, color_temp, args, config) {
+        eprintln!("Failed to dim for the lock screen: {}", e);
+    }
+}
+
+/// Carries out `command`, returning whether an update should run right away
+fn handle(command: Command, args: &Args, config: &Config) -> bool {
+    let result = match command {
+        Command::SetBrightness(brightness) => crate::set_brightness(brightness, args, config),
+        Command::SetTemperature(color_temp) => crate::set_temperature(color_temp, args, config),
+        Command::Pause | Command::Resume => {
+            let mut state = State::load();
+            state.paused = matches!(command, Command::Pause);
+            if !state.paused {
+                state.paused_until = None;
+            }
+            state.save()
+        }
+        Command::Refresh => {
+            crate::forget_monitors();
+            return true;
+        }
+        Command::Relocate => {
+            network::forget_location();
+            return true;
+        }
+    };
+
+    if let Err(e) = &result {
+        eprintln!("{:?} failed: {}", command, e);
+    }
+    matches!(command, Command::Resume)
+}
+
+/// Where changes to the applied settings are announced
+struct Publisher {
+    bus: Option<zbus::Connection>,
+    status: watch::Sender<Status>,
+    events: broadcast::Sender<Event>,
+    /// State at the last announcement, to tell what changed since
+    snapshot: Snapshot,
+}
+
+impl Publisher {
+    /// Tells D-Bus listeners, socket subscribers and event streams about the current settings
+    async fn publish(&mut self, config: &Config) {
+        let state = State::load();
+        self.status.send_replace(Status::from_state(&state, config));
+
+        let snapshot = Snapshot::of(&state);
+        for event in self.snapshot.changes(&snapshot) {
+            // Nobody listening is fine
+            let _ = self.events.send(event);
+        }
+        self.snapshot = snapshot;
+
+        if let Some(connection) = &self.bus {
+            if let Err(e) = dbus::notify_changed(connection).await {
+                eprintln!("Failed to emit D-Bus signals: {}", e);
+            }
+        }
+    }
+
+    fn emit_reminder(&self, kind: &'static str) {
+        let _ = self.events.send(Event::Reminder { at: Utc::now().timestamp(), kind });
+    }
+}
+
+/// Reminds about the break, dimming the screen for its duration when configured. Returns when
+/// the break is over if the screen was dimmed, for the loop to bring it back then.
+async fn take_break(breaks_config: &breaks::BreaksConfig, args: &Args, config: &Config, state: &State) -> Option<Instant> {
+    let length = breaks_config.break_length().as_secs();
+    hooks::fire(config, HookEvent::BreakStarted, json!({ "length_seconds": length }));
+    breaks::remind(breaks_config, config.push.as_ref()).await;
+
+    // The routine paces itself over the break in its own task, and the loop restores the dimmed
+    // screen once the break is over, so commands are still answered meanwhile
+    if let Some(exercises_config) = config.eye_exercises.clone().filter(|e| e.enabled && e.with_breaks) {
+        tokio::spawn(async move {
+            if let Err(e) = exercises::routine(&exercises_config).await {
+                eprintln!("Eye exercises failed: {}", e);
+            }
+        });
+    }
+
+    if !breaks_config.dim {
+        return None;
+    }
+
+    let color_temp = state.color_temp.unwrap_or(args.day_temp);
+    if let Err(e) = crate::set_monitor_brightness(breaks_config.dim_brightness, color_temp, args, config) {
+        eprintln!("Failed to dim for break: {}", e);
+        return None;
+    }
+    Some(Instant::now() + breaks_config.break_length())
+}
@@ -0,0 +1,89 @@
+use chrono::{DateTime, Datelike, Local, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::notify::{self, Urgency};
+use crate::schedule::{self, TimeOfDay};
+use crate::state::State;
+
+/// `[daylight_nudge]` config section: suggest letting daylight in on dark mornings
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DaylightConfig {
+    pub start: TimeOfDay,
+    pub end: TimeOfDay,
+    /// Sun elevation above the horizon (degrees) from which it counts as well up
+    pub min_sun_elevation: f64,
+    /// Ambient readings below this count as a dark room [0.0..1.0]
+    pub dark_below: f64,
+    /// Minutes the room must stay dark before nudging
+    pub minutes: f64,
+}
+
+impl Default for DaylightConfig {
+    fn default() -> Self {
+        DaylightConfig {
+            start: TimeOfDay(8.0),
+            end: TimeOfDay(11.0),
+            min_sun_elevation: 15.0,
+            dark_below: 0.2,
+            minutes: 15.0,
+        }
+    }
+}
+
+/// Suggests opening the blinds when the webcam keeps reading a dark room
+/// while the sun is well up, at most once per morning
+pub async fn update(nudge: &DaylightConfig, config: &Config, state: &mut State) {
+    let now = Local::now();
+    let today = now.date_naive();
+
+    let in_window = TimeOfDay::window_contains(nudge.start, nudge.end, schedule::hour_of(now));
+    let dark = in_window && state.ambient.map_or(false, |ambient| ambient < nudge.dark_below);
+    if !dark {
+        state.dark_room_since = None;
+        return;
+    }
+
+    if state.daylight_nudged_on == Some(today) {
+        return;
+    }
+
+    let since = *state.dark_room_since.get_or_insert(now.timestamp());
+    if ((now.timestamp() - since) as f64 / 60.0) < nudge.minutes {
+        return;
+    }
+
+    let (lat, lon) = match crate::locate(config, state).await {
+        Ok(location) => location,
+        Err(e) => {
+            eprintln!("Failed to look up location for daylight nudge: {}", e);
+            return;
+        }
+    };
+
+    if sun_elevation(lat, lon, now.with_timezone(&Utc)) < nudge.min_sun_elevation {
+        return;
+    }
+
+    notify::send(
+        "It's bright outside",
+        "Open the blinds to get some morning daylight",
+        Urgency::Low,
+    );
+    state.daylight_nudged_on = Some(today);
+}
+
+/// Approximate solar elevation in degrees, ignoring the equation of time
+pub fn sun_elevation(lat: f64, lon: f64, at: DateTime<Utc>) -> f64 {
+    let day = at.ordinal() as f64;
+    let declination = (-23.44f64).to_radians() * ((360.0 / 365.0) * (day + 10.0)).to_radians().cos();
+
+    let utc_hours = at.hour() as f64 + at.minute() as f64 / 60.0;
+    let solar_time = utc_hours + lon / 15.0;
+    let hour_angle = (15.0 * (solar_time - 12.0)).to_radians();
+
+    let lat = lat.to_radians();
+    let sin_elevation = lat.sin() * declination.sin() + lat.cos() * declination.cos() * hour_angle.cos();
+    sin_elevation.asin().to_degrees()
+}
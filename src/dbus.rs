@@ -0,0 +1,146 @@
+use tokio::sync::mpsc::UnboundedSender;
+use zbus::names::BusName;
+use zbus::{fdo, interface, proxy, Connection};
+
+use crate::daemon::Command;
+use crate::state::State;
+
+/// Well-known name requested on the session bus
+pub const NAME: &str = "org.healthymonitor.Daemon";
+/// Object path of the daemon interface
+pub const PATH: &str = "/org/healthymonitor/Daemon";
+
+/// `org.healthymonitor.Daemon`: forwards method calls to the daemon loop and
+/// reads properties from the saved state
+struct Service {
+    commands: UnboundedSender<Command>,
+}
+
+impl Service {
+    fn send(&self, command: Command) -> fdo::Result<()> {
+        command.validate().map_err(fdo::Error::InvalidArgs)?;
+        self.commands
+            .send(command)
+            .map_err(|_| fdo::Error::Failed("The daemon is shutting down".into()))
+    }
+}
+
+#[interface(name = "org.healthymonitor.Daemon")]
+impl Service {
+    /// Applies and holds a brightness, learning from it
+    async fn set_brightness(&self, brightness: f64) -> fdo::Result<()> {
+        self.send(Command::SetBrightness(brightness))
+    }
+
+    /// Applies and holds a color temperature (Kelvin)
+    async fn set_temperature(&self, temperature: f64) -> fdo::Result<()> {
+        self.send(Command::SetTemperature(temperature))
+    }
+
+    /// Holds the current settings until resumed
+    async fn pause(&self) -> fdo::Result<()> {
+        self.send(Command::Pause)
+    }
+
+    /// Resumes automatic adjustments and updates right away
+    async fn resume(&self) -> fdo::Result<()> {
+        self.send(Command::Resume)
+    }
+
+    /// Runs an update now
+    async fn refresh(&self) -> fdo::Result<()> {
+        self.send(Command::Refresh)
+    }
+
+    /// Looks the IP-based location up again and updates
+    async fn relocate(&self) -> fdo::Result<()> {
+        self.send(Command::Relocate)
+    }
+
+    /// Brightness applied at the last update, -1 before the first one
+    #[zbus(property)]
+    async fn current_brightness(&self) -> f64 {
+        State::load().brightness.unwrap_or(-1.0)
+    }
+
+    /// Color temperature applied at the last update (Kelvin), -1 before the first one
+    #[zbus(property)]
+    async fn current_temperature(&self) -> f64 {
+        State::load().color_temp.unwrap_or(-1.0)
+    }
+
+    /// Where the last brightness came from: webcam, remembered, weather or profile, empty before the first update
+    #[zbus(property)]
+    async fn active_sensor(&self) -> String {
+        State::load().source.map_or_else(String::new, |source| source.name().to_string())
+    }
+
+    /// Whether automatic adjustments are paused
+    #[zbus(property)]
+    async fn paused(&self) -> bool {
+        State::load().paused
+    }
+}
+
+/// Publishes the service on the session bus, forwarding method calls to `commands`
+pub async fn serve(commands: UnboundedSender<Command>) -> Result<Connection, Box<dyn std::error::Error>> {
+    let connection = zbus::connection::Builder::session()?
+        .name(NAME)?
+        .serve_at(PATH, Service { commands })?
+        .build()
+        .await?;
+
+    Ok(connection)
+}
+
+#[proxy(
+    interface = "org.healthymonitor.Daemon",
+    default_service = "org.healthymonitor.Daemon",
+    default_path = "/org/healthymonitor/Daemon"
+)]
+trait Daemon {
+    fn set_brightness(&self, brightness: f64) -> zbus::Result<()>;
+    fn set_temperature(&self, temperature: f64) -> zbus::Result<()>;
+    fn pause(&self) -> zbus::Result<()>;
+    fn resume(&self) -> zbus::Result<()>;
+    fn refresh(&self) -> zbus::Result<()>;
+    fn relocate(&self) -> zbus::Result<()>;
+}
+
+/// Hands `commands` to a running daemon, returning false when none is listening on the bus
+pub async fn send(commands: &[Command]) -> Result<bool, Box<dyn std::error::Error>> {
+    let Ok(connection) = Connection::session().await else {
+        return Ok(false);
+    };
+    if !fdo::DBusProxy::new(&connection).await?.name_has_owner(BusName::try_from(NAME)?).await? {
+        return Ok(false);
+    }
+
+    let daemon = DaemonProxy::new(&connection).await?;
+    for command in commands {
+        match *command {
+            Command::SetBrightness(brightness) => daemon.set_brightness(brightness).await?,
+            Command::SetTemperature(temperature) => daemon.set_temperature(temperature).await?,
+            Command::Pause => daemon.pause().await?,
+            Command::Resume => daemon.resume().await?,
+            Command::Refresh => daemon.refresh().await?,
+            Command::Relocate => daemon.relocate().await?,
+        }
+    }
+
+    Ok(true)
+}
+
+/// Emits `PropertiesChanged` for every property so widgets pick up the new values
+pub async fn notify_changed(connection: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let iface = connection.object_server().interface::<_, Service>(PATH).await?;
+    let service = iface.get().await;
+    let ctxt = iface.signal_context();
+
+    service.current_brightness_changed(ctxt).await?;
+    service.current_temperature_changed(ctxt).await?;
+    service.active_sensor_changed(ctxt).await?;
+    service.paused_changed(ctxt).await?;
+
+    Ok(())
+}
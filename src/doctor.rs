@@ -0,0 +1,160 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use crate::config::{self, Config};
+use crate::{als, fetch_location, portal, problem, randr, validate, Args};
+
+/// Result of one check
+enum Outcome {
+    Pass(String),
+    /// Works, but with a limitation worth knowing about
+    Warn(String),
+    Fail(String),
+}
+
+/// Checks the session, display, camera, light sensor, network, API key and config, printing
+/// one line per check. Fails when any check failed.
+pub async fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let location = fetch_location().await;
+    let checks = [
+        ("Session", session()),
+        ("xrandr", xrandr()),
+        ("Camera", camera()),
+        ("Light sensor", light_sensor()),
+        ("Network", match &location {
+            Ok(found) => Outcome::Pass(format!("ip-api.com reachable, located at {:.2}, {:.2}", found.lat, found.lon)),
+            Err(e) => Outcome::Fail(format!("ip-api.com unreachable: {}", e)),
+        }),
+        ("API key", api_key(args, location.as_ref().ok().map(|l| (l.lat, l.lon))).await),
+        ("Config", config_file(args)),
+    ];
+
+    let mut failed = 0;
+    for (name, outcome) in &checks {
+        let (mark, detail) = match outcome {
+            Outcome::Pass(detail) => ("ok  ", detail),
+            Outcome::Warn(detail) => ("warn", detail),
+            Outcome::Fail(detail) => {
+                failed += 1;
+                ("FAIL", detail)
+            }
+        };
+        println!("[{}] {:<13} {}", mark, name, detail);
+    }
+
+    if failed > 0 {
+        return Err(format!("{} of {} checks failed", failed, checks.len()).into());
+    }
+    Ok(())
+}
+
+fn session() -> Outcome {
+    let kind = std::env::var("XDG_SESSION_TYPE").unwrap_or_default();
+    let display = std::env::var("DISPLAY").ok();
+    match (kind.as_str(), display) {
+        ("wayland", Some(_)) => Outcome::Warn("Wayland; xrandr only reaches XWayland outputs".to_string()),
+        (_, Some(display)) => Outcome::Pass(format!("X11 on DISPLAY={}", display)),
+        (_, None) => failure(&problem::Problem::NoDisplay),
+    }
+}
+
+fn xrandr() -> Outcome {
+    let outputs = match randr::query() {
+        Ok(outputs) => outputs,
+        Err(e) => return failure(e.as_ref()),
+    };
+
+    let described: Vec<String> = outputs
+        .iter()
+        .filter(|output| output.connected)
+        .map(|output| match output.geometry {
+            Some(geometry) if output.primary => format!("{} {} primary", output.name, geometry),
+            Some(geometry) => format!("{} {}", output.name, geometry),
+            None => format!("{} off", output.name),
+        })
+        .collect();
+    if outputs.iter().any(|output| output.active()) {
+        Outcome::Pass(described.join(", "))
+    } else {
+        failure(&problem::Problem::NoMonitors)
+    }
+}
+
+fn camera() -> Outcome {
+    if portal::confined() {
+        return Outcome::Pass("sandboxed, asked for through the camera portal".to_string());
+    }
+
+    let mut devices: Vec<PathBuf> = std::fs::read_dir("/dev")
+        .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    devices.retain(|path| path.file_name().map_or(false, |name| name.to_string_lossy().starts_with("video")));
+    devices.sort();
+
+    let Some(first) = devices.first() else {
+        return Outcome::Warn("no /dev/video* devices, the weather stands in".to_string());
+    };
+    match std::fs::OpenOptions::new().read(true).write(true).open(first) {
+        Ok(_) => Outcome::Pass(format!("{} can be opened ({} devices)", first.display(), devices.len())),
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+            failure(&problem::Problem::CameraPermission(first.clone()))
+        }
+        Err(e) => Outcome::Fail(format!("{}: {}", first.display(), e)),
+    }
+}
+
+fn light_sensor() -> Outcome {
+    match als::find() {
+        Some(device) => {
+            let name = std::fs::read_to_string(device.join("name")).unwrap_or_default();
+            let id = device.file_name().unwrap_or_default().to_string_lossy();
+            Outcome::Pass(format!("{} ({}), read along with the webcam", id, name.trim()))
+        }
+        None => Outcome::Warn("none found; not needed, the webcam measures the room".to_string()),
+    }
+}
+
+async fn api_key(args: &Args, location: Option<(f64, f64)>) -> Outcome {
+    let Some(key) = &args.api_key else {
+        return Outcome::Warn("not set, so there is no weather fallback without the webcam".to_string());
+    };
+
+    let (lat, lon) = location.unwrap_or((0.0, 0.0));
+    let url = format!("https://api.openweathermap.org/data/2.5/weather?lat={}&lon={}&appid={}", lat, lon, key);
+    match reqwest::get(&url).await {
+        Ok(response) if response.status().is_success() => Outcome::Pass("accepted by OpenWeather".to_string()),
+        Ok(response) if response.status() == reqwest::StatusCode::UNAUTHORIZED => {
+            Outcome::Fail("rejected by OpenWeather; new keys take a couple of hours to activate".to_string())
+        }
+        Ok(response) => Outcome::Fail(format!("OpenWeather answered {}", response.status())),
+        // The error names the URL, which carries the key
+        Err(e) => Outcome::Fail(format!("OpenWeather unreachable: {}", e.without_url())),
+    }
+}
+
+fn config_file(args: &Args) -> Outcome {
+    let path = args.config.clone().or_else(config::default_path);
+    let shown = path.as_ref().map_or_else(|| "no config location".to_string(), |p| p.display().to_string());
+    let config = match Config::load(args.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => return Outcome::Fail(e.to_string()),
+    };
+
+    // The same checks as `config check`, without its report on unknown keys
+    let problems = validate::out_of_range(&config);
+    if !problems.is_empty() {
+        return Outcome::Fail(format!("{}: {}", shown, problems.join("; ")));
+    }
+
+    match path {
+        Some(path) if path.exists() => Outcome::Pass(shown),
+        _ => Outcome::Pass(format!("{} doesn't exist, defaults apply", shown)),
+    }
+}
+
+fn failure(error: &(dyn std::error::Error + 'static)) -> Outcome {
+    match problem::hint(error) {
+        Some(hint) => Outcome::Fail(format!("{}\n{:21}hint: {}", error, "", hint)),
+        None => Outcome::Fail(error.to_string()),
+    }
+}
@@ -0,0 +1,82 @@
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::history::Source;
+use crate::schedule::Period;
+use crate::state::State;
+
+/// Something that happened in the daemon, streamed to live dashboards
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Event {
+    /// A new ambient light estimate
+    Reading {
+        at: i64,
+        /// Raw webcam reading, when the webcam was used [0.0..1.0]
+        ambient: Option<f64>,
+        /// Ambient level the brightness was based on [0.0..1.0]
+        level: Option<f64>,
+        source: Option<Source>,
+    },
+    /// New brightness or color temperature on the monitors
+    Applied { at: i64, brightness: f64, color_temp: f64 },
+    /// The period of the day changed
+    Transition { at: i64, from: Option<Period>, to: Period },
+    /// A health reminder was shown: "break", "stretch" or "blink"
+    Reminder { at: i64, kind: &'static str },
+}
+
+/// The parts of the state that events are derived from
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Snapshot {
+    ambient_at: Option<i64>,
+    ambient: Option<f64>,
+    ambient_level: Option<f64>,
+    source: Option<Source>,
+    brightness: Option<f64>,
+    color_temp: Option<f64>,
+    period: Option<Period>,
+}
+
+impl Snapshot {
+    pub fn of(state: &State) -> Self {
+        Snapshot {
+            ambient_at: state.ambient_at,
+            ambient: state.ambient,
+            ambient_level: state.ambient_level,
+            source: state.source,
+            brightness: state.brightness,
+            color_temp: state.color_temp,
+            period: state.period,
+        }
+    }
+
+    /// Events that lead from this snapshot to `next`
+    pub fn changes(&self, next: &Snapshot) -> Vec<Event> {
+        let at = Utc::now().timestamp();
+        let mut events = Vec::new();
+
+        if (next.ambient_at, next.ambient_level, next.source) != (self.ambient_at, self.ambient_level, self.source) {
+            events.push(Event::Reading {
+                at,
+                ambient: next.ambient.filter(|_| matches!(next.source, Some(Source::Webcam | Source::Remembered))),
+                level: next.ambient_level,
+                source: next.source,
+            });
+        }
+
+        if let (Some(brightness), Some(color_temp)) = (next.brightness, next.color_temp) {
+            if (next.brightness, next.color_temp) != (self.brightness, self.color_temp) {
+                events.push(Event::Applied { at, brightness, color_temp });
+            }
+        }
+
+        if let Some(to) = next.period {
+            if next.period != self.period {
+                events.push(Event::Transition { at, from: self.period, to });
+            }
+        }
+
+        events
+    }
+}
@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+
+use crate::notify::{self, Urgency};
+
+/// Tag that makes each step's notification replace the previous one
+const STEP_TAG: &str = "healthy-monitor-eye-exercises";
+
+/// `[eye_exercises]` config section
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ExercisesConfig {
+    /// Allow the routine at all
+    pub enabled: bool,
+    /// Also run the routine after each break reminder
+    pub with_breaks: bool,
+    pub steps: Vec<Step>,
+}
+
+/// One instruction of the routine
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Step {
+    pub text: String,
+    pub seconds: f64,
+}
+
+impl Default for ExercisesConfig {
+    fn default() -> Self {
+        let step = |text: &str, seconds: f64| Step { text: text.to_string(), seconds };
+        ExercisesConfig {
+            enabled: true,
+            with_breaks: false,
+            steps: vec![
+                step("Look at something at least 6 m away", 20.0),
+                step("Hold a fingertip 15 cm from your nose and focus on it", 15.0),
+                step("Shift focus between far away and your fingertip, 10 times", 30.0),
+                step("Slowly roll your eyes clockwise 5 times, then counterclockwise", 30.0),
+                step("Palming: rub your hands warm and cup them over your closed eyes", 30.0),
+            ],
+        }
+    }
+}
+
+/// Steps through the routine, one notification per step
+pub async fn routine(config: &ExercisesConfig) -> Result<(), Box<dyn std::error::Error>> {
+    if !config.enabled {
+        return Err("Eye exercises are disabled in the config".into());
+    }
+
+    let total = config.steps.len();
+    for (i, step) in config.steps.iter().enumerate() {
+        let summary = format!("Eye exercise {}/{}", i + 1, total);
+        let body = format!("{} ({:.0}s)", step.text, step.seconds);
+        notify::send_tagged(&summary, &body, Urgency::Normal, STEP_TAG);
+        println!("{}: {}", summary, body);
+        sleep(Duration::from_secs_f64(step.seconds.max(0.0))).await;
+    }
+
+    notify::send_tagged("Eye exercises done", "Welcome back", Urgency::Low, STEP_TAG);
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use std::error::Error;
+use std::fmt;
+use std::process::ExitCode;
+
+/// What went wrong, told apart by the exit code so wrapper scripts and systemd `OnFailure=`
+/// units can react. Other errors exit with 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// No ambient light reading could be taken; the previous settings stay in place
+    Sensing = 2,
+    /// The display backend couldn't apply the settings
+    Display = 3,
+    /// The config file couldn't be read or parsed
+    Config = 4,
+    /// Another daemon is already running
+    AlreadyRunning = 5,
+}
+
+/// An error tagged with the exit code it should end the process with
+#[derive(Debug)]
+pub struct Exit {
+    pub failure: Failure,
+    source: Box<dyn Error>,
+}
+
+impl fmt::Display for Exit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl Error for Exit {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+/// Tags `error` with `failure`, keeping its message
+pub fn fail(failure: Failure, error: impl Into<Box<dyn Error>>) -> Box<dyn Error> {
+    Box::new(Exit { failure, source: error.into() })
+}
+
+/// The exit code for an error that ended the process
+pub fn code(error: &(dyn Error + 'static)) -> ExitCode {
+    match error.downcast_ref::<Exit>() {
+        Some(exit) => ExitCode::from(exit.failure as u8),
+        None => ExitCode::FAILURE,
+    }
+}
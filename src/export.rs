@@ -0,0 +1,105 @@
+use std::io::Write;
+
+use chrono::{Local, NaiveDate};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::history;
+use crate::screentime::{DayStats, ScreenTime};
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Format {
+    Csv,
+    Json,
+}
+
+/// One exported day
+#[derive(Debug, Serialize)]
+struct Row<'a> {
+    date: NaiveDate,
+    #[serde(flatten)]
+    stats: &'a DayStats,
+}
+
+/// Writes recorded update cycles between `from` and `to` (inclusive) to stdout
+pub fn export_cycles(from: Option<NaiveDate>, to: Option<NaiveDate>, format: Format) -> Result<(), Box<dyn std::error::Error>> {
+    let timestamp = |date: NaiveDate| date.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(Local).earliest();
+    let from = from.and_then(timestamp).map_or(0, |t| t.timestamp());
+    let to = to
+        .and_then(|to| to.succ_opt())
+        .and_then(timestamp)
+        .map_or(i64::MAX, |t| t.timestamp() - 1);
+    let cycles = history::cycles(from, to)?;
+
+    let mut out = std::io::stdout().lock();
+    match format {
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, &cycles)?;
+            writeln!(out)?;
+        }
+        Format::Csv => {
+            writeln!(out, "at,ambient,fused,brightness,color_temp,source")?;
+            for cycle in cycles {
+                writeln!(
+                    out,
+                    "{},{},{},{:.3},{:.0},{}",
+                    cycle.at,
+                    optional(cycle.ambient),
+                    optional(cycle.fused),
+                    cycle.brightness,
+                    cycle.color_temp,
+                    cycle.source.name(),
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes recorded daily stats between `from` and `to` (inclusive) to stdout
+pub fn export(from: Option<NaiveDate>, to: Option<NaiveDate>, format: Format) -> Result<(), Box<dyn std::error::Error>> {
+    let history = ScreenTime::load();
+    let rows: Vec<Row> = history
+        .days
+        .iter()
+        .filter(|(date, _)| from.map_or(true, |from| **date >= from) && to.map_or(true, |to| **date <= to))
+        .map(|(date, stats)| Row { date: *date, stats })
+        .collect();
+
+    let mut out = std::io::stdout().lock();
+    match format {
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, &rows)?;
+            writeln!(out)?;
+        }
+        Format::Csv => {
+            writeln!(
+                out,
+                "date,active_seconds,longest_stretch_seconds,breaks,bright_seconds,morning_bright_seconds,evening_blue_light,first_active_hour,last_active_hour"
+            )?;
+            for Row { date, stats } in rows {
+                writeln!(
+                    out,
+                    "{},{:.0},{:.0},{},{:.0},{:.0},{:.1},{},{}",
+                    date,
+                    stats.active_seconds,
+                    stats.longest_stretch_seconds,
+                    stats.breaks,
+                    stats.bright_seconds,
+                    stats.morning_bright_seconds,
+                    stats.evening_blue_light,
+                    optional(stats.first_active_hour.map(|h| (h * 100.0).round() / 100.0)),
+                    optional(stats.last_active_hour.map(|h| (h * 100.0).round() / 100.0)),
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Empty CSV field for missing values
+fn optional(value: Option<f64>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use tokio::time::sleep;
+
+/// Time between intermediate steps of a fade
+const STEP: Duration = Duration::from_millis(50);
+
+/// Moves from one brightness/temperature pair to another over `duration`.
+///
+/// With `panel_bits` set, each intermediate brightness is temporally dithered
+/// between the two nearest levels the panel can show, alternating them over
+/// successive steps so the average follows the fade instead of visibly stepping.
+pub async fn fade(
+    from: (f64, f64),
+    to: (f64, f64),
+    duration: Duration,
+    panel_bits: Option<u32>,
+    mut apply: impl FnMut(f64, f64) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let steps = (duration.as_secs_f64() / STEP.as_secs_f64()).ceil().max(1.0) as u32;
+    let mut dither = Dither::new(panel_bits);
+
+    for i in 1..=steps {
+        let t = i as f64 / steps as f64;
+        let brightness = from.0 + (to.0 - from.0) * t;
+        let temp = from.1 + (to.1 - from.1) * t;
+
+        // Land exactly on the target at the end
+        let brightness = if i == steps { brightness } else { dither.next(brightness) };
+        apply(brightness, temp)?;
+
+        if i < steps {
+            sleep(STEP).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Error-diffusion dithering onto the panel's discrete brightness levels
+struct Dither {
+    levels: Option<f64>,
+    error: f64,
+}
+
+impl Dither {
+    fn new(panel_bits: Option<u32>) -> Self {
+        // 0 bits would leave no level to divide by; `config check` reports it
+        let levels = panel_bits.map(|bits| ((1u64 << bits.clamp(1, 16)) - 1) as f64);
+        Dither { levels, error: 0.0 }
+    }
+
+    fn next(&mut self, value: f64) -> f64 {
+        let Some(levels) = self.levels else {
+            return value;
+        };
+
+        let wanted = value * levels + self.error;
+        let level = wanted.round().clamp(0.0, levels);
+        self.error = wanted - level;
+
+        level / levels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn without_panel_bits() {
+        let mut dither = Dither::new(None);
+        assert_eq!(dither.next(0.123), 0.123);
+        assert_eq!(dither.next(0.456), 0.456);
+    }
+
+    #[test]
+    fn error_carries_to_the_next_step() {
+        // Two bits: levels at thirds, so a half alternates between 2/3 and 1/3
+        let mut dither = Dither::new(Some(2));
+        assert!(close(dither.next(0.5), 2.0 / 3.0));
+        assert!(close(dither.next(0.5), 1.0 / 3.0));
+        assert!(close(dither.next(0.5), 2.0 / 3.0));
+    }
+
+    #[test]
+    fn average_matches_the_value() {
+        let mut dither = Dither::new(Some(8));
+        let steps = 1000;
+        let outputs: Vec<f64> = (0..steps).map(|_| dither.next(0.3001)).collect();
+
+        assert!(outputs.iter().all(|v| close((v * 255.0).round(), v * 255.0)));
+        let mean = outputs.iter().sum::<f64>() / steps as f64;
+        assert!((mean - 0.3001).abs() < 1.0 / 255.0 / steps as f64 * 2.0);
+    }
+
+    #[test]
+    fn levels_stay_in_range() {
+        let mut dither = Dither::new(Some(0));
+        assert_eq!(dither.levels, Some(1.0));
+        assert_eq!(dither.next(-0.2), 0.0);
+
+        let mut dither = Dither::new(Some(8));
+        assert_eq!(dither.next(1.2), 1.0);
+        assert_eq!(dither.next(1.0), 1.0);
+    }
+}
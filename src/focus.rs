@@ -0,0 +1,140 @@
+use std::process::Command;
+
+use serde_json::Value;
+
+use crate::config::Config;
+
+/// What the focused window means for the applied settings
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Focus {
+    /// The focused app is configured as an exception
+    pub exempt: bool,
+    /// Profile mapped to the focused app
+    pub profile: Option<String>,
+    /// The focused window is fullscreen
+    pub fullscreen: bool,
+}
+
+/// The focused window as reported by the window manager
+#[derive(Debug, Default)]
+pub struct Window {
+    /// WM_CLASS instance and class, or the Wayland app id
+    pub classes: Vec<String>,
+    pub fullscreen: bool,
+}
+
+/// Whether focus needs to be checked at all with this config
+pub fn watched(config: &Config) -> bool {
+    !config.exceptions.is_empty() || !config.app_profiles.is_empty() || config.pause_during_playback
+}
+
+/// Matches the focused window against the configured exceptions and app profiles
+pub fn check(config: &Config) -> Focus {
+    if !watched(config) {
+        return Focus::default();
+    }
+
+    let Some(window) = active_window() else {
+        return Focus::default();
+    };
+    let matches = |app: &str| window.classes.iter().any(|class| class.eq_ignore_ascii_case(app));
+
+    Focus {
+        exempt: config.exceptions.iter().any(|app| matches(app)),
+        // The window's own order decides between its instance and class, and the map's order
+        // between keys differing only in case
+        profile: window
+            .classes
+            .iter()
+            .find_map(|class| config.app_profiles.iter().find(|(app, _)| class.eq_ignore_ascii_case(app)))
+            .map(|(_, profile)| profile.clone()),
+        fullscreen: window.fullscreen,
+    }
+}
+
+/// Queries the focused window through compositor IPC on Wayland, or EWMH on X11
+pub fn active_window() -> Option<Window> {
+    if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        hyprland_active_window()
+    } else if std::env::var_os("SWAYSOCK").is_some() {
+        sway_active_window()
+    } else {
+        x11_active_window()
+    }
+}
+
+fn hyprland_active_window() -> Option<Window> {
+    let window: Value = serde_json::from_slice(&command_output("hyprctl", &["activewindow", "-j"])?).ok()?;
+    let class = window.get("class")?.as_str()?;
+
+    // Older versions report a bool, newer ones a fullscreen mode number
+    let fullscreen = match window.get("fullscreen") {
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Number(n)) => n.as_u64().unwrap_or(0) > 0,
+        _ => false,
+    };
+
+    Some(Window { classes: vec![class.to_string()], fullscreen })
+}
+
+fn sway_active_window() -> Option<Window> {
+    let tree: Value = serde_json::from_slice(&command_output("swaymsg", &["-t", "get_tree"])?).ok()?;
+    let node = find_focused(&tree)?;
+
+    let mut classes = Vec::new();
+    if let Some(app_id) = node.get("app_id").and_then(Value::as_str) {
+        classes.push(app_id.to_string());
+    }
+    // XWayland windows report their X11 class instead of an app id
+    if let Some(class) = node.pointer("/window_properties/class").and_then(Value::as_str) {
+        classes.push(class.to_string());
+    }
+
+    let fullscreen = node.get("fullscreen_mode").and_then(Value::as_u64).unwrap_or(0) > 0;
+
+    Some(Window { classes, fullscreen })
+}
+
+fn find_focused(node: &Value) -> Option<&Value> {
+    if node.get("focused").and_then(Value::as_bool) == Some(true) {
+        return Some(node);
+    }
+
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node.get(*key).and_then(Value::as_array))
+        .flatten()
+        .find_map(find_focused)
+}
+
+fn x11_active_window() -> Option<Window> {
+    // _NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007
+    let root = String::from_utf8(command_output("xprop", &["-root", "_NET_ACTIVE_WINDOW"])?).ok()?;
+    let id = root.split_whitespace().last()?.to_string();
+
+    // WM_CLASS(STRING) = "Navigator", "firefox"
+    // _NET_WM_STATE(ATOM) = _NET_WM_STATE_FULLSCREEN
+    let props = String::from_utf8(command_output("xprop", &["-id", &id, "WM_CLASS", "_NET_WM_STATE"])?).ok()?;
+
+    let classes = props
+        .lines()
+        .find(|line| line.starts_with("WM_CLASS"))
+        .and_then(|line| line.split_once('='))
+        .map(|(_, values)| {
+            values
+                .split(',')
+                .map(|v| v.trim().trim_matches('"').to_string())
+                .filter(|v| !v.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let fullscreen = props.contains("_NET_WM_STATE_FULLSCREEN");
+
+    Some(Window { classes, fullscreen })
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<Vec<u8>> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output.status.success().then_some(output.stdout)
+}
@@ -0,0 +1,48 @@
+use futures_util::StreamExt;
+use tokio::sync::watch;
+use zbus::{proxy, Connection};
+
+#[proxy(
+    interface = "com.feralinteractive.GameMode",
+    default_service = "com.feralinteractive.GameMode",
+    default_path = "/com/feralinteractive/GameMode"
+)]
+trait GameMode {
+    /// Number of games that requested GameMode
+    #[zbus(property)]
+    fn client_count(&self) -> zbus::Result<i32>;
+}
+
+/// Whether gamemoded reports a running game; false when it isn't running
+pub async fn active() -> bool {
+    async {
+        let connection = Connection::session().await?;
+        GameModeProxy::new(&connection).await?.client_count().await
+    }
+    .await
+    .map_or(false, |count: i32| count > 0)
+}
+
+/// Follows whether a game is running in the background, so its exit can be acted on right away
+pub async fn watch() -> watch::Receiver<bool> {
+    let (sender, receiver) = watch::channel(active().await);
+
+    tokio::spawn(async move {
+        let result: zbus::Result<()> = async {
+            let connection = Connection::session().await?;
+            let gamemode = GameModeProxy::new(&connection).await?;
+            let mut changes = gamemode.receive_client_count_changed().await;
+            while let Some(change) = changes.next().await {
+                sender.send_replace(change.get().await? > 0);
+            }
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            eprintln!("GameMode state unavailable: {}", e);
+        }
+    });
+
+    receiver
+}
@@ -0,0 +1,103 @@
+use std::collections::{BTreeMap, HashSet};
+
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::history;
+use crate::screentime::{format_duration, DayStats, ScreenTime};
+
+/// `[goals]` config section: a daily target that counts toward a streak when met
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GoalsConfig {
+    /// Breaks to take each day
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breaks: Option<u32>,
+    /// Most screen time per day (hours)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_screen_hours: Option<f64>,
+}
+
+impl GoalsConfig {
+    /// Whether a day with `stats` meets every configured target
+    pub fn met(&self, stats: &DayStats) -> bool {
+        self.breaks.map_or(true, |breaks| stats.breaks >= breaks)
+            && self.max_screen_hours.map_or(true, |hours| stats.active_seconds <= hours * 3600.0)
+    }
+
+    /// Today's progress toward the targets, e.g. "5/8 breaks, 3h 10m of 10h 00m"
+    pub fn progress(&self, stats: &DayStats) -> String {
+        let mut parts = Vec::new();
+        if let Some(breaks) = self.breaks {
+            parts.push(format!("{}/{} breaks", stats.breaks, breaks));
+        }
+        if let Some(hours) = self.max_screen_hours {
+            parts.push(format!(
+                "{} of {} screen time",
+                format_duration(stats.active_seconds),
+                format_duration(hours * 3600.0)
+            ));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Consecutive days the goal was met
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Streak {
+    /// Completed days in a row, not counting today
+    pub days: u32,
+    /// Today's targets are met so far
+    pub today: bool,
+}
+
+/// Records the outcome of past days with screen use that aren't in the history yet.
+/// Outcomes already recorded keep the goal that was in place at the time.
+pub fn update(config: &GoalsConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let today = Local::now().date_naive();
+    let recorded: HashSet<NaiveDate> = history::goal_days()?.into_iter().map(|(day, _)| day).collect();
+
+    for (day, stats) in ScreenTime::load().days.range(..today) {
+        if stats.active_seconds > 0.0 && !recorded.contains(day) {
+            history::record_goal(*day, config.met(stats))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The current streak. Days without screen use neither extend nor break it. Past days the
+/// daemon hasn't recorded yet count with today's goal, without being recorded here.
+pub fn streak(config: &GoalsConfig) -> Result<Streak, Box<dyn std::error::Error>> {
+    let today = Local::now().date_naive();
+    let history = ScreenTime::load();
+    let mut outcomes: BTreeMap<NaiveDate, bool> = history::goal_days()?.into_iter().collect();
+    for (day, stats) in history.days.range(..today) {
+        if stats.active_seconds > 0.0 {
+            outcomes.entry(*day).or_insert_with(|| config.met(stats));
+        }
+    }
+
+    let days = outcomes.values().rev().take_while(|met| **met).count() as u32;
+    Ok(Streak { days, today: config.met(&history.day(today)) })
+}
+
+/// Prints the streak and today's progress
+pub fn report(config: &GoalsConfig, date: NaiveDate) -> Result<(), Box<dyn std::error::Error>> {
+    let streak = streak(config)?;
+
+    println!("  Goal:            {}", config.progress(&ScreenTime::load().day(date)));
+    println!("  Streak:          {}", describe(streak));
+
+    Ok(())
+}
+
+/// e.g. "4 days, today on track"
+pub fn describe(streak: Streak) -> String {
+    format!(
+        "{} day{}, today {}",
+        streak.days,
+        if streak.days == 1 { "" } else { "s" },
+        if streak.today { "on track" } else { "not met" }
+    )
+}
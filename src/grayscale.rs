@@ -0,0 +1,35 @@
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use crate::schedule::TimeOfDay;
+use crate::state::State;
+
+/// Late-night grayscale window
+///
+/// Gamma ramps only scale each channel independently and can't mix colors,
+/// so desaturation is delegated to the compositor through shell commands.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GrayscaleConfig {
+    pub start: TimeOfDay,
+    pub end: TimeOfDay,
+    /// Command enabling grayscale, e.g. loading a compositor shader
+    pub on_command: String,
+    /// Command restoring normal colors
+    pub off_command: String,
+}
+
+/// Switches grayscale on or off when `hour` enters or leaves the configured window
+pub fn update(config: &GrayscaleConfig, state: &mut State, hour: f64) {
+    let active = TimeOfDay::window_contains(config.start, config.end, hour);
+    if active == state.grayscale {
+        return;
+    }
+
+    let command = if active { &config.on_command } else { &config.off_command };
+    match Command::new("sh").args(["-c", command]).status() {
+        Ok(status) if status.success() => state.grayscale = active,
+        Ok(status) => eprintln!("Grayscale command '{}' failed: {}", command, status),
+        Err(e) => eprintln!("Failed to run grayscale command '{}': {}", command, e),
+    }
+}
@@ -0,0 +1,184 @@
+use crate::Args;
+
+/// Opens the settings window, editing the config at `args.config_path()`
+#[cfg(feature = "gui")]
+pub fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    window::run(args)
+}
+
+#[cfg(not(feature = "gui"))]
+pub fn run(_args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    Err("healthy-monitor was built without the `gui` feature".into())
+}
+
+#[cfg(feature = "gui")]
+mod window {
+    use std::path::PathBuf;
+
+    use eframe::egui;
+
+    use crate::config::Config;
+    use crate::state::State;
+    use crate::Args;
+
+    /// Values editable in the window, starting from the config merged with the defaults
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Values {
+        day_temp: f64,
+        night_temp: f64,
+        min_brightness: f64,
+        night_min_brightness: f64,
+        max_brightness: f64,
+    }
+
+    struct Settings {
+        args: Args,
+        path: PathBuf,
+        config: Config,
+        /// As loaded, to only write what was changed
+        initial: Values,
+        values: Values,
+        /// Detected outputs and whether they are adjusted
+        monitors: Vec<(String, bool)>,
+        previewing: bool,
+        message: String,
+    }
+
+    pub fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+        let path = args.config_path()?;
+        let config = Config::load_for_update(&path)?;
+        let values = Values {
+            day_temp: args.day_temp,
+            night_temp: args.night_temp,
+            min_brightness: args.min_brightness,
+            night_min_brightness: args.night_min_brightness.unwrap_or(args.min_brightness),
+            max_brightness: args.max_brightness,
+        };
+        let monitors = crate::detect_monitors()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|name| {
+                let enabled = config.monitors.as_ref().map_or(true, |m| m.contains(&name));
+                (name, enabled)
+            })
+            .collect();
+
+        let settings = Settings {
+            args: args.clone(),
+            path,
+            config,
+            initial: values,
+            values,
+            monitors,
+            previewing: false,
+            message: String::new(),
+        };
+        let options = eframe::NativeOptions {
+            viewport: egui::ViewportBuilder::default().with_inner_size([380.0, 420.0]),
+            ..Default::default()
+        };
+        eframe::run_native("healthy-monitor", options, Box::new(|_| Box::new(settings)))?;
+        Ok(())
+    }
+
+    impl Settings {
+        /// Applies `brightness` and `temp` to the checked monitors until the preview is stopped
+        fn preview(&mut self, brightness: f64, temp: f64) {
+            let mut args = self.args.clone();
+            args.monitors = Some(self.checked());
+            args.max_brightness = self.values.max_brightness;
+            self.message = match crate::set_monitor_brightness(brightness, temp, &args, &self.config) {
+                Ok(()) => format!("Previewing {:.0}K at {:.0}%", temp, brightness * 100.0),
+                Err(e) => format!("Preview failed: {}", e),
+            };
+            self.previewing = true;
+        }
+
+        /// Puts the last automatically applied settings back
+        fn stop_preview(&mut self) {
+            let state = State::load();
+            let brightness = state.brightness.unwrap_or(self.initial.max_brightness);
+            let temp = state.color_temp.unwrap_or(self.initial.day_temp);
+            if let Err(e) = crate::set_monitor_brightness(brightness, temp, &self.args, &self.config) {
+                self.message = format!("Restoring failed: {}", e);
+                return;
+            }
+            self.previewing = false;
+            self.message.clear();
+        }
+
+        fn checked(&self) -> Vec<String> {
+            self.monitors.iter().filter(|(_, enabled)| *enabled).map(|(name, _)| name.clone()).collect()
+        }
+
+        fn save(&mut self) {
+            let (v, initial) = (self.values, self.initial);
+            // Every detected monitor checked means all of them, including ones connected later
+            let monitors = if self.monitors.iter().all(|(_, enabled)| *enabled) { None } else { Some(self.checked()) };
+            let changed = |value: f64, was: f64| (value != was).then_some(value);
+            let config = &mut self.config;
+            config.day_temp = changed(v.day_temp, initial.day_temp).or(config.day_temp);
+            config.night_temp = changed(v.night_temp, initial.night_temp).or(config.night_temp);
+            config.min_brightness = changed(v.min_brightness, initial.min_brightness).or(config.min_brightness);
+            config.night_min_brightness =
+                changed(v.night_min_brightness, initial.night_min_brightness).or(config.night_min_brightness);
+            config.max_brightness = changed(v.max_brightness, initial.max_brightness).or(config.max_brightness);
+            config.monitors = monitors;
+
+            self.message = match config.save(&self.path) {
+                Ok(()) => format!("Saved to {}", self.path.display()),
+                Err(e) => format!("Saving failed: {}", e),
+            };
+        }
+    }
+
+    impl eframe::App for Settings {
+        fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.heading("Color temperature");
+                ui.add(egui::Slider::new(&mut self.values.day_temp, 1000.0..=10000.0).suffix(" K").text("Day"));
+                ui.add(egui::Slider::new(&mut self.values.night_temp, 1000.0..=10000.0).suffix(" K").text("Night"));
+
+                ui.separator();
+                ui.heading("Brightness");
+                ui.add(egui::Slider::new(&mut self.values.min_brightness, 0.0..=1.0).text("Minimum"));
+                ui.add(egui::Slider::new(&mut self.values.night_min_brightness, 0.0..=1.0).text("Minimum at night"));
+                ui.add(egui::Slider::new(&mut self.values.max_brightness, 0.0..=1.0).text("Maximum"));
+
+                ui.separator();
+                ui.heading("Monitors");
+                if self.monitors.is_empty() {
+                    ui.label("No monitors detected");
+                }
+                for (name, enabled) in &mut self.monitors {
+                    ui.checkbox(enabled, name.as_str());
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Preview day").clicked() {
+                        self.preview(self.values.max_brightness, self.values.day_temp);
+                    }
+                    if ui.button("Preview night").clicked() {
+                        self.preview(self.values.night_min_brightness, self.values.night_temp);
+                    }
+                    if ui.add_enabled(self.previewing, egui::Button::new("Stop preview")).clicked() {
+                        self.stop_preview();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        self.save();
+                    }
+                    ui.label(&self.message);
+                });
+            });
+        }
+
+        fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+            if self.previewing {
+                self.stop_preview();
+            }
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use chrono::{Local, NaiveDate, Utc};
+use rusqlite::types::Type;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::screentime::DayStats;
+use crate::state;
+
+/// Days of cycles and screen time kept unless configured otherwise
+const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// Where the brightness of a cycle came from
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// A fresh webcam reading
+    Webcam,
+    /// The last webcam reading while the camera was busy, blended with weather data
+    Remembered,
+    /// The ambient light sensor
+    Sensor,
+    /// Weather data and the time of day
+    Weather,
+    /// Fixed brightness of the active profile
+    Profile,
+    /// Darkroom mode
+    Darkroom,
+    /// Neutral colors for an exception app in focus
+    Exempt,
+    /// Neutral colors while the screen is shared
+    Sharing,
+    /// The winter morning light boost
+    Boost,
+    /// The last settings, held through a busy calendar event
+    Calendar,
+}
+
+impl Source {
+    pub fn name(self) -> &'static str {
+        match self {
+            Source::Webcam => "webcam",
+            Source::Remembered => "remembered",
+            Source::Sensor => "sensor",
+            Source::Weather => "weather",
+            Source::Profile => "profile",
+            Source::Darkroom => "darkroom",
+            Source::Exempt => "exempt",
+            Source::Sharing => "sharing",
+            Source::Boost => "boost",
+            Source::Calendar => "calendar",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [
+            Source::Webcam,
+            Source::Remembered,
+            Source::Sensor,
+            Source::Weather,
+            Source::Profile,
+            Source::Darkroom,
+            Source::Exempt,
+            Source::Sharing,
+            Source::Boost,
+            Source::Calendar,
+        ]
+        .into_iter()
+        .find(|source| source.name() == name)
+    }
+}
+
+/// One update cycle as recorded in the history database
+#[derive(Debug, Clone, Serialize)]
+pub struct Cycle {
+    /// Unix timestamp
+    pub at: i64,
+    /// Webcam reading the cycle was based on [0.0..1.0]
+    pub ambient: Option<f64>,
+    /// Ambient level after falling back or blending [0.0..1.0]
+    pub fused: Option<f64>,
+    pub brightness: f64,
+    pub color_temp: f64,
+    pub source: Source,
+}
+
+/// Days of history to keep, 0 for none
+pub fn retention_days(config: &Config) -> u32 {
+    config.history_days.unwrap_or(DEFAULT_RETENTION_DAYS)
+}
+
+/// Appends `cycle` to the history and drops cycles past the retention period.
+/// Does nothing when `history_days` is 0.
+pub fn record(config: &Config, cycle: &Cycle) -> Result<(), Box<dyn std::error::Error>> {
+    let retention_days = retention_days(config);
+    if retention_days == 0 {
+        return Ok(());
+    }
+
+    let db = open()?;
+    db.execute(
+        "INSERT INTO cycles (at, ambient, fused, brightness, color_temp, source) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![cycle.at, cycle.ambient, cycle.fused, cycle.brightness, cycle.color_temp, cycle.source.name()],
+    )?;
+
+    let cutoff = Utc::now().timestamp() - retention_days as i64 * 86400;
+    db.execute("DELETE FROM cycles WHERE at < ?1", params![cutoff])?;
+
+    Ok(())
+}
+
+/// Cycles recorded between the Unix timestamps `from` and `to`, oldest first
+pub fn cycles(from: i64, to: i64) -> Result<Vec<Cycle>, Box<dyn std::error::Error>> {
+    let db = open()?;
+    let mut query = db.prepare(
+        "SELECT at, ambient, fused, brightness, color_temp, source FROM cycles WHERE at BETWEEN ?1 AND ?2 ORDER BY at",
+    )?;
+
+    let rows = query.query_map(params![from, to], |row| {
+        let name: String = row.get(5)?;
+        let source = Source::from_name(&name).ok_or_else(|| {
+            rusqlite::Error::FromSqlConversionFailure(5, Type::Text, format!("unknown source '{}'", name).into())
+        })?;
+        Ok(Cycle {
+            at: row.get(0)?,
+            ambient: row.get(1)?,
+            fused: row.get(2)?,
+            brightness: row.get(3)?,
+            color_temp: row.get(4)?,
+            source,
+        })
+    })?;
+
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// Screen time per day, oldest first
+pub fn days() -> Result<BTreeMap<NaiveDate, DayStats>, Box<dyn std::error::Error>> {
+    let db = open()?;
+    let mut query = db.prepare(
+        "SELECT day, active_seconds, longest_stretch_seconds, breaks, bright_seconds, morning_bright_seconds,
+            evening_blue_light, first_active_hour, last_active_hour FROM days ORDER BY day",
+    )?;
+    let rows = query.query_map([], |row| {
+        let stats = DayStats {
+            active_seconds: row.get(1)?,
+            longest_stretch_seconds: row.get(2)?,
+            breaks: row.get(3)?,
+            bright_seconds: row.get(4)?,
+            morning_bright_seconds: row.get(5)?,
+            evening_blue_light: row.get(6)?,
+            first_active_hour: row.get(7)?,
+            last_active_hour: row.get(8)?,
+        };
+        Ok((row.get::<_, String>(0)?, stats))
+    })?;
+
+    let mut days = BTreeMap::new();
+    for row in rows {
+        let (day, stats) = row?;
+        if let Ok(day) = day.parse() {
+            days.insert(day, stats);
+        }
+    }
+    Ok(days)
+}
+
+/// Stores the screen time of `days`, replacing earlier entries, and drops days past the retention
+/// period. Does nothing when the retention period is 0 days.
+pub fn record_days<'a>(
+    retention_days: u32,
+    days: impl IntoIterator<Item = (&'a NaiveDate, &'a DayStats)>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if retention_days == 0 {
+        return Ok(());
+    }
+
+    let mut db = open()?;
+    let transaction = db.transaction()?;
+    insert_days(&transaction, days)?;
+    let cutoff = Local::now().date_naive() - chrono::Duration::days(retention_days as i64);
+    transaction.execute("DELETE FROM days WHERE day < ?1", params![cutoff.to_string()])?;
+    transaction.commit()?;
+
+    Ok(())
+}
+
+/// Copies screen time from the JSON file it used to be kept in, leaving pruning to the next
+/// `record_days`
+pub fn import_days(days: &BTreeMap<NaiveDate, DayStats>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut db = open()?;
+    let transaction = db.transaction()?;
+    insert_days(&transaction, days)?;
+    transaction.commit()?;
+    Ok(())
+}
+
+fn insert_days<'a>(
+    db: &Connection,
+    days: impl IntoIterator<Item = (&'a NaiveDate, &'a DayStats)>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut insert = db.prepare(
+        "INSERT OR REPLACE INTO days (day, active_seconds, longest_stretch_seconds, breaks, bright_seconds,
+            morning_bright_seconds, evening_blue_light, first_active_hour, last_active_hour)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+    )?;
+    for (day, stats) in days {
+        insert.execute(params![
+            day.to_string(),
+            stats.active_seconds,
+            stats.longest_stretch_seconds,
+            stats.breaks,
+            stats.bright_seconds,
+            stats.morning_bright_seconds,
+            stats.evening_blue_light,
+            stats.first_active_hour,
+            stats.last_active_hour,
+        ])?;
+    }
+    Ok(())
+}
+
+/// Stores whether the daily goal was met on `day`, replacing an earlier entry
+pub fn record_goal(day: NaiveDate, met: bool) -> Result<(), Box<dyn std::error::Error>> {
+    open()?.execute(
+        "INSERT OR REPLACE INTO goals (day, met) VALUES (?1, ?2)",
+        params![day.to_string(), met],
+    )?;
+    Ok(())
+}
+
+/// Days with a recorded goal outcome, newest first
+pub fn goal_days() -> Result<Vec<(NaiveDate, bool)>, Box<dyn std::error::Error>> {
+    let db = open()?;
+    let mut query = db.prepare("SELECT day, met FROM goals ORDER BY day DESC")?;
+    let rows = query.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?)))?;
+
+    let mut days = Vec::new();
+    for row in rows {
+        let (day, met) = row?;
+        if let Ok(day) = day.parse() {
+            days.push((day, met));
+        }
+    }
+    Ok(days)
+}
+
+fn open() -> Result<Connection, Box<dyn std::error::Error>> {
+    let path = path().ok_or("Cannot determine the history database location")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let db = Connection::open(&path)?;
+    db.execute_batch(
+        "CREATE TABLE IF NOT EXISTS cycles (
+            at INTEGER NOT NULL,
+            ambient REAL,
+            fused REAL,
+            brightness REAL NOT NULL,
+            color_temp REAL NOT NULL,
+            source TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS cycles_at ON cycles (at);
+        CREATE TABLE IF NOT EXISTS goals (
+            day TEXT PRIMARY KEY,
+            met INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS days (
+            day TEXT PRIMARY KEY,
+            active_seconds REAL NOT NULL,
+            longest_stretch_seconds REAL NOT NULL,
+            breaks INTEGER NOT NULL,
+            bright_seconds REAL NOT NULL,
+            morning_bright_seconds REAL NOT NULL,
+            evening_blue_light REAL NOT NULL,
+            first_active_hour REAL,
+            last_active_hour REAL
+        );",
+    )?;
+
+    Ok(db)
+}
+
+/// `$XDG_STATE_HOME/healthy-monitor/history.db`
+fn path() -> Option<PathBuf> {
+    Some(state::state_dir()?.join("history.db"))
+}
@@ -0,0 +1,95 @@
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::daemon::Command;
+use crate::mqtt::MqttConfig;
+
+/// Color temperature range offered in Home Assistant (mireds, 6500 K to 1900 K)
+const MIN_MIREDS: u32 = 153;
+const MAX_MIREDS: u32 = 526;
+
+/// Payload of a light command from Home Assistant (JSON schema)
+#[derive(Debug, Deserialize)]
+struct LightCommand {
+    /// Percent, see `brightness_scale`
+    brightness: Option<f64>,
+    /// Mireds
+    color_temp: Option<f64>,
+}
+
+/// Topic Home Assistant sends light commands to
+pub fn command_topic(config: &MqttConfig) -> String {
+    config.topic("light/set")
+}
+
+/// Topic the daemon's online/offline status is published on
+pub fn availability_topic(config: &MqttConfig) -> String {
+    config.topic("availability")
+}
+
+/// Retained discovery messages that make the monitors show up as a light and the
+/// webcam as an ambient light sensor
+pub fn discovery(config: &MqttConfig) -> Vec<(String, String)> {
+    let node = config.client_id.replace(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '-', "_");
+    let device = json!({
+        "identifiers": [node],
+        "name": "healthy-monitor",
+        "manufacturer": "healthy-monitor",
+        "sw_version": env!("CARGO_PKG_VERSION"),
+    });
+    let availability = availability_topic(config);
+
+    let light = json!({
+        "name": "Monitor",
+        "unique_id": format!("{}_monitor", node),
+        "schema": "json",
+        "command_topic": command_topic(config),
+        "state_topic": config.topic("light/state"),
+        "availability_topic": availability.clone(),
+        "brightness": true,
+        "brightness_scale": 100,
+        "supported_color_modes": ["color_temp"],
+        "min_mireds": MIN_MIREDS,
+        "max_mireds": MAX_MIREDS,
+        "device": device.clone(),
+    });
+    let sensor = json!({
+        "name": "Ambient light",
+        "unique_id": format!("{}_ambient", node),
+        "state_topic": config.topic("ambient"),
+        "availability_topic": availability,
+        "unit_of_measurement": "%",
+        "value_template": "{{ (value | float * 100) | round(0) }}",
+        "state_class": "measurement",
+        "device": device,
+    });
+
+    vec![
+        (format!("{}/light/{}/monitor/config", config.discovery_prefix, node), light.to_string()),
+        (format!("{}/sensor/{}/ambient/config", config.discovery_prefix, node), sensor.to_string()),
+    ]
+}
+
+/// Light state for Home Assistant
+pub fn light_state(brightness: f64, color_temp: f64) -> String {
+    json!({
+        "state": "ON",
+        "color_mode": "color_temp",
+        "brightness": (brightness * 100.0).round(),
+        "color_temp": (1e6 / color_temp).round(),
+    })
+    .to_string()
+}
+
+/// Overrides requested by a light command. Switching the light off isn't supported,
+/// the monitors stay on.
+pub fn commands(payload: &[u8]) -> Result<Vec<Command>, Box<dyn std::error::Error>> {
+    let command: LightCommand = serde_json::from_slice(payload)?;
+
+    Ok(command
+        .color_temp
+        .map(|mireds| Command::SetTemperature((1e6 / mireds).round()))
+        .into_iter()
+        .chain(command.brightness.map(|percent| Command::SetBrightness(percent / 100.0)))
+        .collect())
+}
@@ -0,0 +1,152 @@
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::config::{self, Config};
+use crate::schedule::Period;
+
+/// Lifecycle events `[[event_hooks]]` can run on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookEvent {
+    /// New brightness and temperature were applied by an update
+    Applied,
+    NightStarted,
+    NightEnded,
+    /// The webcam stopped delivering readings and weather data is used instead
+    SensorFallback,
+    BreakStarted,
+}
+
+impl HookEvent {
+    fn name(self) -> &'static str {
+        match self {
+            HookEvent::Applied => "applied",
+            HookEvent::NightStarted => "night-started",
+            HookEvent::NightEnded => "night-ended",
+            HookEvent::SensorFallback => "sensor-fallback",
+            HookEvent::BreakStarted => "break-started",
+        }
+    }
+}
+
+/// A `[[event_hooks]]` entry: an executable run on some of the lifecycle events
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EventHookConfig {
+    pub on: Vec<HookEvent>,
+    pub command: PathBuf,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+}
+
+/// Starts the hooks configured for `event` without waiting for them. The details go to
+/// stdin as a JSON object, and each of them also to a `HEALTHY_MONITOR_<NAME>` variable.
+pub fn fire(config: &Config, event: HookEvent, details: Value) {
+    let hooks: Vec<_> = config.event_hooks.iter().filter(|h| h.on.contains(&event)).collect();
+    if hooks.is_empty() {
+        return;
+    }
+
+    let mut payload = json!({ "event": event.name(), "at": chrono::Utc::now().timestamp() });
+    if let (Some(payload), Value::Object(details)) = (payload.as_object_mut(), details) {
+        payload.extend(details);
+    }
+
+    for hook in hooks {
+        let mut command = Command::new(&hook.command);
+        command.args(&hook.args).stdin(Stdio::piped());
+        for (name, value) in payload.as_object().into_iter().flatten() {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            command.env(format!("HEALTHY_MONITOR_{}", name.to_uppercase()), value);
+        }
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                eprintln!("Failed to run hook {}: {}", hook.command.display(), e);
+                continue;
+            }
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            // A hook that doesn't read its input closes the pipe early, which is fine
+            let _ = stdin.write_all(payload.to_string().as_bytes());
+        }
+
+        // Reaped in the background so a slow hook doesn't hold up the update
+        let path = hook.command.clone();
+        std::thread::spawn(move || match child.wait() {
+            Ok(status) if !status.success() => eprintln!("Hook {} failed: {}", path.display(), status),
+            Err(e) => eprintln!("Failed to wait for hook {}: {}", path.display(), e),
+            _ => {}
+        });
+    }
+}
+
+/// Starts every executable in the hooks directory with redshift's `period-changed` arguments,
+/// without waiting for them. The applied brightness and temperature are passed as environment variables.
+pub fn period_changed(config: &Config, prev: Option<Period>, new: Period, brightness: f64, color_temp: f64) {
+    let Some(dir) = hooks_dir(config) else {
+        return;
+    };
+
+    let prev = prev.map_or("none", Period::name);
+
+    for hook in executables(&dir) {
+        let spawned = Command::new(&hook)
+            .args(["period-changed", prev, new.name()])
+            .env("HEALTHY_MONITOR_BRIGHTNESS", format!("{:.3}", brightness))
+            .env("HEALTHY_MONITOR_TEMPERATURE", format!("{:.0}", color_temp))
+            .spawn();
+
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(e) => {
+                eprintln!("Failed to run hook {}: {}", hook.display(), e);
+                continue;
+            }
+        };
+
+        // Reaped in the background like event hooks, so a slow one doesn't hold up the update
+        std::thread::spawn(move || match child.wait() {
+            Ok(status) if !status.success() => eprintln!("Hook {} failed: {}", hook.display(), status),
+            Err(e) => eprintln!("Failed to wait for hook {}: {}", hook.display(), e),
+            _ => {}
+        });
+    }
+}
+
+/// Configured hooks directory, or `~/.config/healthy-monitor/hooks`
+fn hooks_dir(config: &Config) -> Option<PathBuf> {
+    config
+        .hooks_dir
+        .clone()
+        .or_else(|| Some(config::config_home()?.join("healthy-monitor").join("hooks")))
+}
+
+/// Executable files in `dir`, sorted by name so hooks run in a predictable order
+fn executables(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut hooks: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            // Follow symlinks so hooks linked from redshift's directory work
+            std::fs::metadata(entry.path())
+                .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+                .unwrap_or(false)
+        })
+        .map(|entry| entry.path())
+        .collect();
+
+    hooks.sort();
+    hooks
+}
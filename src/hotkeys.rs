@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use clap::ValueEnum;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::watch;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+use zbus::proxy;
+
+use crate::daemon::Command;
+use crate::portal::{await_response, Portal};
+use crate::status::Status;
+
+/// `[hotkeys]` config section: global shortcuts registered through the desktop portal in daemon mode.
+/// The triggers are only suggestions, the desktop may let the user pick others.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HotkeysConfig {
+    pub brightness_up: String,
+    pub brightness_down: String,
+    pub temp_up: String,
+    pub temp_down: String,
+    pub toggle_night: String,
+    /// Brightness change per press
+    pub brightness_step: f64,
+    /// Temperature change per press (Kelvin)
+    pub temp_step: f64,
+}
+
+impl Default for HotkeysConfig {
+    fn default() -> Self {
+        HotkeysConfig {
+            brightness_up: "CTRL+ALT+Up".into(),
+            brightness_down: "CTRL+ALT+Down".into(),
+            temp_up: "CTRL+ALT+Right".into(),
+            temp_down: "CTRL+ALT+Left".into(),
+            toggle_night: "CTRL+ALT+N".into(),
+            brightness_step: 0.1,
+            temp_step: 500.0,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Hotkey {
+    BrightnessUp,
+    BrightnessDown,
+    TempUp,
+    TempDown,
+    /// Switch between the day and night temperature
+    ToggleNight,
+}
+
+impl Hotkey {
+    const ALL: [Hotkey; 5] =
+        [Hotkey::BrightnessUp, Hotkey::BrightnessDown, Hotkey::TempUp, Hotkey::TempDown, Hotkey::ToggleNight];
+
+    /// Shortcut id registered with the portal
+    fn id(self) -> &'static str {
+        match self {
+            Hotkey::BrightnessUp => "brightness-up",
+            Hotkey::BrightnessDown => "brightness-down",
+            Hotkey::TempUp => "temp-up",
+            Hotkey::TempDown => "temp-down",
+            Hotkey::ToggleNight => "toggle-night",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Hotkey::BrightnessUp => "Increase monitor brightness",
+            Hotkey::BrightnessDown => "Decrease monitor brightness",
+            Hotkey::TempUp => "Cooler screen colors",
+            Hotkey::TempDown => "Warmer screen colors",
+            Hotkey::ToggleNight => "Switch between day and night colors",
+        }
+    }
+
+    fn trigger(self, config: &HotkeysConfig) -> &str {
+        match self {
+            Hotkey::BrightnessUp => &config.brightness_up,
+            Hotkey::BrightnessDown => &config.brightness_down,
+            Hotkey::TempUp => &config.temp_up,
+            Hotkey::TempDown => &config.temp_down,
+            Hotkey::ToggleNight => &config.toggle_night,
+        }
+    }
+
+    /// The override this shortcut asks for, starting from the applied settings. The
+    /// temperatures are the configured day and night ones.
+    pub fn command(
+        self,
+        config: &HotkeysConfig,
+        brightness: Option<f64>,
+        color_temp: Option<f64>,
+        temps: (f64, f64),
+    ) -> Command {
+        let brightness = brightness.unwrap_or(1.0);
+        let (day_temp, night_temp) = temps;
+        let color_temp = color_temp.unwrap_or(day_temp);
+
+        match self {
+            Hotkey::BrightnessUp => Command::SetBrightness((brightness + config.brightness_step).min(1.0)),
+            Hotkey::BrightnessDown => Command::SetBrightness((brightness - config.brightness_step).max(0.0)),
+            Hotkey::TempUp => Command::SetTemperature((color_temp + config.temp_step).min(10000.0)),
+            Hotkey::TempDown => Command::SetTemperature((color_temp - config.temp_step).max(1000.0)),
+            // Anything warmer than halfway counts as night
+            Hotkey::ToggleNight if color_temp > (day_temp + night_temp) / 2.0 => Command::SetTemperature(night_temp),
+            Hotkey::ToggleNight => Command::SetTemperature(day_temp),
+        }
+    }
+}
+
+#[proxy(
+    interface = "org.freedesktop.portal.GlobalShortcuts",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+trait GlobalShortcuts {
+    fn create_session(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<OwnedObjectPath>;
+
+    fn bind_shortcuts(
+        &self,
+        session_handle: &ObjectPath<'_>,
+        shortcuts: Vec<(&str, HashMap<&str, Value<'_>>)>,
+        parent_window: &str,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(signal)]
+    fn activated(
+        &self,
+        session_handle: ObjectPath<'_>,
+        shortcut_id: &str,
+        timestamp: u64,
+        options: HashMap<&str, OwnedValue>,
+    ) -> zbus::Result<()>;
+}
+
+/// Registers the shortcuts with the GlobalShortcuts portal and hands an override to the daemon
+/// loop on every press. Returns once they are bound; presses are handled in the background.
+pub async fn serve(
+    config: &HotkeysConfig,
+    temps: (f64, f64),
+    commands: UnboundedSender<Command>,
+    status: watch::Receiver<Status>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let desktop = Portal::connect().await?;
+    let portal = GlobalShortcutsProxy::new(&desktop.connection).await?;
+    let session = desktop.session("healthy_monitor")?;
+
+    let create = desktop.request("healthy_monitor_create").await?;
+    portal
+        .create_session(HashMap::from([
+            ("handle_token", Value::from("healthy_monitor_create")),
+            ("session_handle_token", Value::from("healthy_monitor")),
+        ]))
+        .await?;
+    await_response(create, "Creating a global shortcuts session").await?;
+
+    let mut activations = portal.receive_activated().await?;
+    let shortcuts = Hotkey::ALL
+        .iter()
+        .map(|hotkey| {
+            let properties = HashMap::from([
+                ("description", Value::from(hotkey.description())),
+                ("preferred_trigger", Value::from(hotkey.trigger(config))),
+            ]);
+            (hotkey.id(), properties)
+        })
+        .collect();
+    let bind = desktop.request("healthy_monitor_bind").await?;
+    portal
+        .bind_shortcuts(&session, shortcuts, "", HashMap::from([("handle_token", Value::from("healthy_monitor_bind"))]))
+        .await?;
+    await_response(bind, "Binding global shortcuts").await?;
+
+    let config = config.clone();
+    tokio::spawn(async move {
+        // Keeps the session, which ends with the connection
+        let _desktop = desktop;
+        while let Some(activation) = activations.next().await {
+            let Ok(args) = activation.args() else { continue };
+            let Some(hotkey) = Hotkey::ALL.into_iter().find(|h| h.id() == *args.shortcut_id()) else {
+                continue;
+            };
+
+            let current = status.borrow().clone();
+            let command = hotkey.command(&config, current.brightness, current.color_temp, temps);
+            if commands.send(command).is_err() {
+                return;
+            }
+        }
+    });
+
+    Ok(())
+}
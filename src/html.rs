@@ -0,0 +1,135 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use chrono::{Duration, Local, NaiveDate, TimeZone};
+
+use crate::history;
+use crate::screentime::{format_duration, ScreenTime};
+
+/// Days shown in the per-day charts
+const DAYS: i64 = 14;
+/// Size of each chart in SVG units
+const WIDTH: f64 = 720.0;
+const HEIGHT: f64 = 200.0;
+/// Room around the plot for axis labels
+const MARGIN: f64 = 32.0;
+
+/// Writes a self-contained HTML page with brightness over `date` and screen time and
+/// evening light exposure for the two weeks ending on it
+pub fn write_report(path: &Path, date: NaiveDate) -> Result<(), Box<dyn std::error::Error>> {
+    let start = date.and_hms_opt(0, 0, 0).unwrap();
+    let from = Local.from_local_datetime(&start).earliest().ok_or("Invalid report date")?.timestamp();
+    let cycles = history::cycles(from, from + 86400 - 1)?;
+    let brightness: Vec<(f64, f64)> = cycles
+        .iter()
+        .map(|cycle| ((cycle.at - from) as f64 / 3600.0, cycle.brightness))
+        .collect();
+
+    let history = ScreenTime::load();
+    let days: Vec<NaiveDate> = (0..DAYS).rev().map(|i| date - Duration::days(i)).collect();
+    let labels: Vec<String> = days.iter().map(|day| day.format("%m-%d").to_string()).collect();
+    let active: Vec<f64> = days.iter().map(|day| history.day(*day).active_seconds / 3600.0).collect();
+    let evening: Vec<f64> = days.iter().map(|day| history.day(*day).evening_blue_light / 3600.0).collect();
+    let stats = history.day(date);
+
+    let mut html = String::new();
+    write!(
+        html,
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>healthy-monitor report for {date}</title>
+<style>
+body {{ font-family: sans-serif; max-width: 780px; margin: 2em auto; color: #222; }}
+h2 {{ font-size: 1.1em; margin-top: 2em; }}
+svg text {{ font-size: 11px; fill: #666; }}
+td {{ padding: 0 1em 0 0; }}
+</style>
+</head>
+<body>
+<h1>Report for {date}</h1>
+<table>
+<tr><td>Screen time</td><td>{active}</td></tr>
+<tr><td>Longest stretch</td><td>{stretch}</td></tr>
+<tr><td>Breaks taken</td><td>{breaks}</td></tr>
+<tr><td>Bright light</td><td>{bright}</td></tr>
+</table>
+<h2>Brightness over the day</h2>
+{brightness}
+<h2>Screen time per day (hours)</h2>
+{active_chart}
+<h2>Evening light exposure (hours at full blue output)</h2>
+{evening_chart}
+</body>
+</html>
+"#,
+        date = date,
+        active = format_duration(stats.active_seconds),
+        stretch = format_duration(stats.longest_stretch_seconds),
+        breaks = stats.breaks,
+        bright = format_duration(stats.bright_seconds),
+        brightness = line_chart(&brightness),
+        active_chart = bar_chart(&labels, &active, "#4a7bd0"),
+        evening_chart = bar_chart(&labels, &evening, "#d08a4a"),
+    )?;
+
+    std::fs::write(path, html)?;
+    println!("Report written to {}", path.display());
+    Ok(())
+}
+
+/// Line chart of `(hour, brightness)` points over a 24-hour axis
+fn line_chart(points: &[(f64, f64)]) -> String {
+    if points.is_empty() {
+        return "<p>No update cycles recorded on this day.</p>".into();
+    }
+
+    let x = |hour: f64| MARGIN + hour / 24.0 * (WIDTH - MARGIN);
+    let y = |value: f64| (HEIGHT - MARGIN) * (1.0 - value.clamp(0.0, 1.0));
+
+    let mut svg = open_svg();
+    for hour in (0..=24).step_by(3) {
+        let _ = write!(svg, r#"<text x="{:.1}" y="{}" text-anchor="middle">{:02}:00</text>"#, x(hour as f64), HEIGHT - 12.0, hour);
+    }
+    for value in [0.0, 0.5, 1.0] {
+        let _ = write!(svg, r#"<text x="{}" y="{:.1}" text-anchor="end">{:.0}%</text>"#, MARGIN - 4.0, y(value) + 4.0, value * 100.0);
+    }
+
+    let path: Vec<String> = points.iter().map(|(hour, value)| format!("{:.1},{:.1}", x(*hour), y(*value))).collect();
+    let _ = write!(svg, r##"<polyline fill="none" stroke="#4a7bd0" stroke-width="2" points="{}"/>"##, path.join(" "));
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Bar chart with one labeled bar per value
+fn bar_chart(labels: &[String], values: &[f64], color: &str) -> String {
+    let max = values.iter().cloned().fold(0.0, f64::max).max(f64::EPSILON);
+    let slot = (WIDTH - MARGIN) / values.len().max(1) as f64;
+    let plot_height = HEIGHT - MARGIN;
+
+    let mut svg = open_svg();
+    let _ = write!(svg, r#"<text x="{}" y="12" text-anchor="end">{:.1}</text>"#, MARGIN - 4.0, max);
+    for (i, (label, value)) in labels.iter().zip(values).enumerate() {
+        let height = value / max * (plot_height - 12.0);
+        let x = MARGIN + i as f64 * slot;
+        let _ = write!(
+            svg,
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}"><title>{}: {:.1}</title></rect>"#,
+            x + slot * 0.15,
+            plot_height - height,
+            slot * 0.7,
+            height,
+            color,
+            label,
+            value,
+        );
+        let _ = write!(svg, r#"<text x="{:.1}" y="{}" text-anchor="middle">{}</text>"#, x + slot / 2.0, HEIGHT - 12.0, label);
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn open_svg() -> String {
+    format!(r#"<svg viewBox="0 0 {} {}" width="100%" xmlns="http://www.w3.org/2000/svg">"#, WIDTH, HEIGHT)
+}
@@ -0,0 +1,367 @@
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::{broadcast, watch};
+
+use crate::daemon::Command;
+use crate::events::Event;
+use crate::status::Status;
+
+/// `[http]` config section: local REST API served by the daemon (needs the `http` feature)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// Address to listen on; only bind beyond localhost on a trusted network
+    pub listen: String,
+    /// Secret that requests changing settings send as `Authorization: Bearer <token>`
+    pub token: Option<String>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig { listen: "127.0.0.1:8787".into(), token: None }
+    }
+}
+
+/// Starts the API in the background, handing commands to the daemon loop and
+/// streaming `events` to WebSocket clients
+#[cfg(feature = "http")]
+pub async fn serve(
+    config: &HttpConfig,
+    commands: UnboundedSender<Command>,
+    status: watch::Receiver<Status>,
+    events: broadcast::Sender<Event>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    server::serve(config, commands, status, events).await
+}
+
+#[cfg(not(feature = "http"))]
+pub async fn serve(
+    _config: &HttpConfig,
+    _commands: UnboundedSender<Command>,
+    _status: watch::Receiver<Status>,
+    _events: broadcast::Sender<Event>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Err("healthy-monitor was built without the `http` feature".into())
+}
+
+#[cfg(feature = "http")]
+mod server {
+    use std::net::IpAddr;
+
+    use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+    use axum::extract::{Request, State};
+    use axum::http::header::{AUTHORIZATION, CONTENT_TYPE, HOST, ORIGIN};
+    use axum::http::{HeaderMap, HeaderName, StatusCode};
+    use axum::middleware::{self, Next};
+    use axum::response::{Html, IntoResponse};
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use chrono::{Local, TimeZone};
+    use serde::Deserialize;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc::UnboundedSender;
+    use tokio::sync::broadcast::{self, error::RecvError};
+    use tokio::sync::watch;
+
+    use super::HttpConfig;
+    use crate::daemon::Command;
+    use crate::events::Event;
+    use crate::history::{self, Cycle};
+    use crate::status::Status;
+
+    /// Single-page dashboard served at `/`
+    const DASHBOARD: &str = include_str!("dashboard.html");
+
+    #[derive(Clone)]
+    struct Shared {
+        commands: UnboundedSender<Command>,
+        status: watch::Receiver<Status>,
+        events: broadcast::Sender<Event>,
+        token: Option<String>,
+    }
+
+    type Response = Result<StatusCode, (StatusCode, String)>;
+
+    impl Shared {
+        fn send(&self, command: Command) -> Response {
+            command.validate().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            self.commands
+                .send(command)
+                .map_err(|_| (StatusCode::SERVICE_UNAVAILABLE, "The daemon is shutting down".to_string()))?;
+            Ok(StatusCode::NO_CONTENT)
+        }
+    }
+
+    /// Body of `POST /override`
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Override {
+        brightness: Option<f64>,
+        temp: Option<f64>,
+    }
+
+    /// Body of `POST /adjust`: changes relative to the applied settings, e.g. from Stream Deck dials
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Adjust {
+        brightness: Option<f64>,
+        temp: Option<f64>,
+    }
+
+    pub async fn serve(
+        config: &HttpConfig,
+        commands: UnboundedSender<Command>,
+        status: watch::Receiver<Status>,
+        events: broadcast::Sender<Event>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let shared = Shared { commands, status, events, token: config.token.clone() };
+        let changes = Router::new()
+            .route("/override", post(post_override))
+            .route("/adjust", post(post_adjust))
+            .route("/pause", post(post_pause))
+            .route("/resume", post(post_resume))
+            .route_layer(middleware::from_fn_with_state(shared.clone(), guard));
+        let app = Router::new()
+            .route("/", get(|| async { Html(DASHBOARD) }))
+            .route("/status", get(get_status))
+            .route("/history", get(get_history))
+            .route("/events", get(get_events))
+            .merge(changes)
+            .with_state(shared);
+        #[cfg(feature = "metrics")]
+        let app = app.route("/metrics", get(|| async { crate::metrics::render() }));
+
+        let listener = TcpListener::bind(&config.listen).await?;
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                eprintln!("HTTP API stopped: {}", e);
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Turns away requests that change settings unless they carry the token, when one is configured.
+    /// Without one, a web page open in the browser could still post to localhost, so such requests
+    /// must also come from the dashboard's own origin and be JSON, which a plain form can't send.
+    /// A page whose name was pointed at localhost would share the Host it sends, so without a token
+    /// the Host must be localhost itself.
+    async fn guard(State(shared): State<Shared>, request: Request, next: Next) -> axum::response::Response {
+        match allowed(request.headers(), shared.token.as_deref()) {
+            Ok(()) => next.run(request).await,
+            Err(rejection) => rejection.into_response(),
+        }
+    }
+
+    fn allowed(headers: &HeaderMap, token: Option<&str>) -> Result<(), (StatusCode, String)> {
+        let header = |name: HeaderName| headers.get(name).and_then(|value| value.to_str().ok());
+
+        if !header(CONTENT_TYPE).map_or(false, |kind| kind.starts_with("application/json")) {
+            return Err((StatusCode::UNSUPPORTED_MEDIA_TYPE, "Expected Content-Type: application/json".into()));
+        }
+
+        same_site(headers, token)?;
+
+        if let Some(token) = token {
+            if header(AUTHORIZATION) != Some(format!("Bearer {}", token).as_str()) {
+                return Err((StatusCode::UNAUTHORIZED, "Missing or wrong bearer token".into()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that a request comes from the dashboard's own origin, and without a token, that it
+    /// was sent to localhost
+    fn same_site(headers: &HeaderMap, token: Option<&str>) -> Result<(), (StatusCode, String)> {
+        let header = |name: HeaderName| headers.get(name).and_then(|value| value.to_str().ok());
+
+        // Browsers always send Origin on cross-origin posts and WebSockets; curl and home automation send none
+        if let Some(origin) = header(ORIGIN) {
+            let origin_host = origin.split_once("://").map(|(_, host)| host);
+            if origin_host.is_none() || origin_host != header(HOST) {
+                return Err((StatusCode::FORBIDDEN, format!("Requests from {} are not allowed", origin)));
+            }
+        }
+
+        if token.is_none() && !header(HOST).map_or(false, loopback) {
+            return Err((StatusCode::FORBIDDEN, "Requests to this host need the bearer token".into()));
+        }
+
+        Ok(())
+    }
+
+    /// Whether a Host header names this machine: localhost, 127.x or ::1, with or without a port
+    fn loopback(host: &str) -> bool {
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return ip.is_loopback();
+        }
+        let name = match host.rsplit_once(':') {
+            Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+            _ => host,
+        };
+        let name = name.strip_prefix('[').and_then(|n| n.strip_suffix(']')).unwrap_or(name);
+        name.eq_ignore_ascii_case("localhost") || name.parse::<IpAddr>().map_or(false, |ip| ip.is_loopback())
+    }
+
+    async fn get_status(State(shared): State<Shared>) -> Json<Status> {
+        Json(shared.status.borrow().clone())
+    }
+
+    /// Update cycles recorded since midnight
+    async fn get_history() -> Result<Json<Vec<Cycle>>, (StatusCode, String)> {
+        let midnight = Local::now().date_naive().and_hms_opt(0, 0, 0).unwrap();
+        let from = Local.from_local_datetime(&midnight).earliest().map_or(0, |t| t.timestamp());
+
+        history::cycles(from, i64::MAX)
+            .map(Json)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+    }
+
+    /// Upgrades to a WebSocket that receives every event as a JSON text message. Browsers let any
+    /// page open one, so it's held to the same origin as the changes.
+    async fn get_events(
+        State(shared): State<Shared>,
+        headers: HeaderMap,
+        upgrade: WebSocketUpgrade,
+    ) -> axum::response::Response {
+        if let Err(rejection) = same_site(&headers, shared.token.as_deref()) {
+            return rejection.into_response();
+        }
+        let events = shared.events.subscribe();
+        upgrade.on_upgrade(move |socket| stream_events(socket, events))
+    }
+
+    async fn stream_events(mut socket: WebSocket, mut events: broadcast::Receiver<Event>) {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                // Too slow to keep up: skip what was missed
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            };
+
+            let Ok(text) = serde_json::to_string(&event) else {
+                continue;
+            };
+            if socket.send(Message::Text(text)).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    async fn post_override(State(shared): State<Shared>, Json(request): Json<Override>) -> Response {
+        if request.brightness.is_none() && request.temp.is_none() {
+            return Err((StatusCode::BAD_REQUEST, "Expected brightness or temp".into()));
+        }
+        if let Some(temp) = request.temp {
+            shared.send(Command::SetTemperature(temp))?;
+        }
+        if let Some(brightness) = request.brightness {
+            shared.send(Command::SetBrightness(brightness))?;
+        }
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    async fn post_adjust(State(shared): State<Shared>, Json(request): Json<Adjust>) -> Response {
+        if request.brightness.is_none() && request.temp.is_none() {
+            return Err((StatusCode::BAD_REQUEST, "Expected brightness or temp".into()));
+        }
+        let current = shared.status.borrow().clone();
+        if let Some(delta) = request.temp {
+            let color_temp = current.color_temp.ok_or((StatusCode::CONFLICT, "No temperature applied yet".to_string()))?;
+            shared.send(Command::SetTemperature((color_temp + delta).clamp(1000.0, 10000.0)))?;
+        }
+        if let Some(delta) = request.brightness {
+            let brightness = current.brightness.ok_or((StatusCode::CONFLICT, "No brightness applied yet".to_string()))?;
+            shared.send(Command::SetBrightness((brightness + delta).clamp(0.0, 1.0)))?;
+        }
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    async fn post_pause(State(shared): State<Shared>) -> Response {
+        shared.send(Command::Pause)
+    }
+
+    async fn post_resume(State(shared): State<Shared>) -> Response {
+        shared.send(Command::Resume)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn headers(pairs: &[(HeaderName, &str)]) -> HeaderMap {
+            pairs.iter().map(|(name, value)| (name.clone(), value.parse().unwrap())).collect()
+        }
+
+        fn status(headers: &HeaderMap, token: Option<&str>) -> Option<StatusCode> {
+            allowed(headers, token).err().map(|(status, _)| status)
+        }
+
+        #[test]
+        fn loopback_hosts() {
+            assert!(loopback("localhost"));
+            assert!(loopback("localhost:8787"));
+            assert!(loopback("LocalHost:8787"));
+            assert!(loopback("127.0.0.1:8787"));
+            assert!(loopback("127.1.2.3"));
+            assert!(loopback("[::1]:8787"));
+            assert!(loopback("::1"));
+            assert!(!loopback("192.168.1.5:8787"));
+            assert!(!loopback("evil.example:8787"));
+            assert!(!loopback("127.0.0.1.evil.example"));
+            assert!(!loopback("localhost.evil.example:8787"));
+        }
+
+        #[test]
+        fn json_from_localhost_without_token() {
+            let local = headers(&[(CONTENT_TYPE, "application/json"), (HOST, "127.0.0.1:8787")]);
+            assert_eq!(status(&local, None), None);
+
+            let form = headers(&[(CONTENT_TYPE, "application/x-www-form-urlencoded"), (HOST, "127.0.0.1:8787")]);
+            assert_eq!(status(&form, None), Some(StatusCode::UNSUPPORTED_MEDIA_TYPE));
+        }
+
+        #[test]
+        fn other_hosts_need_the_token() {
+            // A rebound name reaches localhost with its own Host and a matching Origin
+            let rebound = headers(&[
+                (CONTENT_TYPE, "application/json"),
+                (HOST, "evil.example:8787"),
+                (ORIGIN, "http://evil.example:8787"),
+            ]);
+            assert_eq!(status(&rebound, None), Some(StatusCode::FORBIDDEN));
+
+            let remote = headers(&[
+                (CONTENT_TYPE, "application/json"),
+                (HOST, "192.168.1.5:8787"),
+                (AUTHORIZATION, "Bearer secret"),
+            ]);
+            assert_eq!(status(&remote, Some("secret")), None);
+            assert_eq!(status(&remote, Some("other")), Some(StatusCode::UNAUTHORIZED));
+            assert_eq!(status(&remote, None), Some(StatusCode::FORBIDDEN));
+        }
+
+        #[test]
+        fn origin_must_match_host() {
+            let same = headers(&[
+                (CONTENT_TYPE, "application/json"),
+                (HOST, "localhost:8787"),
+                (ORIGIN, "http://localhost:8787"),
+            ]);
+            assert_eq!(status(&same, None), None);
+
+            let cross = headers(&[
+                (CONTENT_TYPE, "application/json"),
+                (HOST, "localhost:8787"),
+                (ORIGIN, "https://evil.example"),
+            ]);
+            assert_eq!(status(&cross, None), Some(StatusCode::FORBIDDEN));
+            assert_eq!(same_site(&cross, None).map_err(|(status, _)| status), Err(StatusCode::FORBIDDEN));
+
+            let opaque = headers(&[(CONTENT_TYPE, "application/json"), (HOST, "localhost:8787"), (ORIGIN, "null")]);
+            assert_eq!(status(&opaque, None), Some(StatusCode::FORBIDDEN));
+        }
+    }
+}
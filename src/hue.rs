@@ -0,0 +1,151 @@
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::config::Config;
+use crate::roomlight::{Mapping, RoomLight, RoomLightsConfig};
+
+/// `[room_lights.hue]` config section: Philips Hue lights through the bridge's local API
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HueConfig {
+    /// Address of the bridge on the local network
+    pub bridge: String,
+    /// Application key from `healthy-monitor hue pair`
+    pub username: String,
+    /// Ids of the lights to keep in sync, as listed by `healthy-monitor hue lights`
+    pub lights: Vec<String>,
+    /// Color temperature range the lights support; the screen's is clamped into it
+    pub min_kelvin: f64,
+    pub max_kelvin: f64,
+    /// Added to the screen temperature, e.g. -500 for a room a little warmer than the screen
+    pub temp_offset: f64,
+    /// Light brightness at full room brightness [0.0..1.0]
+    pub max_brightness: f64,
+}
+
+impl Default for HueConfig {
+    fn default() -> Self {
+        let mapping = Mapping::default();
+        HueConfig {
+            bridge: String::new(),
+            username: String::new(),
+            lights: Vec::new(),
+            min_kelvin: mapping.min_kelvin,
+            max_kelvin: mapping.max_kelvin,
+            temp_offset: mapping.temp_offset,
+            max_brightness: mapping.max_brightness,
+        }
+    }
+}
+
+/// The `[hue]` section from before `[room_lights]`, still read so older configs keep working
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LegacyHueConfig {
+    pub bridge: String,
+    pub username: String,
+    pub lights: Vec<String>,
+    pub day_brightness: f64,
+    pub night_brightness: f64,
+    pub transition_seconds: f64,
+}
+
+impl Default for LegacyHueConfig {
+    fn default() -> Self {
+        let room_lights = RoomLightsConfig::default();
+        LegacyHueConfig {
+            bridge: String::new(),
+            username: String::new(),
+            lights: Vec::new(),
+            day_brightness: room_lights.day_brightness,
+            night_brightness: room_lights.night_brightness,
+            transition_seconds: room_lights.transition_seconds,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Light {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+/// One light behind the bridge
+pub struct HueLight<'a> {
+    pub config: &'a HueConfig,
+    pub id: &'a str,
+}
+
+impl RoomLight for HueLight<'_> {
+    fn describe(&self) -> String {
+        format!("Hue light {}", self.id)
+    }
+
+    fn mapping(&self) -> Mapping {
+        Mapping {
+            min_kelvin: self.config.min_kelvin,
+            max_kelvin: self.config.max_kelvin,
+            temp_offset: self.config.temp_offset,
+            max_brightness: self.config.max_brightness,
+        }
+    }
+
+    async fn set(&self, kelvin: f64, brightness: f64, transition: Duration) -> Result<(), Box<dyn std::error::Error>> {
+        // Leaving out "on" keeps lights that are off switched off
+        let body = json!({
+            "ct": (1e6 / kelvin).round(),
+            "bri": (brightness * 253.0).round() + 1.0,
+            "transitiontime": (transition.as_secs_f64() * 10.0).round(),
+        });
+        let url = format!("http://{}/api/{}/lights/{}/state", self.config.bridge, self.config.username, self.id);
+        reqwest::Client::new().put(&url).json(&body).send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Registers with the bridge, which only succeeds within 30 seconds of pressing its link
+/// button, and saves the key to the config
+pub async fn pair(bridge: &str, config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let response: serde_json::Value = reqwest::Client::new()
+        .post(format!("http://{}/api", bridge))
+        .json(&json!({ "devicetype": "healthy-monitor" }))
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    let first = &response[0];
+    if let Some(description) = first["error"]["description"].as_str() {
+        return Err(format!("The bridge refused pairing: {} (press its link button first)", description).into());
+    }
+    let username = first["success"]["username"].as_str().ok_or("Unexpected response from the bridge")?;
+
+    let mut config = Config::load_for_update(config_path)?;
+    let hue = config.room_lights.get_or_insert_with(RoomLightsConfig::default).hue.get_or_insert_with(HueConfig::default);
+    hue.bridge = bridge.to_string();
+    hue.username = username.to_string();
+    config.save(config_path)?;
+
+    println!("Paired with {}, pick lights with `healthy-monitor hue lights`", bridge);
+    Ok(())
+}
+
+/// Lists the bridge's lights and which of them are kept in sync
+pub async fn lights(config: &HueConfig) -> Result<(), Box<dyn std::error::Error>> {
+    if config.username.is_empty() {
+        return Err("Not paired with a bridge yet, run `healthy-monitor hue pair --bridge <address>`".into());
+    }
+
+    let url = format!("http://{}/api/{}/lights", config.bridge, config.username);
+    let lights: std::collections::BTreeMap<String, Light> = reqwest::get(&url).await?.json().await?;
+
+    for (id, light) in &lights {
+        let synced = if config.lights.contains(id) { " (synced)" } else { "" };
+        println!("{:>3}  {} [{}]{}", id, light.name, light.kind, synced);
+    }
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use std::process::Command;
+
+/// Seconds since the last user input, from the X screensaver extension via xprintidle
+pub fn idle_seconds() -> Option<f64> {
+    let output = Command::new("xprintidle").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let millis: f64 = String::from_utf8(output.stdout).ok()?.trim().parse().ok()?;
+    Some(millis / 1000.0)
+}
+
+/// Whether logind considers the session idle (set by the desktop or an idle daemon on Wayland)
+fn logind_idle_hint() -> bool {
+    let Some(session) = std::env::var_os("XDG_SESSION_ID") else {
+        return false;
+    };
+
+    Command::new("loginctl")
+        .arg("show-session")
+        .arg(session)
+        .args(["--property", "IdleHint", "--value"])
+        .output()
+        .map_or(false, |output| String::from_utf8_lossy(&output.stdout).trim() == "yes")
+}
+
+/// Whether the user has been inactive for at least `minutes`
+pub fn is_idle(minutes: f64) -> bool {
+    match idle_seconds() {
+        Some(seconds) => seconds >= minutes * 60.0,
+        None => logind_idle_hint(),
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::{self, Config, Location};
+use crate::schedule::TimeOfDay;
+
+/// Length of redshift's fade between temperatures: 40 steps of 100ms
+const REDSHIFT_FADE_SECONDS: f64 = 4.0;
+
+/// Sections of an INI-style file, keyed by section name then option name
+type Ini = HashMap<String, HashMap<String, String>>;
+
+/// Locations redshift and gammastep read their config from, in lookup order
+pub fn default_redshift_paths() -> Vec<PathBuf> {
+    let Some(home) = config::config_home() else {
+        return Vec::new();
+    };
+
+    vec![
+        home.join("redshift").join("redshift.conf"),
+        home.join("redshift.conf"),
+        home.join("gammastep").join("config.ini"),
+    ]
+}
+
+/// Converts a redshift/gammastep config into the native config.
+/// Returns the config along with warnings for settings that have no equivalent.
+pub fn import_redshift(path: &Path) -> Result<(Config, Vec<String>), Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let ini = parse_ini(&contents);
+
+    let mut config = Config::default();
+    let mut warnings = Vec::new();
+
+    // gammastep uses [general], redshift uses [redshift]
+    let general = ini.get("redshift").or_else(|| ini.get("general")).cloned().unwrap_or_default();
+
+    for (key, value) in &general {
+        match key.as_str() {
+            "temp-day" => config.day_temp = Some(parse_number(key, value)?),
+            "temp-night" => config.night_temp = Some(parse_number(key, value)?),
+            // Redshift holds the screen at a fixed level; brightness here follows the ambient
+            // light, so the daytime level becomes the cap it never goes above
+            "brightness" | "brightness-day" => config.max_brightness = Some(parse_number(key, value)?),
+            "brightness-night" => warnings.push(format!(
+                "'{}' was skipped, brightness follows the ambient light and the daytime level caps it",
+                key
+            )),
+            // Both name redshift's short fade on every change, not the day/night transition
+            "transition" | "fade" => match value.trim() {
+                "0" => config.fade_seconds = Some(0.0),
+                "1" => config.fade_seconds = Some(REDSHIFT_FADE_SECONDS),
+                _ => return Err(format!("Invalid value '{}' for {}", value, key).into()),
+            },
+            // Dusk leads up to the night like the transition does to `night_start`, so night
+            // starts when dusk ends and the transition begins where dusk does
+            "dusk-time" => match parse_time_range(value) {
+                Some((start, end)) => {
+                    config.schedule.night_start = Some(TimeOfDay(end));
+                    config.transition_hours = Some((end - start).rem_euclid(24.0));
+                }
+                None => return Err(format!("Invalid dusk-time '{}'", value).into()),
+            },
+            "location-provider" | "adjustment-method" => {}
+            _ => warnings.push(format!("'{}' has no equivalent and was skipped", key)),
+        }
+    }
+
+    if let Some(manual) = ini.get("manual") {
+        match (manual.get("lat"), manual.get("lon")) {
+            (Some(lat), Some(lon)) => {
+                config.location = Some(Location {
+                    lat: parse_number("lat", lat)?,
+                    lon: parse_number("lon", lon)?,
+                });
+            }
+            _ => warnings.push("[manual] section needs both lat and lon, location skipped".to_string()),
+        }
+    }
+
+    Ok((config, warnings))
+}
+
+fn parse_ini(contents: &str) -> Ini {
+    let mut ini = Ini::new();
+    let mut section = String::new();
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.trim().to_string();
+            continue;
+        }
+
+        if let Some((key, value)) = line.split_once('=') {
+            ini.entry(section.clone())
+                .or_default()
+                .insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+
+    ini
+}
+
+fn parse_number(key: &str, value: &str) -> Result<f64, Box<dyn std::error::Error>> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' for {}", value, key).into())
+}
+
+/// Parses `HH:MM-HH:MM` into fractional hours
+fn parse_time_range(value: &str) -> Option<(f64, f64)> {
+    let (start, end) = value.split_once('-')?;
+    let start: TimeOfDay = start.parse().ok()?;
+    let end: TimeOfDay = end.parse().ok()?;
+    Some((start.0, end.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    fn import(contents: &str) -> Result<(Config, Vec<String>), Box<dyn std::error::Error>> {
+        // Tests run in parallel, each with its own file
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let id = NEXT.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("healthy-monitor-import-{}-{}.conf", std::process::id(), id));
+        std::fs::write(&path, contents)?;
+        let result = import_redshift(&path);
+        let _ = std::fs::remove_file(&path);
+        result
+    }
+
+    #[test]
+    fn ini_sections() {
+        let ini = parse_ini("top=1\n; comment\n# comment\n\n[ redshift ]\ntemp-day = 5700\n[manual]\nlat=50.1\nlon = 14.4\nno value here\n");
+        assert_eq!(ini[""]["top"], "1");
+        assert_eq!(ini["redshift"]["temp-day"], "5700");
+        assert_eq!(ini["manual"]["lat"], "50.1");
+        assert_eq!(ini["manual"]["lon"], "14.4");
+        assert_eq!(ini["manual"].len(), 2);
+    }
+
+    #[test]
+    fn time_ranges() {
+        assert_eq!(parse_time_range("18:00-19:30"), Some((18.0, 19.5)));
+        assert_eq!(parse_time_range("23:30-00:30"), Some((23.5, 0.5)));
+        assert_eq!(parse_time_range("18:00"), None);
+        assert_eq!(parse_time_range("dusk-night"), None);
+    }
+
+    #[test]
+    fn redshift_config() {
+        let (config, warnings) = import(
+            "[redshift]\ntemp-day=5700\ntemp-night=3500\nbrightness-day=0.9\nfade=1\ndusk-time=18:00-19:30\ngamma=0.8\n\n[manual]\nlat=50.1\nlon=14.4\n",
+        )
+        .unwrap();
+        assert_eq!(config.day_temp, Some(5700.0));
+        assert_eq!(config.night_temp, Some(3500.0));
+        assert_eq!(config.max_brightness, Some(0.9));
+        assert_eq!(config.fade_seconds, Some(REDSHIFT_FADE_SECONDS));
+        assert_eq!(config.schedule.night_start, Some(TimeOfDay(19.5)));
+        assert_eq!(config.transition_hours, Some(1.5));
+        let location = config.location.unwrap();
+        assert_eq!((location.lat, location.lon), (50.1, 14.4));
+        assert_eq!(warnings, vec!["'gamma' has no equivalent and was skipped".to_string()]);
+    }
+
+    #[test]
+    fn gammastep_config() {
+        let (config, warnings) = import("[general]\ntemp-night=3000\nfade=0\nbrightness-night=0.5\n[manual]\nlat=1\n").unwrap();
+        assert_eq!(config.night_temp, Some(3000.0));
+        assert_eq!(config.fade_seconds, Some(0.0));
+        assert!(config.location.is_none());
+        assert_eq!(warnings.len(), 2);
+    }
+
+    #[test]
+    fn dusk_past_midnight() {
+        let (config, _) = import("[redshift]\ndusk-time=23:30-00:30\n").unwrap();
+        assert_eq!(config.schedule.night_start, Some(TimeOfDay(0.5)));
+        assert_eq!(config.transition_hours, Some(1.0));
+    }
+
+    #[test]
+    fn invalid_values() {
+        assert!(import("[redshift]\ntemp-day=warm\n").is_err());
+        assert!(import("[redshift]\nfade=2\n").is_err());
+        assert!(import("[redshift]\ndusk-time=evening\n").is_err());
+    }
+}
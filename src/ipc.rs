@@ -0,0 +1,229 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::OwnedReadHalf;
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::watch;
+
+use crate::daemon::Command;
+use crate::exit::{self, Failure};
+use crate::status::Status;
+
+/// Version of the protocol, bumped on incompatible changes
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+/// One line of JSON sent by a client
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// Parameters of `set`
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SetParams {
+    brightness: Option<f64>,
+    temp: Option<f64>,
+}
+
+/// Listens on the control socket, handing commands to the daemon loop and answering
+/// status requests from `status`. Fails when another daemon already owns the socket.
+pub fn serve(commands: UnboundedSender<Command>, status: watch::Receiver<Status>) -> Result<(), Box<dyn std::error::Error>> {
+    let path = socket_path().ok_or("XDG_RUNTIME_DIR is not set")?;
+    if running() {
+        return Err(exit::fail(Failure::AlreadyRunning, format!("Another daemon is listening on {}", path.display())));
+    }
+    // Left behind by a daemon that didn't shut down cleanly
+    let _ = std::fs::remove_file(&path);
+
+    let listener = UnixListener::bind(&path)?;
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(handle_connection(stream, commands.clone(), status.clone()));
+                }
+                Err(e) => eprintln!("Control socket error: {}", e),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Answers requests, one JSON object per line, and pushes status notifications once subscribed
+async fn handle_connection(stream: UnixStream, commands: UnboundedSender<Command>, mut status: watch::Receiver<Status>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let mut subscribed = false;
+
+    loop {
+        let message = tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(line)) if line.trim().is_empty() => continue,
+                Ok(Some(line)) => {
+                    let (response, subscribe) = respond(&line, &commands, &status);
+                    if subscribe && !subscribed {
+                        subscribed = true;
+                        status.mark_unchanged();
+                    }
+                    response
+                }
+                _ => return,
+            },
+            changed = status.changed(), if subscribed => {
+                if changed.is_err() {
+                    return;
+                }
+                json!({ "jsonrpc": "2.0", "method": "status", "params": *status.borrow_and_update() })
+            }
+        };
+
+        let mut line = message.to_string();
+        line.push('\n');
+        if writer.write_all(line.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Response to one request line, and whether it subscribes the connection to status updates
+fn respond(line: &str, commands: &UnboundedSender<Command>, status: &watch::Receiver<Status>) -> (Value, bool) {
+    let request: Request = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return (error(Value::Null, PARSE_ERROR, &e.to_string()), false),
+    };
+
+    let send = |command: Command| -> Result<Value, (i64, String)> {
+        command.validate().map_err(|e| (INVALID_PARAMS, e))?;
+        commands
+            .send(command)
+            .map_err(|_| (INTERNAL_ERROR, "The daemon is shutting down".to_string()))?;
+        Ok(Value::Null)
+    };
+
+    let mut subscribe = false;
+    let result = match request.method.as_str() {
+        "version" => Ok(json!({ "protocol": PROTOCOL_VERSION })),
+        "get-status" => serde_json::to_value(&*status.borrow()).map_err(|e| (INTERNAL_ERROR, e.to_string())),
+        "set" => match serde_json::from_value::<Option<SetParams>>(request.params) {
+            Ok(Some(SetParams { brightness: None, temp: None })) | Ok(None) => {
+                Err((INVALID_PARAMS, "Expected brightness or temp".to_string()))
+            }
+            Ok(Some(params)) => params
+                .temp
+                .map_or(Ok(Value::Null), |temp| send(Command::SetTemperature(temp)))
+                .and_then(|_| params.brightness.map_or(Ok(Value::Null), |b| send(Command::SetBrightness(b)))),
+            Err(e) => Err((INVALID_PARAMS, e.to_string())),
+        },
+        "pause" => send(Command::Pause),
+        "resume" => send(Command::Resume),
+        "refresh" => send(Command::Refresh),
+        "relocate" => send(Command::Relocate),
+        "subscribe" => {
+            subscribe = true;
+            Ok(Value::Null)
+        }
+        method => Err((METHOD_NOT_FOUND, format!("Unknown method {}", method))),
+    };
+
+    let response = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": request.id, "result": result }),
+        Err((code, message)) => error(request.id, code, &message),
+    };
+    (response, subscribe)
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Hands `commands` to a running daemon, returning false when nothing listens on the socket
+pub async fn send(commands: &[Command]) -> Result<bool, Box<dyn std::error::Error>> {
+    let Some(path) = socket_path() else {
+        return Ok(false);
+    };
+    let stream = match UnixStream::connect(&path).await {
+        Ok(stream) => stream,
+        Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    for (id, command) in commands.iter().enumerate() {
+        let (method, params) = match *command {
+            Command::SetBrightness(brightness) => ("set", json!({ "brightness": brightness })),
+            Command::SetTemperature(temp) => ("set", json!({ "temp": temp })),
+            Command::Pause => ("pause", Value::Null),
+            Command::Resume => ("resume", Value::Null),
+            Command::Refresh => ("refresh", Value::Null),
+            Command::Relocate => ("relocate", Value::Null),
+        };
+
+        let mut request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }).to_string();
+        request.push('\n');
+        writer.write_all(request.as_bytes()).await?;
+
+        let line = lines.next_line().await?.ok_or("The daemon closed the connection")?;
+        let response: Value = serde_json::from_str(&line)?;
+        if let Some(message) = response["error"]["message"].as_str() {
+            return Err(message.into());
+        }
+    }
+
+    Ok(true)
+}
+
+/// A connection to the daemon that is told about every status change
+pub struct Subscription {
+    lines: tokio::io::Lines<BufReader<OwnedReadHalf>>,
+    // Keeps the connection open
+    _writer: tokio::net::unix::OwnedWriteHalf,
+}
+
+impl Subscription {
+    /// Subscribes to a running daemon, `None` when none is listening
+    pub async fn connect() -> Option<Self> {
+        let stream = UnixStream::connect(socket_path()?).await.ok()?;
+        let (reader, mut writer) = stream.into_split();
+        let request = json!({ "jsonrpc": "2.0", "id": 0, "method": "subscribe" }).to_string() + "\n";
+        writer.write_all(request.as_bytes()).await.ok()?;
+
+        let mut lines = BufReader::new(reader).lines();
+        // The subscribe response itself
+        lines.next_line().await.ok()??;
+        Some(Subscription { lines, _writer: writer })
+    }
+
+    /// Waits for the next status change, returning false once the daemon went away
+    pub async fn changed(&mut self) -> bool {
+        matches!(self.lines.next_line().await, Ok(Some(_)))
+    }
+}
+
+/// Whether a daemon answers on the control socket
+pub fn running() -> bool {
+    socket_path().map_or(false, |path| std::os::unix::net::UnixStream::connect(path).is_ok())
+}
+
+/// `$XDG_RUNTIME_DIR/healthy-monitor.sock`
+pub fn socket_path() -> Option<PathBuf> {
+    let dir = PathBuf::from(std::env::var_os("XDG_RUNTIME_DIR")?);
+    dir.is_absolute().then(|| dir.join("healthy-monitor.sock"))
+}
@@ -0,0 +1,337 @@
+use std::path::PathBuf;
+
+use chrono::{Local, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::schedule;
+use crate::state;
+
+/// Oldest overrides are dropped beyond this many
+const MAX_SAMPLES: usize = 500;
+/// Spread of the time-of-day weighting (hours)
+const HOUR_SIGMA: f64 = 3.0;
+/// Weight of the default mapping, in samples; the learned model takes over as overrides pile up
+const PRIOR_WEIGHT: f64 = 10.0;
+
+/// A brightness chosen by hand
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Sample {
+    /// Unix timestamp
+    pub at: i64,
+    /// Ambient level at the time [0.0..1.0]
+    pub ambient: f64,
+    /// Local time as fractional hours
+    pub hour: f64,
+    pub brightness: f64,
+}
+
+/// Manual overrides, oldest first
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Overrides {
+    pub samples: Vec<Sample>,
+}
+
+/// Linear brightness model fitted to the overrides around one time of day
+#[derive(Debug, Clone, Copy)]
+pub struct Fit {
+    pub intercept: f64,
+    pub slope: f64,
+    /// How much the model replaces the default mapping [0.0..1.0]
+    pub weight: f64,
+}
+
+impl Fit {
+    pub fn predict(&self, ambient: f64) -> f64 {
+        (self.intercept + self.slope * ambient).clamp(0.0, 1.0)
+    }
+
+    /// Mixes the model's prediction into the default brightness according to its weight
+    pub fn blend(&self, ambient: f64, default: f64) -> f64 {
+        default * (1.0 - self.weight) + self.predict(ambient) * self.weight
+    }
+}
+
+impl Overrides {
+    pub fn load() -> Self {
+        let Some(path) = path() else {
+            return Self::default();
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("Ignoring invalid overrides file {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = path().ok_or("Cannot determine the overrides file location")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        state::write_atomic(&path, &serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Logs a brightness chosen by hand at `ambient`, now
+    pub fn record(&mut self, ambient: f64, brightness: f64) {
+        self.samples.push(Sample {
+            at: Utc::now().timestamp(),
+            ambient,
+            hour: schedule::hour_of(Local::now()),
+            brightness,
+        });
+
+        let excess = self.samples.len().saturating_sub(MAX_SAMPLES);
+        self.samples.drain(..excess);
+    }
+
+    /// Weighted least-squares fit of brightness against ambient level,
+    /// favoring overrides made around `hour`
+    pub fn fit(&self, hour: f64) -> Option<Fit> {
+        let weighted: Vec<(f64, &Sample)> = self
+            .samples
+            .iter()
+            .map(|s| {
+                let distance = (s.hour - hour).rem_euclid(24.0).min((hour - s.hour).rem_euclid(24.0));
+                ((-distance.powi(2) / (2.0 * HOUR_SIGMA.powi(2))).exp(), s)
+            })
+            .collect();
+
+        let total: f64 = weighted.iter().map(|(w, _)| w).sum();
+        if total < 1e-6 {
+            return None;
+        }
+
+        let mean_x = weighted.iter().map(|(w, s)| w * s.ambient).sum::<f64>() / total;
+        let mean_y = weighted.iter().map(|(w, s)| w * s.brightness).sum::<f64>() / total;
+        let var_x = weighted.iter().map(|(w, s)| w * (s.ambient - mean_x).powi(2)).sum::<f64>() / total;
+        let cov = weighted
+            .iter()
+            .map(|(w, s)| w * (s.ambient - mean_x) * (s.brightness - mean_y))
+            .sum::<f64>()
+            / total;
+
+        // Overrides all made in similar light say nothing about the slope
+        let slope = if var_x > 1e-4 { cov / var_x } else { 0.0 };
+
+        Some(Fit {
+            intercept: mean_y - slope * mean_x,
+            slope,
+            weight: total / (total + PRIOR_WEIGHT),
+        })
+    }
+}
+
+/// Prints what has been learned for the current time of day
+pub fn show(default: impl Fn(f64) -> f64) -> Result<(), Box<dyn std::error::Error>> {
+    let overrides = Overrides::load();
+    let hour = schedule::hour_of(Local::now());
+
+    println!("{} manual override(s) recorded", overrides.samples.len());
+    let Some(fit) = overrides.fit(hour) else {
+        println!("Nothing learned for this time of day yet, using the default mapping");
+        return Ok(());
+    };
+
+    println!("Learned model weight at this time of day: {:.0}%", fit.weight * 100.0);
+    println!();
+    println!("  ambient  default  learned  applied");
+    for ambient in [0.0, 0.25, 0.5, 0.75, 1.0] {
+        println!(
+            "  {:>7.2}  {:>7.2}  {:>7.2}  {:>7.2}",
+            ambient,
+            default(ambient),
+            fit.predict(ambient),
+            fit.blend(ambient, default(ambient))
+        );
+    }
+
+    Ok(())
+}
+
+/// Hours covered by each time band in the analysis
+const BAND_HOURS: usize = 3;
+/// Upper bounds and names of the ambient bands in the analysis
+const AMBIENT_BANDS: [(f64, &str); 4] = [(0.15, "dark"), (0.35, "dim"), (0.6, "moderate"), (f64::INFINITY, "bright")];
+
+/// Override count and how far off the default mapping was, for one group of overrides
+#[derive(Debug, Default)]
+struct Misfit {
+    count: usize,
+    /// Sum of chosen minus default brightness
+    error: f64,
+    abs_error: f64,
+}
+
+impl Misfit {
+    fn add(&mut self, error: f64) {
+        self.count += 1;
+        self.error += error;
+        self.abs_error += error.abs();
+    }
+
+    fn mean_abs(&self) -> f64 {
+        self.abs_error / self.count.max(1) as f64
+    }
+
+    fn describe(&self) -> String {
+        let mean = self.error / self.count.max(1) as f64;
+        let direction = if mean < 0.0 { "dimmer" } else { "brighter" };
+        format!("{:>3} override(s), off by {:.2} on average, usually {}", self.count, self.mean_abs(), direction)
+    }
+}
+
+/// Prints when and in which light overrides happen, and where the default mapping fits worst
+pub fn analyze(default: impl Fn(f64) -> f64) -> Result<(), Box<dyn std::error::Error>> {
+    let overrides = Overrides::load();
+    if overrides.samples.is_empty() {
+        println!("No manual overrides recorded yet");
+        return Ok(());
+    }
+
+    let (by_time, by_ambient) = misfits(&overrides.samples, default);
+
+    println!("{} manual override(s)", overrides.samples.len());
+    println!();
+    println!("By time of day:");
+    for (i, misfit) in by_time.iter().enumerate().filter(|(_, m)| m.count > 0) {
+        println!("  {:02}:00-{:02}:00  {}", i * BAND_HOURS, (i + 1) * BAND_HOURS, misfit.describe());
+    }
+
+    println!();
+    println!("By ambient light:");
+    for ((_, name), misfit) in AMBIENT_BANDS.iter().zip(&by_ambient).filter(|(_, m)| m.count > 0) {
+        println!("  {:<9} {}", name, misfit.describe());
+    }
+
+    // Weigh the size of the misfit by how often it comes up
+    let worst = by_time
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| m.count > 0)
+        .max_by(|(_, a), (_, b)| a.abs_error.total_cmp(&b.abs_error));
+    if let Some((i, misfit)) = worst {
+        println!();
+        println!(
+            "The default mapping fits you worst between {:02}:00 and {:02}:00 ({}).",
+            i * BAND_HOURS,
+            (i + 1) * BAND_HOURS,
+            misfit.describe().trim_start()
+        );
+    }
+
+    Ok(())
+}
+
+/// How far `samples` are from the default mapping, grouped by time band and by ambient band
+fn misfits(samples: &[Sample], default: impl Fn(f64) -> f64) -> (Vec<Misfit>, Vec<Misfit>) {
+    let mut by_time: Vec<Misfit> = (0..24 / BAND_HOURS).map(|_| Misfit::default()).collect();
+    let mut by_ambient: Vec<Misfit> = AMBIENT_BANDS.iter().map(|_| Misfit::default()).collect();
+
+    for sample in samples {
+        let error = sample.brightness - default(sample.ambient);
+        let time_band = (sample.hour as usize / BAND_HOURS).min(by_time.len() - 1);
+        by_time[time_band].add(error);
+
+        let band = AMBIENT_BANDS.iter().position(|(limit, _)| sample.ambient < *limit).unwrap_or(0);
+        by_ambient[band].add(error);
+    }
+
+    (by_time, by_ambient)
+}
+
+/// Forgets all overrides and feedback corrections
+pub fn reset() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = state::state_dir().ok_or("Cannot determine the state directory")?;
+    for file in ["overrides.json", "curve.json"] {
+        match std::fs::remove_file(dir.join(file)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+
+    println!("Learned preferences reset, back to the default mapping");
+    Ok(())
+}
+
+/// `$XDG_STATE_HOME/healthy-monitor/overrides.json`
+fn path() -> Option<PathBuf> {
+    Some(state::state_dir()?.join("overrides.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(hour: f64, ambient: f64, brightness: f64) -> Sample {
+        Sample { at: 0, ambient, hour, brightness }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn no_overrides() {
+        assert!(Overrides::default().fit(12.0).is_none());
+    }
+
+    #[test]
+    fn linear_overrides() {
+        let samples = [0.0, 0.25, 0.5, 1.0].map(|ambient| sample(12.0, ambient, 0.2 + 0.6 * ambient));
+        let fit = Overrides { samples: samples.to_vec() }.fit(12.0).unwrap();
+        assert!(close(fit.slope, 0.6));
+        assert!(close(fit.intercept, 0.2));
+        assert!(close(fit.weight, 4.0 / 14.0));
+        assert!(close(fit.predict(0.5), 0.5));
+        assert_eq!(fit.predict(10.0), 1.0);
+    }
+
+    #[test]
+    fn same_light_has_no_slope() {
+        let samples = vec![sample(12.0, 0.5, 0.4), sample(12.0, 0.5, 0.6)];
+        let fit = Overrides { samples }.fit(12.0).unwrap();
+        assert_eq!(fit.slope, 0.0);
+        assert!(close(fit.intercept, 0.5));
+        assert!(close(fit.predict(0.0), fit.predict(1.0)));
+    }
+
+    #[test]
+    fn hours_wrap_past_midnight() {
+        let before = Overrides { samples: vec![sample(23.5, 0.5, 0.5)] }.fit(0.5).unwrap();
+        let after = Overrides { samples: vec![sample(1.5, 0.5, 0.5)] }.fit(0.5).unwrap();
+        assert!(close(before.weight, after.weight));
+
+        let far = Overrides { samples: vec![sample(12.5, 0.5, 0.5)] }.fit(0.5).unwrap();
+        assert!(far.weight < before.weight);
+    }
+
+    #[test]
+    fn nearby_overrides_count_more() {
+        let samples = vec![sample(8.0, 0.5, 0.2), sample(20.0, 0.5, 0.8)];
+        let fit = Overrides { samples }.fit(8.0).unwrap();
+        assert!(fit.predict(0.5) < 0.25);
+    }
+
+    #[test]
+    fn blend_follows_weight() {
+        let fit = Fit { intercept: 0.8, slope: 0.0, weight: 0.25 };
+        assert!(close(fit.blend(0.5, 0.4), 0.5));
+    }
+
+    #[test]
+    fn misfit_bands() {
+        let samples = [sample(0.5, 0.1, 0.3), sample(23.9, 0.9, 0.5), sample(23.0, 0.5, 0.9)];
+        let (by_time, by_ambient) = misfits(&samples, |_| 0.5);
+
+        assert_eq!(by_time.iter().map(|m| m.count).collect::<Vec<_>>(), [1, 0, 0, 0, 0, 0, 0, 2]);
+        assert!(close(by_time[0].error, -0.2));
+        assert!(close(by_time[7].error, 0.4));
+        assert!(close(by_time[7].mean_abs(), 0.2));
+        assert_eq!(by_ambient.iter().map(|m| m.count).collect::<Vec<_>>(), [1, 0, 1, 1]);
+    }
+}
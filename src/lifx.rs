@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::net::UdpSocket;
+
+use crate::roomlight::{Mapping, RoomLight};
+
+/// UDP port LIFX bulbs listen on
+const PORT: u16 = 56700;
+/// LAN protocol message type of `Light::SetColor`
+const SET_COLOR: u16 = 102;
+
+/// A `[[room_lights.lifx]]` bulb, driven over the LIFX LAN protocol
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LifxConfig {
+    /// IP address of the bulb
+    pub address: String,
+    /// Color temperature range the bulb supports; the screen's is clamped into it
+    pub min_kelvin: f64,
+    pub max_kelvin: f64,
+    /// Added to the screen temperature, e.g. -500 for a room a little warmer than the screen
+    pub temp_offset: f64,
+    /// Bulb brightness at full room brightness [0.0..1.0]
+    pub max_brightness: f64,
+}
+
+impl Default for LifxConfig {
+    fn default() -> Self {
+        let mapping = Mapping::default();
+        LifxConfig {
+            address: String::new(),
+            min_kelvin: mapping.min_kelvin,
+            max_kelvin: mapping.max_kelvin,
+            temp_offset: mapping.temp_offset,
+            max_brightness: mapping.max_brightness,
+        }
+    }
+}
+
+impl RoomLight for LifxConfig {
+    fn describe(&self) -> String {
+        format!("LIFX bulb {}", self.address)
+    }
+
+    fn mapping(&self) -> Mapping {
+        Mapping {
+            min_kelvin: self.min_kelvin,
+            max_kelvin: self.max_kelvin,
+            temp_offset: self.temp_offset,
+            max_brightness: self.max_brightness,
+        }
+    }
+
+    // SetColor leaves the power state alone, so bulbs that are off stay off
+    async fn set(&self, kelvin: f64, brightness: f64, transition: Duration) -> Result<(), Box<dyn std::error::Error>> {
+        let socket = UdpSocket::bind("0.0.0.0:0").await?;
+        socket.send_to(&set_color(kelvin, brightness, transition), (self.address.as_str(), PORT)).await?;
+        Ok(())
+    }
+}
+
+/// A `SetColor` message for white light (hue and saturation zero)
+fn set_color(kelvin: f64, brightness: f64, transition: Duration) -> Vec<u8> {
+    let mut message = Vec::with_capacity(49);
+
+    // Frame header: size, protocol 1024 with the addressable and tagged bits, source
+    message.extend_from_slice(&49u16.to_le_bytes());
+    message.extend_from_slice(&(1024u16 | 1 << 12 | 1 << 13).to_le_bytes());
+    message.extend_from_slice(&0x484d_4f4eu32.to_le_bytes());
+    // Frame address: any target, no acknowledgement, sequence 0
+    message.extend_from_slice(&[0; 16]);
+    // Protocol header
+    message.extend_from_slice(&[0; 8]);
+    message.extend_from_slice(&SET_COLOR.to_le_bytes());
+    message.extend_from_slice(&[0; 2]);
+
+    // Payload: reserved, hue, saturation, brightness, kelvin, duration (ms)
+    message.push(0);
+    message.extend_from_slice(&0u16.to_le_bytes());
+    message.extend_from_slice(&0u16.to_le_bytes());
+    message.extend_from_slice(&((brightness.clamp(0.0, 1.0) * 65535.0).round() as u16).to_le_bytes());
+    message.extend_from_slice(&(kelvin.round() as u16).to_le_bytes());
+    message.extend_from_slice(&(transition.as_millis() as u32).to_le_bytes());
+
+    message
+}
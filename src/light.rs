@@ -0,0 +1,40 @@
+/// Relative output of a channel driven with xrandr `gamma`, averaged over all input levels.
+/// xrandr maps input `x` to `x^(1/gamma)`, whose mean over [0, 1] is `gamma / (gamma + 1)`;
+/// dividing by the neutral mean (1/2) makes 1.0 the unshifted output.
+fn channel_energy(gamma: f64) -> f64 {
+    if gamma <= 0.0 {
+        return 0.0;
+    }
+    2.0 * gamma / (gamma + 1.0)
+}
+
+/// Estimated blue light emitted relative to a neutral screen at full brightness [0.0..1.0]
+pub fn blue_light(brightness: f64, color_temp: f64) -> f64 {
+    let (_, _, blue) = crate::temp_to_gamma(color_temp);
+    channel_energy(blue) * brightness
+}
+
+/// Share of a neutral white's luminance contributed by each sRGB primary
+const LUMINANCE_WEIGHTS: [f64; 3] = [0.2126, 0.7152, 0.0722];
+
+/// Approximate melanopic daylight efficacy ratio (CIE S 026) of typical LCD primaries,
+/// chosen so that a neutral D65 white comes out at roughly 1.0
+const MELANOPIC_DER: [f64; 3] = [0.03, 0.8, 5.8];
+
+/// Illuminance at the eye from a neutral white screen at full brightness, unless configured
+pub const DEFAULT_SCREEN_LUX: f64 = 100.0;
+
+/// Estimated melanopic equivalent daylight illuminance (melanopic EDI, lux) at the eye.
+/// `screen_lux` is the photopic illuminance the screen produces at full neutral white.
+pub fn melanopic_edi(brightness: f64, color_temp: f64, screen_lux: f64) -> f64 {
+    let (red, green, blue) = crate::temp_to_gamma(color_temp);
+
+    let ratio: f64 = [red, green, blue]
+        .iter()
+        .zip(LUMINANCE_WEIGHTS)
+        .zip(MELANOPIC_DER)
+        .map(|((gamma, weight), der)| channel_energy(*gamma) * weight * der)
+        .sum();
+
+    screen_lux * brightness * ratio
+}
@@ -0,0 +1,79 @@
+use futures_util::StreamExt;
+use tokio::sync::watch;
+use zbus::{proxy, Connection};
+
+#[proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+trait Session {
+    #[zbus(property)]
+    fn locked_hint(&self) -> zbus::Result<bool>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.ScreenSaver",
+    default_service = "org.freedesktop.ScreenSaver",
+    default_path = "/org/freedesktop/ScreenSaver"
+)]
+trait ScreenSaver {
+    fn get_active(&self) -> zbus::Result<bool>;
+
+    #[zbus(signal)]
+    fn active_changed(&self, active: bool) -> zbus::Result<()>;
+}
+
+/// Whether the session is locked, as told by logind's `LockedHint` or the screensaver
+pub struct LockWatch {
+    logind: watch::Receiver<bool>,
+    screensaver: watch::Receiver<bool>,
+}
+
+impl LockWatch {
+    pub fn locked(&self) -> bool {
+        *self.logind.borrow() || *self.screensaver.borrow()
+    }
+}
+
+/// Follows the lock state in the background. A source that isn't available counts as unlocked.
+pub async fn watch() -> LockWatch {
+    let (logind_sender, logind) = watch::channel(false);
+    let (screensaver_sender, screensaver) = watch::channel(false);
+
+    tokio::spawn(async move {
+        if let Err(e) = follow_logind(logind_sender).await {
+            eprintln!("Lock state from logind unavailable: {}", e);
+        }
+    });
+    tokio::spawn(async move {
+        // Most desktops don't run a screensaver service; logind covers them
+        let _ = follow_screensaver(screensaver_sender).await;
+    });
+
+    LockWatch { logind, screensaver }
+}
+
+async fn follow_logind(locked: watch::Sender<bool>) -> zbus::Result<()> {
+    let connection = Connection::system().await?;
+    let session = SessionProxy::new(&connection).await?;
+    let mut changes = session.receive_locked_hint_changed().await;
+
+    locked.send_replace(session.locked_hint().await?);
+    while let Some(change) = changes.next().await {
+        locked.send_replace(change.get().await?);
+    }
+    Ok(())
+}
+
+async fn follow_screensaver(active: watch::Sender<bool>) -> zbus::Result<()> {
+    let connection = Connection::session().await?;
+    let screensaver = ScreenSaverProxy::new(&connection).await?;
+    let mut changes = screensaver.receive_active_changed().await?;
+
+    active.send_replace(screensaver.get_active().await?);
+    while let Some(change) = changes.next().await {
+        active.send_replace(*change.args()?.active());
+    }
+    Ok(())
+}
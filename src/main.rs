@@ -1,18 +1,132 @@
-use std::process::Command;
+mod activitywatch;
+mod als;
+mod announce;
+mod bar;
+mod battery;
+mod bedtime;
+mod blink;
+mod boost;
+mod breaks;
+mod calendar;
+mod calibrate;
+mod circadian;
+mod compare;
+mod config;
+mod content;
+mod contrast;
+mod crash;
+mod curve;
+mod daemon;
+mod daylight;
+mod dbus;
+mod doctor;
+mod events;
+mod exercises;
+mod exit;
+mod export;
+mod fade;
+mod focus;
+mod gamemode;
+mod goals;
+mod grayscale;
+mod gui;
+mod history;
+mod homeassistant;
+mod hooks;
+mod hotkeys;
+mod hue;
+mod html;
+mod http;
+mod idle;
+mod import;
+mod ipc;
+mod learn;
+mod lifx;
+mod light;
+mod lock;
+mod media;
+mod metrics;
+mod midi;
+mod mqtt;
+mod network;
+mod notify;
+mod pomodoro;
+mod portal;
+mod posture;
+mod powerprofiles;
+mod problem;
+mod profile;
+mod push;
+mod randr;
+mod reminders;
+mod roomlight;
+mod schedule;
+mod screentime;
+mod sharing;
+mod state;
+mod status;
+mod stretch;
+mod sunrise;
+mod telemetry;
+mod therapy;
+mod tray;
+mod tui;
+mod tuya;
+mod validate;
+mod video;
+mod webcam;
+mod weekly;
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+use std::sync::{Mutex, TryLockError};
 use std::time::Duration;
 
-use chrono::{DateTime, Utc, Timelike};
-use clap::Parser;
+use chrono::{DateTime, Datelike, Local, NaiveDate, Utc};
+use clap::{parser::ValueSource, ArgGroup, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use serde::Deserialize;
-use tokio::time::sleep;
-use nokhwa::{
-    Camera,
-    utils::{CameraFormat, CameraIndex, FrameFormat, RequestedFormat, RequestedFormatType},
-    pixel_format::RgbFormat
-};
+use serde_json::json;
 use dotenv::dotenv;
 
-#[derive(Parser, Debug)]
+use config::Config;
+use curve::Curve;
+use exit::Failure;
+use learn::Overrides;
+use problem::Problem;
+use randr::OutputInfo;
+use history::{Cycle, Source};
+use hooks::HookEvent;
+use hotkeys::Hotkey;
+use state::{Output, State};
+
+/// Minutes after which a remembered webcam reading is trusted only half as much
+const READING_HALF_LIFE_MINUTES: f64 = 30.0;
+
+/// Ambient levels assumed for the first estimate with the sun well up, and after dusk
+const ESTIMATE_DAY_AMBIENT: f64 = 0.6;
+const ESTIMATE_NIGHT_AMBIENT: f64 = 0.05;
+
+/// Seconds a weather estimate is reused; OpenWeather updates its data about every 10 minutes
+const WEATHER_MAX_AGE_SECS: i64 = 10 * 60;
+/// Seconds an IP-based location is reused; the daemon also looks it up again on a new network
+const IP_LOCATION_MAX_AGE_SECS: i64 = 6 * 60 * 60;
+/// How long the weather and location lookups may take
+const API_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long the camera gets before the fallback reading is shown in the meantime
+const PREVIEW_AFTER: Duration = Duration::from_millis(1500);
+
+/// Brightness used in darkroom mode unless configured otherwise
+const DARKROOM_BRIGHTNESS: f64 = 0.3;
+/// Darkroom mode shows red only; it's recorded as the warmest temperature
+const DARKROOM_GAMMA: (f64, f64, f64) = (1.0, 0.0, 0.0);
+const DARKROOM_TEMP: f64 = 1000.0;
+/// Untinted colors, as for exception apps and shared screens
+const NEUTRAL_GAMMA: (f64, f64, f64) = (1.0, 1.0, 1.0);
+const NEUTRAL_TEMP: f64 = 6500.0;
+/// Minutes values set by hand are held unless configured otherwise
+const DEFAULT_MANUAL_HOLD_MINUTES: f64 = 60.0;
+
+#[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 struct Args {
     /// OpenWeather API key for weather data (required only if webcam is not available)
@@ -23,6 +137,18 @@ struct Args {
     #[arg(long, default_value_t = 0.6)]
     min_brightness: f64,
 
+    /// Minimum brightness level at night, blended with --min-brightness during the transition
+    #[arg(long)]
+    night_min_brightness: Option<f64>,
+
+    /// Exponent applied to the ambient light level; above 1.0 dims more in dark rooms
+    #[arg(long, default_value_t = 1.0)]
+    brightness_exponent: f64,
+
+    /// Maximum brightness level (0.0 to 1.0), overridable per monitor in the config
+    #[arg(long, default_value_t = 1.0)]
+    max_brightness: f64,
+
     /// Color temperature during day (Kelvin)
     #[arg(long, default_value_t = 6500.0)]
     day_temp: f64,
@@ -35,9 +161,371 @@ struct Args {
     #[arg(long, default_value_t = 2.0)]
     transition_hours: f64,
 
+    /// Whether `--transition-hours` was given, in which case it also wins over `[schedule]`
+    #[arg(skip)]
+    transition_hours_given: bool,
+
+    /// Maximum brightness change per run, spreading large jumps over several runs
+    #[arg(long)]
+    max_step: Option<f64>,
+
     /// Comma-separated list of monitor names (e.g., "DP-0,HDMI-0")
     #[arg(long, value_delimiter = ',')]
     monitors: Option<Vec<String>>,
+
+    /// Only adjust the primary output, leaving e.g. a TV or a reference display alone
+    #[arg(long)]
+    primary_only: bool,
+
+    /// Path to the TOML config file [default: ~/.config/healthy-monitor/config.toml]
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Fail instead of falling back from the webcam to a remembered reading or the weather
+    #[arg(long)]
+    strict: bool,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Commands {
+    /// Import settings from another tool into the config file
+    Import {
+        #[command(subcommand)]
+        source: ImportSource,
+    },
+
+    /// Keep running and update settings periodically
+    Daemon {
+        /// Seconds between updates
+        #[arg(long, default_value_t = 300)]
+        interval: u64,
+    },
+
+    /// Show the most recently applied settings
+    Status {
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show screen time recorded by the daemon
+    Report {
+        /// Day to report on (YYYY-MM-DD) [default: today]
+        #[arg(long)]
+        date: Option<NaiveDate>,
+
+        /// Summarize the seven days ending on the date instead
+        #[arg(long)]
+        week: bool,
+
+        /// Write a self-contained HTML page with charts to this file instead
+        #[arg(long, value_name = "FILE", conflicts_with = "week")]
+        html: Option<PathBuf>,
+    },
+
+    /// Write recorded screen time and light exposure per day, or every update cycle, to stdout
+    Export {
+        /// First day to include (YYYY-MM-DD)
+        #[arg(long)]
+        from: Option<NaiveDate>,
+
+        /// Last day to include (YYYY-MM-DD)
+        #[arg(long)]
+        to: Option<NaiveDate>,
+
+        #[arg(long, value_enum, default_value_t = export::Format::Csv)]
+        format: export::Format,
+
+        /// Export every recorded update cycle instead of daily totals
+        #[arg(long)]
+        cycles: bool,
+    },
+
+    /// Suggest a schedule fitted to your recorded activity and light exposure
+    Suggest {
+        /// Write the suggested schedule into the config file
+        #[arg(long)]
+        apply: bool,
+    },
+
+    /// Run focus/break cycles, warming and dimming the screen during breaks
+    Pomodoro {
+        /// Focus and break minutes, e.g. "25/5"
+        #[arg(default_value = "25/5")]
+        cycle: pomodoro::Cycle,
+    },
+
+    /// Control break reminders
+    Breaks {
+        #[command(subcommand)]
+        action: BreaksAction,
+    },
+
+    /// Apply a temperature and brightness temporarily, then restore automatic settings
+    Preview {
+        /// Color temperature to preview (Kelvin) [default: current scheduled temperature]
+        #[arg(long)]
+        temp: Option<f64>,
+
+        /// Brightness to preview (0.0 to 1.0) [default: last applied brightness]
+        #[arg(long)]
+        brightness: Option<f64>,
+
+        /// How long to keep the preview, e.g. "10s", "2m"
+        #[arg(long = "for", default_value = "10s", value_parser = parse_duration)]
+        duration: Duration,
+    },
+
+    /// Activate a profile from the config, or "auto" to return control to the schedule
+    Profile {
+        name: String,
+    },
+
+    /// Guided eye exercises, one notification per step
+    EyeExercises,
+
+    /// Full brightness, neutral white session in place of a light box, then back to automatic control
+    LightTherapy {
+        /// Session length, e.g. "30m"
+        #[arg(default_value = "30m", value_parser = parse_duration)]
+        duration: Duration,
+
+        /// Only use this monitor (e.g. "DP-1")
+        #[arg(long)]
+        monitor: Option<String>,
+    },
+
+    /// Live terminal dashboard with hotkeys for pausing and overrides
+    Tui,
+
+    /// System tray icon with quick controls (needs the `tray` feature)
+    Tray,
+
+    /// Settings window for the temperatures, brightness bounds and monitors (needs the `gui` feature)
+    Gui,
+
+    /// Nudge the brightness and teach the curve to do the same at this ambient level next time
+    Feedback {
+        #[arg(value_enum)]
+        direction: Nudge,
+    },
+
+    /// Measure dark and room light with the webcam and pick a comfortable brightness for each
+    Calibrate,
+
+    /// Find preferred night settings by picking between two alternating candidates
+    Compare {
+        /// Profile to save the night settings to
+        #[arg(long, default_value = "night")]
+        profile: String,
+
+        /// How long each candidate is shown, e.g. "3s"
+        #[arg(long, default_value = "3s", value_parser = parse_duration)]
+        every: Duration,
+    },
+
+    /// Set brightness or temperature by hand, through the running daemon if there is one.
+    /// The values are held for `manual_hold_minutes`; a brightness choice is learned from.
+    #[command(group(ArgGroup::new("value").required(true).multiple(true).args(["brightness", "temp"])))]
+    Set {
+        /// Brightness (0.0 to 1.0)
+        #[arg(long)]
+        brightness: Option<f64>,
+
+        /// Color temperature (Kelvin)
+        #[arg(long)]
+        temp: Option<f64>,
+    },
+
+    /// Show or reset what was learned from manual overrides and feedback
+    Model {
+        #[arg(value_enum, default_value_t = ModelAction::Show)]
+        action: ModelAction,
+    },
+
+    /// Summarize recorded data
+    Analyze {
+        #[command(subcommand)]
+        target: AnalyzeTarget,
+    },
+
+    /// Lift bedtime enforcement until the end of tonight
+    OverrideTonight,
+
+    /// Switch to deep red, very dim output (e.g. for astronomy), or back to automatic control
+    Darkroom {
+        #[arg(value_enum, default_value_t = Toggle::Toggle)]
+        action: Toggle,
+    },
+
+    /// Pair with a Philips Hue bridge or list its lights
+    Hue {
+        #[command(subcommand)]
+        action: HueAction,
+    },
+
+    /// Run one of the global shortcut actions, for binding in the window manager or compositor
+    Hotkey {
+        #[arg(value_enum)]
+        action: Hotkey,
+    },
+
+    /// Hold the current settings, or resume automatic adjustments, through the running daemon if there is one
+    Pause {
+        #[arg(value_enum, default_value_t = Toggle::Toggle)]
+        action: Toggle,
+
+        /// Resume automatically after this long, e.g. "1h"
+        #[arg(long = "for", value_parser = parse_duration)]
+        duration: Option<Duration>,
+    },
+
+    /// Print status as JSON lines for a Waybar custom module (`"return-type": "json"`)
+    Waybar {
+        /// How often to refresh when no daemon announces changes, e.g. "5s"
+        #[arg(long, default_value = "5s", value_parser = parse_duration)]
+        interval: Duration,
+    },
+
+    /// Print a one-line status for polybar or i3status-rs, updated on every change
+    Bar {
+        /// Placeholders: {brightness} {kelvin} {period} {source} {ambient} {paused}
+        #[arg(long, default_value = "{brightness} {kelvin}")]
+        format: String,
+
+        /// How often to refresh when no daemon announces changes, e.g. "5s"
+        #[arg(long, default_value = "5s", value_parser = parse_duration)]
+        interval: Duration,
+    },
+
+    /// Check the session, xrandr, camera, light sensor, network, API key and config
+    Doctor,
+
+    /// Work with the config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum ConfigAction {
+    /// Report parse errors, unknown keys and out-of-range values, failing when there are any
+    Check,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum HueAction {
+    /// Register with the bridge; press its link button first
+    Pair {
+        /// Address of the bridge, e.g. "192.168.1.20"
+        #[arg(long)]
+        bridge: String,
+    },
+    /// List the bridge's lights, to pick ids for `lights` in `[room_lights.hue]`
+    Lights,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum BreaksAction {
+    /// Hold off break reminders for a while
+    Snooze {
+        /// How long to snooze, e.g. "30m", "1h"
+        #[arg(long = "for", default_value = "30m", value_parser = parse_duration)]
+        duration: Duration,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Nudge {
+    Dimmer,
+    Brighter,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum AnalyzeTarget {
+    /// When and in which light you override the automatic brightness
+    Overrides,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ModelAction {
+    Show,
+    Reset,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Toggle {
+    On,
+    Off,
+    Toggle,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum ImportSource {
+    /// Convert a redshift or gammastep config
+    Redshift {
+        /// Path to redshift.conf or gammastep's config.ini [default: autodetect]
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+impl Args {
+    /// Config file to write to: `--config`, or the default location
+    fn config_path(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        match &self.config {
+            Some(p) => Ok(p.clone()),
+            None => Ok(config::default_path().ok_or("Cannot determine the config location, pass it with --config")?),
+        }
+    }
+
+    /// Night boundaries for the day of `now`: the schedule's, with a transition length from the
+    /// command line overriding any the schedule sets
+    fn day_times(&self, config: &Config, now: DateTime<Local>) -> schedule::DayTimes {
+        let mut times = config.schedule.times(now, self.transition_hours);
+        if self.transition_hours_given {
+            times.transition_hours = self.transition_hours;
+        }
+        times
+    }
+
+    /// Fills in config file values for options that weren't given on the command line
+    fn apply_config(&mut self, config: &Config, matches: &ArgMatches) {
+        let explicit = |id: &str| {
+            matches!(
+                matches.value_source(id),
+                Some(ValueSource::CommandLine) | Some(ValueSource::EnvVariable)
+            )
+        };
+
+        macro_rules! merge {
+            ($($field:ident),*) => {
+                $(
+                    if !explicit(stringify!($field)) {
+                        if let Some(value) = config.$field.clone() {
+                            self.$field = value.into();
+                        }
+                    }
+                )*
+            };
+        }
+
+        merge!(api_key, min_brightness, night_min_brightness, brightness_exponent, max_brightness, day_temp, night_temp, transition_hours, max_step, monitors, primary_only);
+        self.transition_hours_given = explicit("transition_hours");
+
+        if let Some(step) = self.max_step.filter(|step| !valid_step(*step)) {
+            eprintln!("Ignoring max_step {}: it must be a positive number", step);
+            self.max_step = None;
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -64,45 +552,934 @@ struct LocationApiResponse {
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Parse command line arguments
-    let args = Args::parse();
-
-    // Try webcam first
-    match detect_brightness_from_webcam(args.min_brightness) {
-        Ok(brightness) => {
-            if let Err(e) = set_monitor_brightness(brightness, &args) {
-                eprintln!("Failed to set brightness: {}", e);
-            }
-        }
+async fn main() -> ExitCode {
+    match try_main().await {
+        Ok(()) => ExitCode::SUCCESS,
         Err(e) => {
-            eprintln!("Webcam not available ({}), falling back to weather API", e);
-            
-            // Check if API key is provided when falling back to weather API
-            let api_key = args.api_key.clone().ok_or("OpenWeather API key is required when webcam is not available")?;
-            
-            // Fall back to weather API
-            let location = fetch_location().await?;
-            let lat = location.lat.to_string();
-            let lon = location.lon.to_string();
-
-            match fetch_weather(&lat, &lon, &api_key).await {
-                Ok(weather_data) => {
-                    let brightness = compute_brightness(&weather_data, args.min_brightness);
-                    if let Err(e) = set_monitor_brightness(brightness, &args) {
-                        eprintln!("Failed to set brightness: {}", e);
+            eprintln!("Error: {}", problem::present(e.as_ref()));
+            exit::code(e.as_ref())
+        }
+    }
+}
+
+async fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    // Parse command line arguments, falling back to the config file
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches)?;
+    // `doctor` and `config check` report a broken config themselves, and `import` writes
+    // a new one, so it must not fail on a missing or broken file it is about to replace
+    let config = if matches!(args.command, Some(Commands::Import { .. })) {
+        Config::default()
+    } else {
+        match Config::load(args.config.as_deref()) {
+            Ok(config) => config,
+            Err(_) if matches!(args.command, Some(Commands::Doctor | Commands::Config { .. })) => Config::default(),
+            Err(e) => return Err(exit::fail(Failure::Config, e)),
+        }
+    };
+    args.apply_config(&config, &matches);
+
+    // Only commands that change the display have anything to put back after a crash
+    let changes_display = matches!(
+        args.command,
+        None | Some(Commands::Daemon { .. } | Commands::Preview { .. } | Commands::LightTherapy { .. })
+    );
+    if changes_display {
+        crash::install(&args);
+    }
+
+    if let Some(telemetry_config) = &config.telemetry {
+        if let Err(e) = telemetry::init(telemetry_config) {
+            eprintln!("Telemetry export unavailable: {}", e);
+        }
+    }
+
+    let result = match &args.command {
+        Some(Commands::Import { source: ImportSource::Redshift { file, force } }) => {
+            import_redshift_config(file.as_deref(), *force, &args)
+        }
+        Some(Commands::Daemon { interval }) => {
+            crash::restore_on_signal(&args)?;
+            daemon::run(&args, &config, Duration::from_secs(*interval)).await
+        }
+        Some(Commands::Status { json }) => status::print(*json, &config),
+        Some(Commands::Report { date, week, html }) => {
+            let date = date.unwrap_or_else(|| Local::now().date_naive());
+            if let Some(path) = html {
+                html::write_report(path, date)
+            } else {
+                let mut history = screentime::ScreenTime::load();
+                if let Some(activitywatch) = config.activitywatch.as_ref().filter(|aw| aw.screen_time) {
+                    let first = if *week { date - chrono::Duration::days(6) } else { date };
+                    activitywatch::merge(activitywatch, &mut history, first, date).await;
+                }
+
+                if *week {
+                    weekly::report(&history, date, &config.schedule)
+                } else {
+                    screentime::report(&history, date)?;
+                    match &config.goals {
+                        Some(goals_config) => goals::report(goals_config, date),
+                        None => Ok(()),
                     }
-                },
+                }
+            }
+        }
+        Some(Commands::Export { from, to, format, cycles }) => {
+            if *cycles {
+                export::export_cycles(*from, *to, *format)
+            } else {
+                export::export(*from, *to, *format)
+            }
+        }
+        Some(Commands::Suggest { apply }) => {
+            circadian::suggest(*apply, &args.config_path()?)
+        }
+        Some(Commands::Pomodoro { cycle }) => pomodoro::run(*cycle, &args, &config).await,
+        Some(Commands::Breaks { action: BreaksAction::Snooze { duration } }) => breaks::snooze(*duration),
+        Some(Commands::Preview { temp, brightness, duration }) => {
+            preview(*temp, *brightness, *duration, &args, &config).await
+        }
+        Some(Commands::Profile { name }) => switch_profile(name, &args, &config).await,
+        Some(Commands::EyeExercises) => {
+            exercises::routine(&config.eye_exercises.clone().unwrap_or_default()).await
+        }
+        Some(Commands::LightTherapy { duration, monitor }) => {
+            therapy::run(*duration, monitor.as_deref(), &args, &config).await
+        }
+        Some(Commands::Tui) => tui::run(&args, &config).await,
+        Some(Commands::Tray) => tray::run(&args, &config).await,
+        Some(Commands::Gui) => gui::run(&args),
+        Some(Commands::Feedback { direction }) => feedback(*direction, &args, &config),
+        Some(Commands::Calibrate) => {
+            calibrate::wizard(&args, &config, &args.config_path()?).await
+        }
+        Some(Commands::Compare { profile, every }) => {
+            compare::run(profile, *every, &args, &config, &args.config_path()?).await
+        }
+        Some(Commands::Set { brightness, temp }) => set(*brightness, *temp, &args, &config).await,
+        Some(Commands::Model { action: ModelAction::Show }) => {
+            learn::show(|ambient| default_brightness(ambient, args.min_brightness, &args, &config))
+        }
+        Some(Commands::Model { action: ModelAction::Reset }) => learn::reset(),
+        Some(Commands::Analyze { target: AnalyzeTarget::Overrides }) => {
+            learn::analyze(|ambient| default_brightness(ambient, args.min_brightness, &args, &config))
+        }
+        Some(Commands::OverrideTonight) => override_tonight(&args, &config).await,
+        Some(Commands::Darkroom { action }) => darkroom(*action, &args, &config).await,
+        Some(Commands::Hue { action: HueAction::Pair { bridge } }) => hue::pair(bridge, &args.config_path()?).await,
+        Some(Commands::Hue { action: HueAction::Lights }) => {
+            let hue = config.room_lights.as_ref().and_then(|r| r.hue.clone()).unwrap_or_default();
+            hue::lights(&hue).await
+        }
+        Some(Commands::Hotkey { action }) => hotkey(*action, &args, &config).await,
+        Some(Commands::Pause { action, duration }) => pause(*action, *duration, &args, &config).await,
+        Some(Commands::Waybar { interval }) => bar::waybar(*interval, &config).await,
+        Some(Commands::Bar { format, interval }) => bar::line(format, *interval, &config).await,
+        Some(Commands::Doctor) => doctor::run(&args).await,
+        Some(Commands::Config { action: ConfigAction::Check }) => validate::check(args.config.as_deref()),
+        None => run(&args, &config).await,
+    };
+
+    telemetry::shutdown();
+    result
+}
+
+/// Measures ambient light once and applies the resulting settings
+async fn run(args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    // Nobody is looking: leave the camera off and the screen as it is until activity resumes
+    if let Some(minutes) = config.idle_minutes {
+        if idle::is_idle(minutes) {
+            return Ok(());
+        }
+    }
+
+    // Traced as sense → compute → apply under one span per update
+    let trace = telemetry::Trace::update();
+
+    let mut state = State::load();
+
+    announce::expire_overrides(config, &mut state);
+
+    // Paused from the dashboard: hold the current settings
+    if state.paused {
+        return state.save();
+    }
+
+    // Values set by hand stay until the hold runs out
+    if state.manual_until.is_some() {
+        return state.save();
+    }
+
+    // Leave gamma and the camera alone while a game runs, for frame pacing and in-game colors
+    if config.pause_during_games && gamemode::active().await {
+        return state.save();
+    }
+
+    // Keep the screen as it is through meetings and presentations, once there is something to keep
+    if let Some(calendar) = &config.calendar {
+        let busy = calendar::busy(calendar, &mut state).await;
+        if let Some((brightness, color_temp)) = state.brightness.zip(state.color_temp).filter(|_| busy) {
+            let cycle = Cycle {
+                at: Utc::now().timestamp(),
+                ambient: None,
+                fused: None,
+                brightness,
+                color_temp,
+                source: Source::Calendar,
+            };
+            if let Err(e) = history::record(config, &cycle) {
+                eprintln!("Failed to record history: {}", e);
+            }
+            return state.save();
+        }
+    }
+
+    let focus = focus::check(config);
+
+    // Battery overrides come first so a profile can still change the same settings
+    let power = if config.battery.is_some() { battery::power().await } else { None };
+    let battery_args;
+    let args = match &config.battery {
+        Some(battery) if power.map_or(false, |p| p.on_battery) => {
+            battery_args = battery.apply(args);
+            &battery_args
+        }
+        _ => args,
+    };
+
+    let profile = profile::active(config, &focus, &state);
+    let profiled_args;
+    let args = match profile {
+        Some(profile) => {
+            profiled_args = profile.apply(args);
+            &profiled_args
+        }
+        None => args,
+    };
+
+    // Hold the current settings steady while a video plays so it isn't re-tinted mid-scene
+    if !state.darkroom && config.pause_during_playback && (focus.fullscreen || media::playing()) {
+        return state.save();
+    }
+
+    let now = Local::now();
+    let times = args.day_times(config, now);
+    let point = schedule::at(now, &times);
+
+    // Fixed settings skip sensing, but are applied, recorded and saved like measured ones
+    let target = if let Some(target) = fixed_target(args, config, &focus, &state, now) {
+        target
+    } else {
+        // The brightness floor follows the schedule when a separate night floor is set
+        let min_brightness = match args.night_min_brightness {
+            Some(night_min) => point.blend(args.min_brightness, night_min),
+            None => args.min_brightness,
+        };
+
+        // Ambient light maps to brightness through the configured curve, its learned corrections and
+        // what was learned from manual overrides, which gradually takes over from the default mapping
+        let curve = Curve::load();
+        let overrides = Overrides::load().fit(schedule::hour_of(now));
+        let map = |ambient: f64| {
+            let corrected = (default_brightness(ambient, min_brightness, args, config) + curve.offset(ambient)).clamp(0.0, 1.0);
+            match &overrides {
+                Some(fit) => fit.blend(ambient, corrected),
+                None => corrected,
+            }
+        };
+
+        // Power saving and a low battery cap how bright the screen may get
+        let power_saving = match &config.power_saver {
+            Some(power_saver) if powerprofiles::power_saver().await => Some(power_saver.max_brightness),
+            _ => None,
+        };
+        let low_battery = config.battery.as_ref().and_then(|battery| battery::low_battery_cap(battery, power, &mut state));
+        let cap = |brightness: f64| power_saving.into_iter().chain(low_battery).fold(brightness, f64::min);
+
+        // Shown while the camera is still warming up, at the temperature already applied. It isn't
+        // saved, so the final value still steps from what was applied before.
+        let preview = |ambient: f64, state: &mut State| {
+            let Some((previous, color_temp)) = state.brightness.zip(state.color_temp) else {
+                return;
+            };
+            let brightness = match args.max_step.filter(|step| valid_step(*step)) {
+                Some(step) => previous + (map(ambient) - previous).clamp(-step, step),
+                None => map(ambient),
+            };
+            if let Err(e) = set_monitor_brightness(cap(brightness), color_temp, args, config) {
+                eprintln!("Failed to apply the first reading: {}", e);
+            }
+        };
+
+        let mut sense = trace.span("sense");
+        let (brightness, source, fused) = match profile.and_then(|p| p.brightness) {
+            Some(fixed) => (fixed, Source::Profile, None),
+            None => match sense_ambient(args, config, &mut state, preview).await.map_err(|e| exit::fail(Failure::Sensing, e))? {
+                Some((ambient, source)) => {
+                    state.ambient_level = Some(ambient);
+                    metrics::record_ambient_level(ambient);
+                    (map(ambient), source, Some(ambient))
+                }
+                None => return Err(exit::fail(Failure::Sensing, "No ambient light reading, keeping the previous settings")),
+            },
+        };
+        sense.set("source", source.name());
+        sense.end();
+        let mut compute = trace.span("compute");
+
+        // Limit how far brightness may move from the previously applied value
+        // Profiles can bring their own step, so it's checked here too; clamp panics on a negative one
+        let mut brightness = match (state.brightness, args.max_step.filter(|step| valid_step(*step))) {
+            (Some(previous), Some(step)) => previous + (brightness - previous).clamp(-step, step),
+            _ => brightness,
+        };
+
+        let mut color_temp = profile
+            .and_then(|p| p.temp)
+            .unwrap_or_else(|| point.blend(args.day_temp, args.night_temp));
+
+        // Artificial dawn: rise slowly from the night values once night ends
+        if let Some(sunrise) = &config.sunrise {
+            if let Some(progress) = sunrise::progress(sunrise, times.night_end, schedule::hour_of(now)) {
+                let night_floor = args.night_min_brightness.unwrap_or(args.min_brightness);
+                brightness = sunrise::ramp(progress, night_floor.min(brightness), brightness);
+                color_temp = sunrise::ramp(progress, args.night_temp.min(color_temp), color_temp);
+            }
+        }
+
+        // Wind down toward the bedtime values over the last stretch before bed
+        let (brightness, color_temp) = match config.schedule.lookup(now.weekday(), |d| d.bedtime) {
+            Some(bedtime) => {
+                let hour = schedule::hour_of(now);
+                let progress = bedtime::progress(&config.bedtime, bedtime.0, times.night_end, hour);
+                if !notify::quiet(&config.quiet) {
+                    bedtime::remind(&config.bedtime, config.push.as_ref(), &mut state, bedtime.0, hour, progress).await;
+                }
+                let (brightness, color_temp) = bedtime::wind_down(&config.bedtime, progress, brightness, color_temp);
+                bedtime::enforce(&config.bedtime, &state, bedtime.0, times.night_end, hour, brightness, color_temp)
+            }
+            None => (brightness, color_temp),
+        };
+
+        let brightness = cap(brightness);
+
+        // Bright content on screen is dimmed a little further
+        let brightness = match &config.screen_content {
+            Some(content) => match content::luma(&mut state).await {
+                Ok(luma) => content::compensate(content, brightness, luma),
                 Err(e) => {
-                    eprintln!("Failed to fetch weather data: {}", e);
-                },
+                    eprintln!("Screen content not available: {}", e);
+                    brightness
+                }
+            },
+            None => brightness,
+        };
+
+        compute.end();
+
+        Target { brightness, color_temp, gamma: None, source, fused }
+    };
+    let Target { brightness, color_temp, gamma, source, fused } = target;
+
+    // Fade from the previously applied values when configured, otherwise switch at once
+    let mut apply = trace.span("apply");
+    apply.set("brightness", brightness);
+    apply.set("color_temp", color_temp);
+    let fade_duration = Duration::from_secs_f64(config.fade_seconds.unwrap_or(0.0).max(0.0));
+    let result = match (gamma, state.brightness, state.color_temp) {
+        (Some(gamma), _, _) => set_monitor_gamma(brightness, gamma, args, config),
+        (None, Some(prev_brightness), Some(prev_temp)) if !fade_duration.is_zero() => {
+            fade::fade(
+                (prev_brightness, prev_temp),
+                (brightness, color_temp),
+                fade_duration,
+                config.panel_bits,
+                |b, t| set_monitor_brightness(b, t, args, config),
+            )
+            .await
+        }
+        _ => set_monitor_brightness(brightness, color_temp, args, config),
+    };
+
+    let applied = match result {
+        Ok(()) => {
+            let details = json!({ "brightness": brightness, "color_temp": color_temp, "source": source.name() });
+            hooks::fire(config, HookEvent::Applied, details);
+            if let Some(activitywatch) = config.activitywatch.as_ref().filter(|aw| aw.publish) {
+                activitywatch::publish(activitywatch, brightness, color_temp, point.period.name()).await;
             }
+            Ok(())
+        }
+        Err(e) => Err(exit::fail(Failure::Display, e)),
+    };
+    apply.end();
+
+    // Notify hooks when the period of the day changed since the last run
+    if state.period != Some(point.period) {
+        hooks::period_changed(config, state.period, point.period, brightness, color_temp);
+        let details = json!({ "brightness": brightness, "color_temp": color_temp });
+        if point.period == schedule::Period::Night && state.period.is_some() {
+            hooks::fire(config, HookEvent::NightStarted, details);
+        } else if state.period == Some(schedule::Period::Night) {
+            hooks::fire(config, HookEvent::NightEnded, details);
+        }
+        if state.period.is_some() {
+            announce::period_changed(config, point.period, color_temp);
+        }
+        state.period = Some(point.period);
+    }
+
+    // Room lights wind down along with the screen
+    if let Some(room_lights) = &config.room_lights {
+        let room_brightness = point.blend(room_lights.day_brightness, room_lights.night_brightness);
+        if roomlight::sync(room_lights, color_temp, room_brightness, state.room_light).await {
+            state.room_light = Some([color_temp, room_brightness]);
+        }
+    }
+
+    // Health warnings wait while quiet; brightness control above carries on regardless
+    if !notify::quiet(&config.quiet) {
+        if let Some(contrast) = &config.contrast_warning {
+            contrast::update(contrast, &mut state, brightness);
+        }
+
+        if let Some(nudge) = &config.daylight_nudge {
+            daylight::update(nudge, config, &mut state).await;
         }
     }
 
+    if let Some(grayscale) = &config.grayscale {
+        grayscale::update(grayscale, &mut state, schedule::hour_of(now));
+    }
+
+    let cycle = Cycle {
+        at: now.timestamp(),
+        ambient: matches!(source, Source::Webcam | Source::Remembered).then_some(state.ambient).flatten(),
+        fused,
+        brightness,
+        color_temp,
+        source,
+    };
+    if let Err(e) = history::record(config, &cycle) {
+        eprintln!("Failed to record history: {}", e);
+    }
+
+    state.brightness = Some(brightness);
+    state.color_temp = Some(color_temp);
+    state.source = Some(source);
+    state.outputs = output_results();
+    if let Err(e) = state.save() {
+        eprintln!("Failed to save state: {}", e);
+    }
+
+    applied
+}
+
+/// Settings an update applies, and what they were based on
+struct Target {
+    brightness: f64,
+    color_temp: f64,
+    /// Channel multipliers applied as they are; `color_temp` is then only recorded
+    gamma: Option<(f64, f64, f64)>,
+    source: Source,
+    /// Ambient level the brightness was mapped from
+    fused: Option<f64>,
+}
+
+/// Settings that take the place of measured ones: darkroom mode, an exception app in focus,
+/// a shared screen and the morning boost, in that order
+fn fixed_target(args: &Args, config: &Config, focus: &focus::Focus, state: &State, now: DateTime<Local>) -> Option<Target> {
+    let fixed = |brightness, color_temp, gamma, source| Target { brightness, color_temp, gamma, source, fused: None };
+
+    // Darkroom mode stays in place until it's switched off
+    if state.darkroom {
+        let brightness = config.darkroom_brightness.unwrap_or(DARKROOM_BRIGHTNESS);
+        return Some(fixed(brightness, DARKROOM_TEMP, Some(DARKROOM_GAMMA), Source::Darkroom));
+    }
+
+    // Exception apps get neutral colors and full brightness while focused
+    if focus.exempt {
+        return Some(fixed(1.0, NEUTRAL_TEMP, Some(NEUTRAL_GAMMA), Source::Exempt));
+    }
+
+    // Viewers of a shared screen shouldn't see the night tint
+    if config.neutral_while_sharing && sharing::active(config) {
+        let brightness = state.brightness.unwrap_or(1.0);
+        return Some(fixed(brightness, NEUTRAL_TEMP, Some(NEUTRAL_GAMMA), Source::Sharing));
+    }
+
+    // Winter morning light boost overrides ambient readings entirely
+    let boost = config.morning_boost.as_ref()?;
+    boost::active(boost, &config.schedule, now).then(|| fixed(args.max_brightness, boost.temp, None, Source::Boost))
+}
+
+/// Reads the ambient light level [0.0..1.0] from the webcam, falling back to the light sensor or
+/// weather data, along with where it came from. Returns `None` when the fallbacks failed too.
+/// `preview` is handed the fallback level when the camera is slow and the fallback is ready first.
+async fn sense_ambient(
+    args: &Args,
+    config: &Config,
+    state: &mut State,
+    preview: impl FnOnce(f64, &mut State),
+) -> Result<Option<(f64, Source)>, Box<dyn std::error::Error>> {
+    let from_weather = |ambient: Option<f64>| ambient.map(|a| (a, Source::Weather));
+
+    let now_ts = Utc::now().timestamp();
+
+    // Don't fight a video call for the camera (and flicker its LED); lean on the last reading,
+    // trusting it less the older it gets
+    state.camera_busy = webcam::in_use_by_other_process();
+    state.ambient_confidence = None;
+
+    if state.camera_busy {
+        if args.strict {
+            return Err(exit::fail(Failure::Sensing, "Webcam is in use by another application"));
+        }
+        let Some((last, at)) = state.ambient.zip(state.ambient_at) else {
+            eprintln!("Webcam is in use by another application, falling back to weather API");
+            return weather_ambient(args, config, state).await.map(from_weather);
+        };
+
+        let age_minutes = (now_ts - at).max(0) as f64 / 60.0;
+        let confidence = 0.5f64.powf(age_minutes / READING_HALF_LIFE_MINUTES);
+        state.ambient_confidence = Some(confidence);
+
+        return match weather_ambient(args, config, state).await {
+            Ok(Some(weather)) => Ok(Some((last * confidence + weather * (1.0 - confidence), Source::Remembered))),
+            _ => Ok(Some((last, Source::Remembered))),
+        };
+    }
+
+    // A camera that takes a while to warm up gets the light sensor, or else the weather, read in the
+    // meantime. When that's ready first it's shown, and the camera refines it once it has a frame.
+    let capture = webcam::capture(config.capture_resolution.unwrap_or_default(), config.posture.is_some());
+    let mut capture = std::pin::pin!(capture);
+    let captured = tokio::select! {
+        result = &mut capture => Some(result),
+        _ = tokio::time::sleep(PREVIEW_AFTER) => None,
+    };
+    let (capture, fallback) = match captured {
+        Some(result) => (result, None),
+        None => {
+            let raced = {
+                let mut fallback = std::pin::pin!(fallback_ambient(args, config, state));
+                tokio::select! {
+                    result = &mut capture => Err(result),
+                    ready = &mut fallback => Ok(ready),
+                }
+            };
+            match raced {
+                Err(result) => (result, None),
+                Ok(ready) => {
+                    if let Ok(Some((ambient, _))) = &ready {
+                        preview(*ambient, state);
+                    }
+                    (capture.await, Some(ready))
+                }
+            }
+        }
+    };
+
+    // Webcam readings win over the light sensor and the weather
+    match capture {
+        Ok(reading) => {
+            if let (Some(posture), Some(frame)) = (&config.posture, &reading.gray) {
+                match posture::face_width(posture, frame) {
+                    Ok(width) if !notify::quiet(&config.quiet) => posture::update(posture, state, width),
+                    Ok(_) => {}
+                    Err(e) => eprintln!("Face detection failed: {}", e),
+                }
+            }
+
+            metrics::record_luma(reading.ambient);
+            state.ambient = Some(reading.ambient);
+            state.ambient_at = Some(now_ts);
+            state.webcam_failed = false;
+            Ok(Some((reading.ambient, Source::Webcam)))
+        }
+        Err(e) => {
+            eprintln!("Webcam not available ({}), falling back to the light sensor or weather API", e);
+            if let Some(hint) = problem::hint(e.as_ref()) {
+                eprintln!("  hint: {}", hint);
+            }
+            metrics::record_sensor_error("webcam");
+            if !state.webcam_failed {
+                hooks::fire(config, HookEvent::SensorFallback, json!({ "sensor": "webcam", "error": e.to_string() }));
+            }
+            announce::webcam_failed(config, state, &e.to_string());
+            if args.strict {
+                return Err(exit::fail(Failure::Sensing, "No webcam reading, and --strict rules out the weather"));
+            }
+            match fallback {
+                Some(ready) => ready,
+                None => fallback_ambient(args, config, state).await,
+            }
+        }
+    }
+}
+
+/// The light sensor reading, or the weather estimate when there is no sensor. Nothing under
+/// `--strict`, which rules out anything but the webcam.
+async fn fallback_ambient(args: &Args, config: &Config, state: &mut State) -> Result<Option<(f64, Source)>, Box<dyn std::error::Error>> {
+    if args.strict {
+        return Ok(None);
+    }
+    if let Some(ambient) = tokio::task::spawn_blocking(als::read).await? {
+        return Ok(Some((ambient, Source::Sensor)));
+    }
+    Ok(weather_ambient(args, config, state).await?.map(|ambient| (ambient, Source::Weather)))
+}
+
+/// Applies settings from the clock and the sun position alone, without the camera or network,
+/// so the screen settles right away while the first real update is still sensing
+fn apply_estimate(args: &Args, config: &Config) {
+    let mut state = State::load();
+    if state.paused || state.manual_until.is_some() || state.darkroom {
+        return;
+    }
+
+    let now = Local::now();
+    let times = args.day_times(config, now);
+    let point = schedule::at(now, &times);
+
+    // The sun from civil dusk (-6°) up to 30° covers the range; the schedule stands in without a location
+    let location = config.location.map(|l| (l.lat, l.lon)).or(state.ip_location.map(|[lat, lon]| (lat, lon)));
+    let ambient = match location {
+        Some((lat, lon)) => {
+            let elevation = daylight::sun_elevation(lat, lon, now.with_timezone(&Utc));
+            let sun = ((elevation + 6.0) / 36.0).clamp(0.0, 1.0);
+            ESTIMATE_NIGHT_AMBIENT + (ESTIMATE_DAY_AMBIENT - ESTIMATE_NIGHT_AMBIENT) * sun
+        }
+        None => point.blend(ESTIMATE_DAY_AMBIENT, ESTIMATE_NIGHT_AMBIENT),
+    };
+
+    let min_brightness = match args.night_min_brightness {
+        Some(night_min) => point.blend(args.min_brightness, night_min),
+        None => args.min_brightness,
+    };
+    let brightness = default_brightness(ambient, min_brightness, args, config);
+    let color_temp = point.blend(args.day_temp, args.night_temp);
+    if let Err(e) = set_monitor_brightness(brightness, color_temp, args, config) {
+        eprintln!("Failed to apply the first estimate: {}", e);
+        return;
+    }
+
+    // Later updates fade from here
+    state.brightness = Some(brightness);
+    state.color_temp = Some(color_temp);
+    if let Err(e) = state.save() {
+        eprintln!("Failed to save state: {}", e);
+    }
+}
+
+/// Estimates the ambient light level from weather data and the time of day.
+/// Returns `None` when the weather data couldn't be fetched.
+async fn weather_ambient(args: &Args, config: &Config, state: &mut State) -> Result<Option<f64>, Box<dyn std::error::Error>> {
+    let now = Utc::now().timestamp();
+    if let Some((ambient, at)) = state.weather.zip(state.weather_at) {
+        if now - at < WEATHER_MAX_AGE_SECS {
+            return Ok(Some(ambient));
+        }
+    }
+
+    // Check if API key is provided when falling back to weather API
+    let api_key = args.api_key.clone().ok_or("OpenWeather API key is required when webcam is not available")?;
+
+    let (lat, lon) = locate(config, state).await?;
+    let lat = lat.to_string();
+    let lon = lon.to_string();
+
+    match fetch_weather(&lat, &lon, &api_key).await {
+        Ok(weather_data) => {
+            let ambient = compute_brightness(&weather_data);
+            state.weather = Some(ambient);
+            state.weather_at = Some(now);
+            Ok(Some(ambient))
+        }
+        Err(e) => {
+            eprintln!("Failed to fetch weather data: {}", e);
+            Ok(None)
+        },
+    }
+}
+
+/// Activates a configured profile, or returns to automatic control with "auto"
+async fn switch_profile(name: &str, args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = State::load();
+    state.profile = if name == "auto" {
+        None
+    } else if config.profile.contains_key(name) {
+        Some(name.to_string())
+    } else {
+        let mut known: Vec<&str> = config.profile.keys().map(String::as_str).collect();
+        known.sort();
+        return Err(format!("Unknown profile '{}' (configured: {})", name, known.join(", ")).into());
+    };
+    state.save()?;
+
+    run(args, config).await
+}
+
+/// Nudges the applied brightness one step and records the correction for the current ambient level
+fn feedback(direction: Nudge, args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = State::load();
+    let ambient = state.ambient_level.ok_or("No ambient reading yet, run an update first")?;
+    let delta = match direction {
+        Nudge::Dimmer => -curve::STEP,
+        Nudge::Brighter => curve::STEP,
+    };
+
+    let mut curve = Curve::load();
+    curve.nudge(ambient, delta);
+    curve.save()?;
+
+    let brightness = (state.brightness.unwrap_or(1.0) + delta).clamp(0.0, 1.0);
+    override_brightness(brightness, Some(ambient), &mut state, args, config)?;
+    println!("Brightness {:.2}, remembered for ambient level {:.2}", brightness, ambient);
+
     Ok(())
 }
 
+/// Hands values chosen by hand to the running daemon so it holds them,
+/// applying them directly when no daemon is running
+async fn set(brightness: Option<f64>, temp: Option<f64>, args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let commands: Vec<_> = temp
+        .map(daemon::Command::SetTemperature)
+        .into_iter()
+        .chain(brightness.map(daemon::Command::SetBrightness))
+        .collect();
+
+    match send_to_daemon(&commands).await {
+        Ok(true) => {
+            println!("Sent to the running daemon");
+            return Ok(());
+        }
+        Ok(false) => {}
+        Err(e) => eprintln!("Couldn't reach the daemon, applying directly: {}", e),
+    }
+
+    if let Some(temp) = temp {
+        set_temperature(temp, args, config)?;
+    }
+    if let Some(brightness) = brightness {
+        set_brightness(brightness, args, config)?;
+    }
+    Ok(())
+}
+
+/// Hands `commands` to a running daemon over the control socket, or D-Bus when the socket
+/// isn't there, returning false when no daemon is reachable either way
+async fn send_to_daemon(commands: &[daemon::Command]) -> Result<bool, Box<dyn std::error::Error>> {
+    if ipc::send(commands).await? {
+        return Ok(true);
+    }
+    dbus::send(commands).await
+}
+
+/// Applies a brightness chosen by hand and holds it, logging it to learn from once there's an
+/// ambient reading to pair it with
+fn set_brightness(brightness: f64, args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = State::load();
+    let ambient = state.ambient_level;
+
+    override_brightness(brightness.clamp(0.0, 1.0), ambient, &mut state, args, config)?;
+    match ambient {
+        Some(ambient) => println!("Brightness {:.2}, remembered for ambient level {:.2}", brightness, ambient),
+        None => println!("Brightness {:.2}, not remembered without an ambient reading yet", brightness),
+    }
+
+    Ok(())
+}
+
+/// Applies a color temperature at the current brightness and holds it
+fn set_temperature(color_temp: f64, args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = State::load();
+    set_monitor_brightness(state.brightness.unwrap_or(args.max_brightness), color_temp, args, config)?;
+
+    state.color_temp = Some(color_temp);
+    hold_manual(&mut state, config);
+    state.save()
+}
+
+/// Keeps automatic updates from replacing values set by hand for `manual_hold_minutes`
+fn hold_manual(state: &mut State, config: &Config) {
+    let minutes = config.manual_hold_minutes.unwrap_or(DEFAULT_MANUAL_HOLD_MINUTES);
+    state.manual_until = Some(Utc::now().timestamp() + (minutes * 60.0) as i64);
+}
+
+fn override_brightness(
+    brightness: f64,
+    ambient: Option<f64>,
+    state: &mut State,
+    args: &Args,
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let color_temp = state.color_temp.unwrap_or(args.day_temp);
+    set_monitor_brightness(brightness, color_temp, args, config)?;
+
+    if let Some(ambient) = ambient {
+        let mut overrides = Overrides::load();
+        overrides.record(ambient, brightness);
+        overrides.save()?;
+    }
+
+    state.brightness = Some(brightness);
+    hold_manual(state, config);
+    state.save()
+}
+
+/// Suspends bedtime enforcement until night ends
+async fn override_tonight(args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = State::load();
+    let until = bedtime::override_tonight(config, args.transition_hours, &mut state);
+    state.save()?;
+    println!("Bedtime enforcement lifted until {}", until);
+
+    run(args, config).await
+}
+
+/// Applies the given values, waits, then restores automatic settings.
+/// Interrupting with Ctrl-C restores early.
+async fn preview(
+    temp: Option<f64>,
+    brightness: Option<f64>,
+    duration: Duration,
+    args: &Args,
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let temp = temp.unwrap_or_else(|| {
+        let now = Local::now();
+        schedule::at(now, &args.day_times(config, now)).blend(args.day_temp, args.night_temp)
+    });
+    let brightness = brightness.or(State::load().brightness).unwrap_or(1.0);
+
+    set_monitor_brightness(brightness, temp, args, config)?;
+    println!("Previewing {:.0}K at brightness {:.2} for {}s", temp, brightness, duration.as_secs());
+
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+
+    run(args, config).await
+}
+
+/// Turns darkroom mode on or off, restoring automatic settings when switched off
+async fn darkroom(action: Toggle, args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = State::load();
+    state.darkroom = match action {
+        Toggle::On => true,
+        Toggle::Off => false,
+        Toggle::Toggle => !state.darkroom,
+    };
+    state.save()?;
+
+    if state.darkroom {
+        apply_darkroom(args, config)
+    } else {
+        run(args, config).await
+    }
+}
+
+/// Carries out a shortcut action as an override, through the running daemon if there is one
+async fn hotkey(hotkey: Hotkey, args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let state = State::load();
+    let hotkeys_config = config.hotkeys.clone().unwrap_or_default();
+    let temps = (args.day_temp, args.night_temp);
+
+    match hotkey.command(&hotkeys_config, state.brightness, state.color_temp, temps) {
+        daemon::Command::SetBrightness(brightness) => set(Some(brightness), None, args, config).await,
+        daemon::Command::SetTemperature(temp) => set(None, Some(temp), args, config).await,
+        command => Err(format!("Unexpected shortcut command {:?}", command).into()),
+    }
+}
+
+/// Pauses or resumes automatic adjustments, updating right away on resume. A pause
+/// with a `duration` ends by itself.
+async fn pause(action: Toggle, duration: Option<Duration>, args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = State::load();
+    state.paused = match action {
+        Toggle::On => true,
+        Toggle::Off => false,
+        Toggle::Toggle => !state.paused,
+    };
+    state.paused_until = duration
+        .filter(|_| state.paused)
+        .map(|d| Utc::now().timestamp() + d.as_secs() as i64);
+    // Saved first so the daemon keeps the end of the pause
+    state.save()?;
+    let command = if state.paused { daemon::Command::Pause } else { daemon::Command::Resume };
+
+    match send_to_daemon(&[command]).await {
+        Ok(true) => return Ok(()),
+        Ok(false) => {}
+        Err(e) => eprintln!("Couldn't reach the daemon, applying directly: {}", e),
+    }
+
+    if state.paused {
+        Ok(())
+    } else {
+        run(args, config).await
+    }
+}
+
+/// Red-only output at very low brightness
+fn apply_darkroom(args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let brightness = config.darkroom_brightness.unwrap_or(DARKROOM_BRIGHTNESS);
+    set_monitor_gamma(brightness, DARKROOM_GAMMA, args, config)
+}
+
+/// Converts a redshift/gammastep config and writes it as the native config
+fn import_redshift_config(file: Option<&Path>, force: bool, args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let source = match file {
+        Some(f) => f.to_path_buf(),
+        None => import::default_redshift_paths()
+            .into_iter()
+            .find(|p| p.exists())
+            .ok_or("No redshift or gammastep config found, pass it with --file")?,
+    };
+
+    let target = args.config_path()?;
+
+    if target.exists() && !force {
+        return Err(format!("{} already exists, use --force to overwrite it", target.display()).into());
+    }
+
+    let (imported, warnings) = import::import_redshift(&source)?;
+    for warning in warnings {
+        eprintln!("Warning: {}", warning);
+    }
+
+    imported.save(&target)?;
+    println!("Imported {} into {}", source.display(), target.display());
+
+    Ok(())
+}
+
+/// Parses durations like "90", "10s", "5m" or "1h" (plain numbers are seconds)
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (value, unit) = match s.char_indices().find(|(_, c)| c.is_ascii_alphabetic()) {
+        Some((i, _)) => (&s[..i], &s[i..]),
+        None => (s, "s"),
+    };
+
+    let value: f64 = value.parse().map_err(|_| format!("Invalid duration '{}'", s))?;
+    let seconds = match unit {
+        "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        _ => return Err(format!("Invalid duration unit '{}', expected s, m or h", unit)),
+    };
+
+    if !(seconds >= 0.0 && seconds.is_finite()) {
+        return Err(format!("Invalid duration '{}'", s));
+    }
+
+    Ok(Duration::from_secs_f64(seconds))
+}
+
 async fn fetch_weather(
     lat: &str,
     lon: &str,
@@ -114,19 +1491,47 @@ async fn fetch_weather(
         lat, lon, api_key
     );
 
-    let resp = reqwest::get(&url).await?.json::<WeatherApiResponse>().await?;
-    Ok(resp)
+    let resp = async { api_client()?.get(&url).send().await?.json::<WeatherApiResponse>().await }.await;
+    metrics::record_api_call("weather", resp.is_ok());
+    // The error would name the URL, which carries the key
+    Ok(resp.map_err(reqwest::Error::without_url)?)
+}
+
+/// The configured location, or else the one looked up from the IP address. The lookup is
+/// remembered for a few hours, or until the daemon sees the network change.
+async fn locate(config: &Config, state: &mut State) -> Result<(f64, f64), Box<dyn std::error::Error>> {
+    if let Some(location) = config.location {
+        return Ok((location.lat, location.lon));
+    }
+    let now = Utc::now().timestamp();
+    if let Some(([lat, lon], at)) = state.ip_location.zip(state.ip_location_at) {
+        if now - at < IP_LOCATION_MAX_AGE_SECS {
+            return Ok((lat, lon));
+        }
+    }
+
+    let location = fetch_location().await?;
+    state.ip_location = Some([location.lat, location.lon]);
+    state.ip_location_at = Some(now);
+    Ok((location.lat, location.lon))
 }
 
 async fn fetch_location() -> Result<LocationApiResponse, Box<dyn std::error::Error>> {
     let url = "http://ip-api.com/json";
-    let resp = reqwest::get(url).await?.json::<LocationApiResponse>().await?;
-    Ok(resp)
+    let resp = async { api_client()?.get(url).send().await?.json::<LocationApiResponse>().await }.await;
+    metrics::record_api_call("location", resp.is_ok());
+    Ok(resp.map_err(reqwest::Error::without_url)?)
+}
+
+/// A client for the weather and location lookups, bounded so a stalled request can't hold up
+/// an update that's waiting on the fallback
+fn api_client() -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder().timeout(API_TIMEOUT).build()
 }
 
 /// Computes a simplistic “outside brightness” factor [0.0..1.0]
 /// based on sunrise/sunset times and cloud coverage.
-fn compute_brightness(weather: &WeatherApiResponse, min_brightness: f64) -> f64 {
+fn compute_brightness(weather: &WeatherApiResponse) -> f64 {
     let now_utc: DateTime<Utc> = Utc::now();
     let now_ts = now_utc.timestamp();
 
@@ -135,7 +1540,7 @@ fn compute_brightness(weather: &WeatherApiResponse, min_brightness: f64) -> f64
     let cloud_cover = weather.clouds.all;
 
     if now_ts < sunrise || now_ts > sunset {
-        return min_brightness;
+        return 0.0;
     }
 
     let day_length = (sunset - sunrise) as f64;
@@ -151,55 +1556,117 @@ fn compute_brightness(weather: &WeatherApiResponse, min_brightness: f64) -> f64
     };
 
     let cloud_factor = 1.0 - (cloud_cover / 100.0);
-    let outside_brightness = midday_bump * cloud_factor;
+    midday_bump * cloud_factor
+}
 
-    min_brightness + outside_brightness * (1.0 - min_brightness)
+/// Brightness for an ambient light level before any learned corrections:
+/// the calibrated anchors when set, otherwise `map_ambient`
+fn default_brightness(ambient: f64, min_brightness: f64, args: &Args, config: &Config) -> f64 {
+    if config.curve_anchors.is_empty() {
+        map_ambient(ambient, min_brightness, args.brightness_exponent)
+    } else {
+        calibrate::anchored(&config.curve_anchors, ambient)
+    }
+}
+
+/// Maps an ambient light level [0.0..1.0] onto the screen brightness range.
+/// Exponents above 1.0 dim more aggressively in dark rooms, below 1.0 less so.
+fn map_ambient(ambient: f64, min_brightness: f64, exponent: f64) -> f64 {
+    min_brightness + ambient.clamp(0.0, 1.0).powf(exponent) * (1.0 - min_brightness)
 }
 
 /// Sets brightness and color temperature for monitors using xrandr
-fn set_monitor_brightness(brightness: f64, args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+fn set_monitor_brightness(brightness: f64, color_temp: f64, args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    metrics::record_color_temp(color_temp);
+    set_monitor_gamma(brightness, temp_to_gamma(color_temp), args, config)
+}
+
+/// Sets brightness and raw RGB gamma for monitors using xrandr
+fn set_monitor_gamma(brightness: f64, gamma: (f64, f64, f64), args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
     let monitors = match &args.monitors {
         Some(m) => m.clone(),
-        None => detect_monitors()?
+        None if args.primary_only => vec![primary_monitor().map_err(|e| exit::fail(Failure::Display, e))?],
+        None => detect_monitors().map_err(|e| exit::fail(Failure::Display, e))?
     };
 
-    let now_utc: DateTime<Utc> = Utc::now();
-    let now_local = now_utc.with_timezone(&chrono::Local);
-    let hour = now_local.hour() as f64 + (now_local.minute() as f64 / 60.0);
-
-    let color_temp = if hour >= 18.0 || hour <= 6.0 {
-        args.night_temp
-    } else if hour >= (18.0 - args.transition_hours) && hour < 18.0 {
-        let progress = (18.0 - hour) / args.transition_hours;
-        args.day_temp * progress + args.night_temp * (1.0 - progress)
-    } else {
-        args.day_temp
-    };
+    let (r_temp, g_temp, b_temp) = gamma;
 
-    let (r_gamma, g_gamma, b_gamma) = temp_to_gamma(color_temp);
+    // Setting gamma on a powered-down screen wakes it on some drivers; the daemon applies
+    // again once it's back on
+    let powered_off = powered_off_connectors();
 
+    let mut outputs = Vec::new();
     for monitor in &monitors {
-        match Command::new("xrandr")
+        if powered_off.contains(&connector_key(monitor)) {
+            outputs.push(Output { name: monitor.clone(), error: None, powered_off: true });
+            continue;
+        }
+
+        let monitor_config = config.monitor(monitor);
+        let brightness = brightness.min(monitor_config.max_brightness.unwrap_or(args.max_brightness));
+
+        // Compose the static per-monitor correction with the requested gamma
+        let [r_mul, g_mul, b_mul] = monitor_config.gamma.unwrap_or([1.0, 1.0, 1.0]);
+        let (r_gamma, g_gamma, b_gamma) = (r_temp * r_mul, g_temp * g_mul, b_temp * b_mul);
+
+        // One output failing (e.g. unplugged since it was listed) doesn't stop the others
+        let error = match Command::new("xrandr")
             .args(&[
                 "--output", monitor,
                 "--brightness", &format!("{:.3}", brightness),
                 "--gamma", &format!("{:.3}:{:.3}:{:.3}", r_gamma, g_gamma, b_gamma)
             ])
-            .status()
+            .output()
         {
-            Ok(status) if !status.success() => {
-                eprintln!("Failed to set brightness/gamma for {}: {:?}", monitor, status);
+            Ok(output) if !output.status.success() => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                Some(format!("xrandr {}: {}", output.status, stderr.trim()))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(exit::fail(Failure::Display, problem::spawn_failed("xrandr", e)));
             }
-            Err(e) => {
-                eprintln!("Error setting brightness/gamma for {}: {}", monitor, e);
+            Err(e) => Some(e.to_string()),
+            Ok(_) => {
+                metrics::record_brightness(monitor, brightness);
+                None
             }
-            _ => {}
+        };
+        if let Some(error) = &error {
+            eprintln!("Failed to set brightness/gamma for {}: {}", monitor, error);
         }
+        outputs.push(Output { name: monitor.clone(), error, powered_off: false });
     }
 
+    let tried: Vec<&Output> = outputs.iter().filter(|output| !output.powered_off).collect();
+    let all_failed = !tried.is_empty() && tried.iter().all(|output| output.error.is_some());
+    *OUTPUTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = outputs;
+    if all_failed {
+        return Err(exit::fail(Failure::Display, "xrandr couldn't set any monitor"));
+    }
     Ok(())
 }
 
+/// How each output fared the last time settings were applied
+fn output_results() -> Vec<Output> {
+    OUTPUTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+/// Outputs settings were last applied to, without waiting for a lock: `None` when nothing was
+/// applied yet, or when the list is being written (perhaps by code that just panicked)
+fn last_monitors() -> Option<Vec<String>> {
+    let outputs = match OUTPUTS.try_lock() {
+        Ok(outputs) => outputs,
+        Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+        Err(TryLockError::WouldBlock) => return None,
+    };
+    (!outputs.is_empty()).then(|| outputs.iter().map(|output| output.name.clone()).collect())
+}
+
+/// Whether `step` works as a `max_step`: finite and above zero
+fn valid_step(step: f64) -> bool {
+    step.is_finite() && step > 0.0
+}
+
 /// Convert color temperature (in Kelvin) to RGB gamma values
 fn temp_to_gamma(temp: f64) -> (f64, f64, f64) {
     let temp = temp / 100.0;
@@ -231,69 +1698,123 @@ fn temp_to_gamma(temp: f64) -> (f64, f64, f64) {
     (red, green, blue)
 }
 
-/// Captures an image from webcam and computes average brightness
-fn detect_brightness_from_webcam(min_brightness: f64) -> Result<f64, Box<dyn std::error::Error>> {
-    let mut camera = Camera::new(
-        CameraIndex::Index(0),
-        RequestedFormat::new::<RgbFormat>(RequestedFormatType::Exact(
-            CameraFormat::new_from(640, 480, FrameFormat::MJPEG, 30)
-        ))
-    )?;
+/// Outputs from the last `xrandr --query`, with the connector states seen at the time
+static MONITORS: Mutex<Option<(String, Vec<OutputInfo>)>> = Mutex::new(None);
 
-    camera.open_stream()?;
+/// How each output fared the last time settings were applied
+static OUTPUTS: Mutex<Vec<Output>> = Mutex::new(Vec::new());
 
-    for _ in 0..5 {
-        let _ = camera.frame()?;
-        sleep(Duration::from_millis(100));
-    }
+/// Connected outputs. The list is kept until a connector is plugged or unplugged, or until
+/// `forget_monitors`, so the daemon doesn't run xrandr on every update.
+fn detect_monitors() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    Ok(detect_outputs()?.into_iter().map(|output| output.name).collect())
+}
 
-    let frame = camera.frame()?;
-    let img = frame.decode_image::<RgbFormat>()?;
+/// The output xrandr marks as primary
+fn primary_monitor() -> Result<String, Box<dyn std::error::Error>> {
+    detect_outputs()?
+        .into_iter()
+        .find(|output| output.primary)
+        .map(|output| output.name)
+        .ok_or_else(|| Problem::NoPrimary.into())
+}
 
-    camera.stop_stream()?;
+fn detect_outputs() -> Result<Vec<OutputInfo>, Box<dyn std::error::Error>> {
+    let connectors = connector_states();
+    if let Some((seen, outputs)) = MONITORS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).as_ref() {
+        if *seen == connectors {
+            return Ok(outputs.clone());
+        }
+    }
 
-    let mut total_brightness = 0.0;
-    let pixels = img.pixels();
-    let pixel_count = pixels.len() as f64;
+    // The cache isn't locked while xrandr runs
+    let outputs = list_monitors()?;
+    *MONITORS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some((connectors, outputs.clone()));
+    Ok(outputs)
+}
 
-    for pixel in pixels {
-        let r = pixel[0] as f64;
-        let g = pixel[1] as f64;
-        let b = pixel[2] as f64;
-        total_brightness += (0.2126 * r + 0.7152 * g + 0.0722 * b) / 255.0;
-    }
+/// Makes the next `detect_monitors` ask xrandr again
+fn forget_monitors() {
+    *MONITORS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+}
 
-    let avg_brightness = total_brightness / pixel_count;
-    let clamped_brightness = avg_brightness.clamp(0.0, 1.0);
-    let screen_brightness = min_brightness + (clamped_brightness * (1.0 - min_brightness));
+/// Connectors whose screen is powered down (DPMS standby, suspend or off), as `connector_key`s
+fn powered_off_connectors() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir("/sys/class/drm") else {
+        return Vec::new();
+    };
 
-    Ok(screen_brightness)
+    let mut off: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let read = |file: &str| std::fs::read_to_string(entry.path().join(file)).unwrap_or_default();
+            read("status").trim() == "connected" && !matches!(read("dpms").trim(), "On" | "")
+        })
+        .filter_map(|entry| {
+            // "card1-DP-1" is DP-1 on card1
+            let name = entry.file_name().to_string_lossy().into_owned();
+            name.split_once('-').map(|(_, connector)| connector_key(connector))
+        })
+        .collect();
+    off.sort();
+    off
 }
 
-/// Detect available monitors using xrandr
-fn detect_monitors() -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let output = Command::new("xrandr")
-        .arg("--listmonitors")
-        .output()?;
-
-    if !output.status.success() {
-        return Err("Failed to execute xrandr --listmonitors".into());
-    }
-
-    let output_str = String::from_utf8(output.stdout)?;
-    let monitors: Vec<String> = output_str
-        .lines()
-        .skip(1)  // Skip the first line (contains count)
-        .filter_map(|line| {
-            line.split_whitespace()
-                .last()
-                .map(String::from)
+/// Output name with the spelling differences between the kernel and X drivers removed, so
+/// "HDMI-A-1" matches "HDMI-1" and "HDMI1". Outputs that still don't match count as powered on.
+fn connector_key(name: &str) -> String {
+    name.replace("HDMI-A", "HDMI").replace('-', "")
+}
+
+/// Status of every DRM connector, e.g. "card1-DP-1=connected;", which changes on hotplug
+fn connector_states() -> String {
+    let Ok(entries) = std::fs::read_dir("/sys/class/drm") else {
+        return String::new();
+    };
+
+    let mut states: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let status = std::fs::read_to_string(entry.path().join("status")).ok()?;
+            Some(format!("{}={};", entry.file_name().to_string_lossy(), status.trim()))
         })
         .collect();
+    states.sort();
+    states.concat()
+}
+
+/// Outputs that are connected and switched on. Stale entries of unplugged screens, which xrandr
+/// can still list with a position, are left out.
+fn list_monitors() -> Result<Vec<OutputInfo>, Box<dyn std::error::Error>> {
+    let monitors: Vec<OutputInfo> = randr::query()?.into_iter().filter(|output| output.active()).collect();
 
     if monitors.is_empty() {
-        return Err("No monitors detected".into());
+        return Err(Problem::NoMonitors.into());
     }
 
     Ok(monitors)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("10s"), Ok(Duration::from_secs(10)));
+        assert_eq!(parse_duration(" 5m "), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("1.5h"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("0"), Ok(Duration::ZERO));
+    }
+
+    #[test]
+    fn invalid_durations() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("-5m").is_err());
+        assert!(parse_duration("2d").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("inf").is_err());
+        assert!(parse_duration("1e3").is_err());
+    }
+}
@@ -0,0 +1,11 @@
+use std::process::Command;
+
+/// Whether any MPRIS media player reports that it's playing, via playerctl
+pub fn playing() -> bool {
+    match Command::new("playerctl").args(["--all-players", "status"]).output() {
+        Ok(output) => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .any(|line| line.trim() == "Playing"),
+        Err(_) => false,
+    }
+}
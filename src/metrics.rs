@@ -0,0 +1,209 @@
+// Always recorded, but only served (in the Prometheus text format at `/metrics`)
+// when built with the `metrics` feature. With the `otel` feature everything is also
+// reported to the global OpenTelemetry meter, which does nothing unless `[telemetry]`
+// export is set up.
+#![cfg_attr(not(feature = "metrics"), allow(dead_code))]
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+#[cfg(feature = "otel")]
+use std::sync::OnceLock;
+use std::time::Duration;
+
+#[cfg(feature = "otel")]
+use opentelemetry::metrics::{Counter, Histogram, ObservableGauge};
+#[cfg(feature = "otel")]
+use opentelemetry::{global, KeyValue};
+
+/// Upper bounds of the cycle duration histogram buckets (seconds)
+const CYCLE_BUCKETS: [f64; 8] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+struct Metrics {
+    ambient_luma: Option<f64>,
+    ambient_level: Option<f64>,
+    color_temp: Option<f64>,
+    /// Applied brightness keyed by output
+    brightness: BTreeMap<String, f64>,
+    sensor_errors: BTreeMap<&'static str, u64>,
+    api_calls: BTreeMap<&'static str, u64>,
+    api_errors: BTreeMap<&'static str, u64>,
+    cycle_buckets: [u64; CYCLE_BUCKETS.len()],
+    cycle_count: u64,
+    cycle_seconds: f64,
+}
+
+static METRICS: Mutex<Metrics> = Mutex::new(Metrics {
+    ambient_luma: None,
+    ambient_level: None,
+    color_temp: None,
+    brightness: BTreeMap::new(),
+    sensor_errors: BTreeMap::new(),
+    api_calls: BTreeMap::new(),
+    api_errors: BTreeMap::new(),
+    cycle_buckets: [0; CYCLE_BUCKETS.len()],
+    cycle_count: 0,
+    cycle_seconds: 0.0,
+});
+
+/// OpenTelemetry instruments, created on first use so they bind to the installed meter provider
+#[cfg(feature = "otel")]
+struct Instruments {
+    cycle_duration: Histogram<f64>,
+    sensor_errors: Counter<u64>,
+    api_calls: Counter<u64>,
+    _gauges: Vec<ObservableGauge<f64>>,
+}
+
+#[cfg(feature = "otel")]
+static INSTRUMENTS: OnceLock<Instruments> = OnceLock::new();
+
+#[cfg(feature = "otel")]
+fn instruments() -> &'static Instruments {
+    INSTRUMENTS.get_or_init(|| {
+        let meter = global::meter("healthy-monitor");
+        let gauge = |name: &'static str, read: fn(&Metrics) -> Vec<(f64, Vec<KeyValue>)>| {
+            meter
+                .f64_observable_gauge(name)
+                .with_callback(move |observer| {
+                    if let Ok(metrics) = METRICS.lock() {
+                        for (value, attributes) in read(&metrics) {
+                            observer.observe(value, &attributes);
+                        }
+                    }
+                })
+                .init()
+        };
+
+        Instruments {
+            cycle_duration: meter.f64_histogram("healthy_monitor.cycle.duration").with_unit("s").init(),
+            sensor_errors: meter.u64_counter("healthy_monitor.sensor.errors").init(),
+            api_calls: meter.u64_counter("healthy_monitor.api.calls").init(),
+            _gauges: vec![
+                gauge("healthy_monitor.ambient.luma", |m| m.ambient_luma.map(|v| (v, Vec::new())).into_iter().collect()),
+                gauge("healthy_monitor.ambient.level", |m| m.ambient_level.map(|v| (v, Vec::new())).into_iter().collect()),
+                gauge("healthy_monitor.brightness", |m| {
+                    m.brightness.iter().map(|(output, b)| (*b, vec![KeyValue::new("output", output.clone())])).collect()
+                }),
+                gauge("healthy_monitor.color_temp", |m| {
+                    m.brightness
+                        .keys()
+                        .filter_map(|output| m.color_temp.map(|t| (t, vec![KeyValue::new("output", output.clone())])))
+                        .collect()
+                }),
+            ],
+        }
+    })
+}
+
+fn update(f: impl FnOnce(&mut Metrics)) {
+    if let Ok(mut metrics) = METRICS.lock() {
+        f(&mut metrics);
+    }
+}
+
+/// A webcam reading [0.0..1.0]
+pub fn record_luma(luma: f64) {
+    update(|m| m.ambient_luma = Some(luma));
+}
+
+/// The ambient level a brightness was based on, after any fallback or blending [0.0..1.0]
+pub fn record_ambient_level(level: f64) {
+    update(|m| m.ambient_level = Some(level));
+}
+
+pub fn record_color_temp(color_temp: f64) {
+    update(|m| m.color_temp = Some(color_temp));
+}
+
+pub fn record_brightness(output: &str, brightness: f64) {
+    update(|m| {
+        m.brightness.insert(output.to_string(), brightness);
+    });
+}
+
+/// A sensor failing to deliver a reading, e.g. "webcam"
+pub fn record_sensor_error(sensor: &'static str) {
+    update(|m| *m.sensor_errors.entry(sensor).or_default() += 1);
+    #[cfg(feature = "otel")]
+    instruments().sensor_errors.add(1, &[KeyValue::new("sensor", sensor)]);
+}
+
+/// A request to an external API, e.g. "weather"
+pub fn record_api_call(api: &'static str, ok: bool) {
+    update(|m| {
+        *m.api_calls.entry(api).or_default() += 1;
+        if !ok {
+            *m.api_errors.entry(api).or_default() += 1;
+        }
+    });
+    #[cfg(feature = "otel")]
+    instruments().api_calls.add(1, &[KeyValue::new("api", api), KeyValue::new("ok", ok)]);
+}
+
+/// How long one update cycle took
+pub fn record_cycle(duration: Duration) {
+    let seconds = duration.as_secs_f64();
+    update(|m| {
+        for (bucket, bound) in m.cycle_buckets.iter_mut().zip(CYCLE_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        m.cycle_count += 1;
+        m.cycle_seconds += seconds;
+    });
+    #[cfg(feature = "otel")]
+    instruments().cycle_duration.record(seconds, &[]);
+}
+
+/// All metrics in the Prometheus text exposition format
+pub fn render() -> String {
+    let Ok(m) = METRICS.lock() else {
+        return String::new();
+    };
+    let mut out = String::new();
+
+    let mut gauge = |name: &str, help: &str, value: Option<f64>| {
+        if let Some(value) = value {
+            let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}");
+        }
+    };
+    gauge("healthy_monitor_ambient_luma", "Last webcam reading (0-1)", m.ambient_luma);
+    gauge("healthy_monitor_ambient_level", "Ambient level the brightness was based on (0-1)", m.ambient_level);
+
+    let _ = writeln!(out, "# HELP healthy_monitor_brightness Applied brightness per output (0-1)");
+    let _ = writeln!(out, "# TYPE healthy_monitor_brightness gauge");
+    for (output, brightness) in &m.brightness {
+        let _ = writeln!(out, "healthy_monitor_brightness{{output=\"{}\"}} {}", output, brightness);
+    }
+
+    if let Some(color_temp) = m.color_temp {
+        let _ = writeln!(out, "# HELP healthy_monitor_color_temp_kelvin Applied color temperature per output");
+        let _ = writeln!(out, "# TYPE healthy_monitor_color_temp_kelvin gauge");
+        for output in m.brightness.keys() {
+            let _ = writeln!(out, "healthy_monitor_color_temp_kelvin{{output=\"{}\"}} {}", output, color_temp);
+        }
+    }
+
+    let mut counter = |name: &str, help: &str, label: &str, values: &BTreeMap<&'static str, u64>| {
+        let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} counter");
+        for (key, value) in values {
+            let _ = writeln!(out, "{name}{{{label}=\"{key}\"}} {value}");
+        }
+    };
+    counter("healthy_monitor_sensor_errors_total", "Failed sensor readings", "sensor", &m.sensor_errors);
+    counter("healthy_monitor_api_calls_total", "Requests to external APIs", "api", &m.api_calls);
+    counter("healthy_monitor_api_errors_total", "Failed requests to external APIs", "api", &m.api_errors);
+
+    let name = "healthy_monitor_cycle_duration_seconds";
+    let _ = writeln!(out, "# HELP {name} Duration of update cycles\n# TYPE {name} histogram");
+    for (bound, count) in CYCLE_BUCKETS.iter().zip(m.cycle_buckets) {
+        let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {count}");
+    }
+    let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {}", m.cycle_count);
+    let _ = writeln!(out, "{name}_sum {}", m.cycle_seconds);
+    let _ = writeln!(out, "{name}_count {}", m.cycle_count);
+
+    out
+}
@@ -0,0 +1,204 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::watch;
+
+use crate::daemon::Command;
+use crate::status::Status;
+
+/// `[midi]` config section: knobs or faders of a MIDI controller (needs the `midi` feature)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MidiConfig {
+    /// Part of the input port name, e.g. "nanoKONTROL"; the first port when empty
+    pub port: String,
+    /// MIDI channel (1 to 16) to listen on; any channel when unset
+    #[serde(skip_serializing_if = "Option::is_none", deserialize_with = "channel")]
+    pub channel: Option<u8>,
+    /// Control change number driving the brightness
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness_cc: Option<u8>,
+    /// Control change number driving the color temperature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temp_cc: Option<u8>,
+    /// Endless encoders sending steps (1 to 63 up, 65 to 127 down) instead of positions
+    pub relative: bool,
+    /// Brightness change per encoder step
+    pub brightness_step: f64,
+    /// Temperature change per encoder step (Kelvin)
+    pub temp_step: f64,
+    /// Temperature range covered by an absolute control (Kelvin)
+    pub min_temp: f64,
+    pub max_temp: f64,
+}
+
+impl Default for MidiConfig {
+    fn default() -> Self {
+        MidiConfig {
+            port: String::new(),
+            channel: None,
+            brightness_cc: Some(1),
+            temp_cc: Some(2),
+            relative: false,
+            brightness_step: 0.01,
+            temp_step: 50.0,
+            min_temp: 2500.0,
+            max_temp: 6500.0,
+        }
+    }
+}
+
+/// Rejects channels outside 1 to 16 when the config is loaded, rather than never matching
+fn channel<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u8>, D::Error> {
+    match Option::<u8>::deserialize(deserializer)? {
+        Some(channel) if !(1..=16).contains(&channel) => {
+            Err(serde::de::Error::custom(format!("MIDI channel {} is out of range, it must be 1 to 16", channel)))
+        }
+        channel => Ok(channel),
+    }
+}
+
+/// Listens to the controller and hands overrides to the daemon loop, so they are held for
+/// `manual_hold_minutes` like any other. Returns once the port is open.
+#[cfg(feature = "midi")]
+pub async fn serve(
+    config: &MidiConfig,
+    commands: UnboundedSender<Command>,
+    status: watch::Receiver<Status>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    controller::serve(config, commands, status).await
+}
+
+#[cfg(not(feature = "midi"))]
+pub async fn serve(
+    _config: &MidiConfig,
+    _commands: UnboundedSender<Command>,
+    _status: watch::Receiver<Status>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Err("healthy-monitor was built without the `midi` feature".into())
+}
+
+#[cfg(feature = "midi")]
+mod controller {
+    use std::time::Duration;
+
+    use midir::{Ignore, MidiInput};
+    use tokio::sync::mpsc::{self, UnboundedSender};
+    use tokio::sync::{oneshot, watch};
+    use tokio::time::sleep;
+
+    use super::MidiConfig;
+    use crate::daemon::Command;
+    use crate::status::Status;
+
+    /// Turning a knob sends a stream of values; they are gathered this long and applied at once
+    const SETTLE: Duration = Duration::from_millis(150);
+    /// Status byte of a control change, without the channel
+    const CONTROL_CHANGE: u8 = 0xb0;
+
+    pub async fn serve(
+        config: &MidiConfig,
+        commands: UnboundedSender<Command>,
+        status: watch::Receiver<Status>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (sender, mut changes) = mpsc::unbounded_channel();
+        let (ready, opened) = oneshot::channel();
+        let wanted = config.port.clone();
+        let channel = config.channel;
+
+        // The connection lives on its own thread, which the MIDI backend calls back on
+        std::thread::spawn(move || {
+            let open = || -> Result<_, String> {
+                let mut input = MidiInput::new("healthy-monitor").map_err(|e| e.to_string())?;
+                input.ignore(Ignore::All);
+                let port = input
+                    .ports()
+                    .into_iter()
+                    .find(|port| input.port_name(port).map_or(false, |name| name.contains(&wanted)))
+                    .ok_or_else(|| format!("No MIDI input port matching \"{}\"", wanted))?;
+
+                input
+                    .connect(
+                        &port,
+                        "healthy-monitor",
+                        move |_, message, _| {
+                            if let [status, controller, value] = *message {
+                                let on_channel = channel.map_or(true, |c| status & 0x0f == c.saturating_sub(1));
+                                if status & 0xf0 == CONTROL_CHANGE && on_channel {
+                                    let _ = sender.send((controller, value));
+                                }
+                            }
+                        },
+                        (),
+                    )
+                    .map_err(|e| e.to_string())
+            };
+
+            match open() {
+                Ok(_connection) => {
+                    let _ = ready.send(Ok(()));
+                    loop {
+                        std::thread::park();
+                    }
+                }
+                Err(e) => {
+                    let _ = ready.send(Err(e));
+                }
+            }
+        });
+        opened.await.map_err(|_| "The MIDI thread stopped")??;
+
+        let config = config.clone();
+        tokio::spawn(async move {
+            while let Some(first) = changes.recv().await {
+                sleep(SETTLE).await;
+                let mut burst = vec![first];
+                while let Ok(change) = changes.try_recv() {
+                    burst.push(change);
+                }
+
+                let current = status.borrow().clone();
+                for command in overrides(&config, &burst, current.brightness, current.color_temp) {
+                    if commands.send(command).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// The overrides asked for by a burst of `(controller, value)` changes, starting from the
+    /// applied settings. Positions take the last value, steps add up.
+    fn overrides(config: &MidiConfig, changes: &[(u8, u8)], brightness: Option<f64>, color_temp: Option<f64>) -> Vec<Command> {
+        let values = |cc: Option<u8>| -> Vec<u8> {
+            changes.iter().filter(|(c, _)| Some(*c) == cc).map(|(_, v)| *v).collect()
+        };
+        let steps = |values: &[u8]| -> f64 {
+            values.iter().map(|&v| if v < 64 { v as f64 } else { v as f64 - 128.0 }).sum()
+        };
+
+        let mut commands = Vec::new();
+        let brightness_values = values(config.brightness_cc);
+        if let Some(&last) = brightness_values.last() {
+            let brightness = if config.relative {
+                brightness.unwrap_or(1.0) + steps(&brightness_values) * config.brightness_step
+            } else {
+                last as f64 / 127.0
+            };
+            commands.push(Command::SetBrightness(brightness.clamp(0.0, 1.0)));
+        }
+
+        let temp_values = values(config.temp_cc);
+        if let Some(&last) = temp_values.last() {
+            let color_temp = if config.relative {
+                color_temp.unwrap_or(config.max_temp) + steps(&temp_values) * config.temp_step
+            } else {
+                config.min_temp + (config.max_temp - config.min_temp) * last as f64 / 127.0
+            };
+            commands.push(Command::SetTemperature(color_temp.round().clamp(1000.0, 10000.0)));
+        }
+
+        commands
+    }
+}
@@ -0,0 +1,161 @@
+use std::time::Duration;
+
+use rumqttc::{AsyncClient, Event as MqttEvent, LastWill, MqttOptions, Packet, QoS};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::time::sleep;
+
+use crate::daemon::Command;
+use crate::events::Event;
+use crate::homeassistant;
+use crate::state::State;
+
+/// Wait before reconnecting after the broker connection drops
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// `[mqtt]` config section: state and sensor readings published to a broker in daemon mode
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    pub client_id: String,
+    /// Prepended to every topic, e.g. `healthy-monitor/brightness`
+    pub topic_prefix: String,
+    /// Announce the monitors and ambient sensor to Home Assistant and accept its commands
+    pub home_assistant: bool,
+    /// Home Assistant's discovery prefix
+    pub discovery_prefix: String,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        MqttConfig {
+            host: "localhost".into(),
+            port: 1883,
+            username: None,
+            password: None,
+            client_id: "healthy-monitor".into(),
+            topic_prefix: "healthy-monitor".into(),
+            home_assistant: false,
+            discovery_prefix: "homeassistant".into(),
+        }
+    }
+}
+
+impl MqttConfig {
+    pub fn topic(&self, name: &str) -> String {
+        format!("{}/{}", self.topic_prefix, name)
+    }
+}
+
+/// Connects to the broker in the background and publishes every event. With Home Assistant
+/// enabled, light commands from it are handed to the daemon loop as `commands`.
+pub fn serve(config: &MqttConfig, mut events: broadcast::Receiver<Event>, commands: UnboundedSender<Command>) {
+    let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    if let Some(username) = &config.username {
+        options.set_credentials(username, config.password.clone().unwrap_or_default());
+    }
+    if config.home_assistant {
+        let availability = homeassistant::availability_topic(config);
+        options.set_last_will(LastWill::new(availability, "offline", QoS::AtLeastOnce, true));
+    }
+
+    let (client, mut eventloop) = AsyncClient::new(options, 16);
+
+    // The event loop drives the connection and reconnects on the next poll after an error.
+    // It can't wait on its own queue, so it only ever uses the non-blocking client calls.
+    let connection_client = client.clone();
+    let connection_config = config.clone();
+    tokio::spawn(async move {
+        loop {
+            match eventloop.poll().await {
+                Ok(MqttEvent::Incoming(Packet::ConnAck(_))) if connection_config.home_assistant => {
+                    announce(&connection_client, &connection_config);
+                }
+                Ok(MqttEvent::Incoming(Packet::Publish(publish)))
+                    if publish.topic == homeassistant::command_topic(&connection_config) =>
+                {
+                    match homeassistant::commands(&publish.payload) {
+                        Ok(requested) => {
+                            for command in requested.into_iter().filter(|c| c.validate().is_ok()) {
+                                let _ = commands.send(command);
+                            }
+                        }
+                        Err(e) => eprintln!("Ignoring invalid Home Assistant command: {}", e),
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("MQTT connection error: {}", e);
+                    sleep(RECONNECT_DELAY).await;
+                }
+            }
+        }
+    });
+
+    let config = config.clone();
+    tokio::spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            };
+            if let Err(e) = publish(&client, &config, &event).await {
+                eprintln!("Failed to publish to MQTT: {}", e);
+            }
+        }
+    });
+}
+
+/// Publishes discovery messages, availability and the current light state, and
+/// subscribes to light commands, after every (re)connect
+fn announce(client: &AsyncClient, config: &MqttConfig) {
+    let state = State::load();
+    let mut messages = homeassistant::discovery(config);
+    messages.push((homeassistant::availability_topic(config), "online".into()));
+    if let (Some(brightness), Some(color_temp)) = (state.brightness, state.color_temp) {
+        messages.push((config.topic("light/state"), homeassistant::light_state(brightness, color_temp)));
+    }
+
+    for (topic, payload) in messages {
+        if let Err(e) = client.try_publish(topic, QoS::AtLeastOnce, true, payload) {
+            eprintln!("Failed to publish to MQTT: {}", e);
+        }
+    }
+    if let Err(e) = client.try_subscribe(homeassistant::command_topic(config), QoS::AtLeastOnce) {
+        eprintln!("Failed to subscribe to Home Assistant commands: {}", e);
+    }
+}
+
+/// Readings and settings go to retained topics of their own, and every event
+/// also goes to `events` as JSON
+async fn publish(client: &AsyncClient, config: &MqttConfig, event: &Event) -> Result<(), Box<dyn std::error::Error>> {
+    let retained = |name: &str, value: String| (config.topic(name), value);
+    let topics = match event {
+        Event::Reading { level, .. } => level.map(|level| retained("ambient", format!("{:.3}", level))).into_iter().collect(),
+        Event::Applied { brightness, color_temp, .. } => vec![
+            retained("brightness", format!("{:.3}", brightness)),
+            retained("color_temp", format!("{:.0}", color_temp)),
+            retained("light/state", homeassistant::light_state(*brightness, *color_temp)),
+        ],
+        Event::Transition { to, .. } => vec![retained("period", to.name().to_string())],
+        Event::Reminder { .. } => Vec::new(),
+    };
+
+    for (topic, payload) in topics {
+        client.publish(topic, QoS::AtLeastOnce, true, payload).await?;
+    }
+    client
+        .publish(config.topic("events"), QoS::AtLeastOnce, false, serde_json::to_string(event)?)
+        .await?;
+
+    Ok(())
+}
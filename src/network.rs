@@ -0,0 +1,93 @@
+use futures_util::StreamExt;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::watch;
+use zbus::zvariant::OwnedObjectPath;
+use zbus::{proxy, Connection};
+
+use crate::daemon::Command;
+use crate::state::State;
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager"
+)]
+trait NetworkManager {
+    /// 1 none, 2 portal, 3 limited, 4 full
+    #[zbus(property)]
+    fn connectivity(&self) -> zbus::Result<u32>;
+
+    /// The connection holding the default route, "/" when there is none
+    #[zbus(property)]
+    fn primary_connection(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+/// Connectivity and primary connection; a change in either may mean a new location
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Network {
+    pub connectivity: u32,
+    pub primary: String,
+}
+
+async fn current(manager: &NetworkManagerProxy<'_>) -> zbus::Result<Network> {
+    Ok(Network {
+        connectivity: manager.connectivity().await?,
+        primary: manager.primary_connection().await?.to_string(),
+    })
+}
+
+/// Follows NetworkManager in the background; stays at the default without it
+pub async fn watch() -> watch::Receiver<Network> {
+    let (sender, receiver) = watch::channel(Network::default());
+
+    tokio::spawn(async move {
+        let result: zbus::Result<()> = async {
+            let connection = Connection::system().await?;
+            let manager = NetworkManagerProxy::new(&connection).await?;
+            let mut connectivity = manager.receive_connectivity_changed().await;
+            let mut primary = manager.receive_primary_connection_changed().await;
+
+            sender.send_replace(current(&manager).await?);
+            loop {
+                tokio::select! {
+                    Some(_) = connectivity.next() => {}
+                    Some(_) = primary.next() => {}
+                    else => return Ok(()),
+                }
+                sender.send_replace(current(&manager).await?);
+            }
+        }
+        .await;
+
+        if let Err(e) = result {
+            eprintln!("Network state unavailable: {}", e);
+        }
+    });
+
+    receiver
+}
+
+/// Asks the daemon loop to look the location up again whenever the network changes. The
+/// first state after startup only establishes where the daemon is.
+pub fn relocate_on_change(mut network: watch::Receiver<Network>, commands: UnboundedSender<Command>) {
+    tokio::spawn(async move {
+        let mut last = network.borrow_and_update().clone();
+        while network.changed().await.is_ok() {
+            let current = network.borrow_and_update().clone();
+            if last != Network::default() && current != last && commands.send(Command::Relocate).is_err() {
+                return;
+            }
+            last = current;
+        }
+    });
+}
+
+/// Drops the remembered IP-based location so the next update looks it up again
+pub fn forget_location() {
+    let mut state = State::load();
+    if state.ip_location.take().is_some() {
+        if let Err(e) = state.save() {
+            eprintln!("Failed to save state: {}", e);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::schedule::{self, TimeOfDay};
+
+/// `[quiet]` config section: when health reminders are held back
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct QuietConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<TimeOfDay>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<TimeOfDay>,
+    /// Follow the desktop's Do-Not-Disturb state
+    pub dnd: bool,
+}
+
+impl Default for QuietConfig {
+    fn default() -> Self {
+        QuietConfig { start: None, end: None, dnd: true }
+    }
+}
+
+/// Notification urgency levels understood by notify-send
+#[derive(Debug, Clone, Copy)]
+pub enum Urgency {
+    Low,
+    Normal,
+    Critical,
+}
+
+impl Urgency {
+    fn name(self) -> &'static str {
+        match self {
+            Urgency::Low => "low",
+            Urgency::Normal => "normal",
+            Urgency::Critical => "critical",
+        }
+    }
+}
+
+/// Shows a desktop notification using notify-send
+pub fn send(summary: &str, body: &str, urgency: Urgency) {
+    notify_send(summary, body, urgency, &[]);
+}
+
+/// Shows a desktop notification with a freedesktop category (e.g. "device.error")
+pub fn send_with_category(summary: &str, body: &str, urgency: Urgency, category: &str) {
+    notify_send(summary, body, urgency, &["--category", category]);
+}
+
+/// Shows a desktop notification replacing the previous one with the same tag,
+/// on notification daemons that support it (dunst, notify-osd and others)
+pub fn send_tagged(summary: &str, body: &str, urgency: Urgency, tag: &str) {
+    let dunst_tag = format!("string:x-dunst-stack-tag:{}", tag);
+    let synchronous = format!("string:x-canonical-private-synchronous:{}", tag);
+    notify_send(summary, body, urgency, &["--hint", &dunst_tag, "--hint", &synchronous]);
+}
+
+fn notify_send(summary: &str, body: &str, urgency: Urgency, extra: &[&str]) {
+    let result = Command::new("notify-send")
+        .args(["--app-name", "healthy-monitor", "--urgency", urgency.name()])
+        .args(extra)
+        .args([summary, body])
+        .status();
+
+    match result {
+        Ok(status) if !status.success() => eprintln!("notify-send failed: {}", status),
+        Err(e) => eprintln!("Failed to run notify-send: {}", e),
+        _ => {}
+    }
+}
+
+/// Whether reminders should be held back: inside the quiet hours, or while
+/// the desktop is in Do-Not-Disturb mode (e.g. during a presentation)
+pub fn quiet(config: &QuietConfig) -> bool {
+    let in_quiet_hours = match (config.start, config.end) {
+        (Some(start), Some(end)) => TimeOfDay::window_contains(start, end, schedule::hour_of(Local::now())),
+        _ => false,
+    };
+
+    in_quiet_hours || (config.dnd && do_not_disturb())
+}
+
+/// How long a Do-Not-Disturb answer is reused; the daemon asks after every poll, and each
+/// check runs up to five programs
+const DND_MAX_AGE: Duration = Duration::from_secs(30);
+
+/// The last Do-Not-Disturb answer and when it was checked
+static DND: Mutex<Option<(Instant, bool)>> = Mutex::new(None);
+
+/// Whether Do-Not-Disturb is on, checked at most every `DND_MAX_AGE`
+fn do_not_disturb() -> bool {
+    let mut cached = DND.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match *cached {
+        Some((at, dnd)) if at.elapsed() < DND_MAX_AGE => dnd,
+        _ => {
+            let dnd = query_do_not_disturb();
+            *cached = Some((Instant::now(), dnd));
+            dnd
+        }
+    }
+}
+
+/// Asks the running notification daemon whether Do-Not-Disturb is on
+fn query_do_not_disturb() -> bool {
+    let output = |program: &str, args: &[&str]| {
+        Command::new(program)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+    };
+
+    // dunst, swaync, mako, KDE Plasma and GNOME respectively
+    output("dunstctl", &["is-paused"]).map_or(false, |o| o == "true")
+        || output("swaync-client", &["--get-dnd"]).map_or(false, |o| o == "true")
+        || output("makoctl", &["mode"]).map_or(false, |o| o.lines().any(|mode| mode == "do-not-disturb"))
+        || output(
+            "qdbus",
+            &["org.freedesktop.Notifications", "/org/freedesktop/Notifications", "org.freedesktop.Notifications.Inhibited"],
+        )
+        .map_or(false, |o| o == "true")
+        || output("gsettings", &["get", "org.gnome.desktop.notifications", "show-banners"]).map_or(false, |o| o == "false")
+}
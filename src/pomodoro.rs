@@ -0,0 +1,95 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::time::{sleep, Instant};
+
+use crate::config::Config;
+use crate::notify::{self, Urgency};
+use crate::Args;
+
+/// How often automatic settings are refreshed during a focus period
+const FOCUS_UPDATE: Duration = Duration::from_secs(300);
+
+/// `[pomodoro]` config section: how the screen looks during pomodoro breaks
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PomodoroConfig {
+    pub break_brightness: f64,
+    /// Color temperature during breaks (Kelvin)
+    pub break_temp: f64,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        PomodoroConfig { break_brightness: 0.3, break_temp: 3000.0 }
+    }
+}
+
+/// Focus and break lengths, written as `FOCUS/BREAK` minutes (e.g. "25/5")
+#[derive(Debug, Clone, Copy)]
+pub struct Cycle {
+    pub focus: Duration,
+    pub rest: Duration,
+}
+
+impl FromStr for Cycle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid cycle '{}', expected FOCUS/BREAK minutes like 25/5", s);
+        let (focus, rest) = s.trim().split_once('/').ok_or_else(invalid)?;
+        let minutes = |v: &str| -> Result<Duration, String> {
+            match v.trim().parse::<f64>() {
+                Ok(m) if m > 0.0 && m.is_finite() => Ok(Duration::from_secs_f64(m * 60.0)),
+                _ => Err(invalid()),
+            }
+        };
+
+        Ok(Cycle { focus: minutes(focus)?, rest: minutes(rest)? })
+    }
+}
+
+/// Alternates focus periods under automatic control with warm, dim breaks
+/// until interrupted with Ctrl-C, then restores automatic settings
+pub async fn run(cycle: Cycle, args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let pomodoro = config.pomodoro.clone().unwrap_or_default();
+
+    tokio::select! {
+        result = cycles(cycle, &pomodoro, args, config) => result?,
+        _ = tokio::signal::ctrl_c() => {}
+    }
+
+    crate::run(args, config).await
+}
+
+async fn cycles(cycle: Cycle, pomodoro: &PomodoroConfig, args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    for round in 1.. {
+        notify::send(
+            &format!("Focus #{}", round),
+            &format!("{} minutes of focus", cycle.focus.as_secs() / 60),
+            Urgency::Low,
+        );
+        println!("Focus #{} for {} minutes", round, cycle.focus.as_secs() / 60);
+
+        let deadline = Instant::now() + cycle.focus;
+        while Instant::now() < deadline {
+            if let Err(e) = crate::run(args, config).await {
+                eprintln!("Update failed: {}", e);
+            }
+            sleep(FOCUS_UPDATE.min(deadline - Instant::now())).await;
+        }
+
+        notify::send(
+            "Break time",
+            &format!("Look away from the screen for {} minutes", cycle.rest.as_secs() / 60),
+            Urgency::Normal,
+        );
+        println!("Break for {} minutes", cycle.rest.as_secs() / 60);
+
+        crate::set_monitor_brightness(pomodoro.break_brightness, pomodoro.break_temp, args, config)?;
+        sleep(cycle.rest).await;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::os::fd::OwnedFd;
+use std::path::Path;
+
+use futures_util::StreamExt;
+use zbus::zvariant::{self, ObjectPath, OwnedObjectPath, OwnedValue, Value};
+use zbus::{proxy, Connection};
+
+#[proxy(interface = "org.freedesktop.portal.Request", default_service = "org.freedesktop.portal.Desktop")]
+trait Request {
+    #[zbus(signal)]
+    fn response(&self, response: u32, results: HashMap<String, OwnedValue>) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.portal.Camera",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+trait Camera {
+    fn access_camera(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<OwnedObjectPath>;
+
+    fn open_pipe_wire_remote(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<zvariant::OwnedFd>;
+
+    #[zbus(property)]
+    fn is_camera_present(&self) -> zbus::Result<bool>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.portal.ScreenCast",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+trait ScreenCast {
+    fn create_session(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<OwnedObjectPath>;
+
+    fn select_sources(
+        &self,
+        session_handle: &ObjectPath<'_>,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    fn start(
+        &self,
+        session_handle: &ObjectPath<'_>,
+        parent_window: &str,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    fn open_pipe_wire_remote(
+        &self,
+        session_handle: &ObjectPath<'_>,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<zvariant::OwnedFd>;
+}
+
+/// ScreenCast source type of whole monitors
+const MONITOR: u32 = 1;
+/// Keep the permission until it is revoked, not just for this session
+const PERSIST_UNTIL_REVOKED: u32 = 2;
+
+/// A session bus connection to xdg-desktop-portal
+pub struct Portal {
+    pub connection: Connection,
+    /// Unique name in the form used in request and session paths
+    sender: String,
+}
+
+impl Portal {
+    pub async fn connect() -> Result<Self, Box<dyn std::error::Error>> {
+        let connection = Connection::session().await?;
+        let sender = connection
+            .unique_name()
+            .ok_or("Not connected to the session bus")?
+            .as_str()
+            .trim_start_matches(':')
+            .replace('.', "_");
+        Ok(Portal { connection, sender })
+    }
+
+    /// Starts listening for the response to the request made with the `handle_token` `token`.
+    /// The path is derived from the token, so this can happen before the call that answers.
+    pub async fn request(&self, token: &str) -> zbus::Result<ResponseStream<'static>> {
+        let path = format!("/org/freedesktop/portal/desktop/request/{}/{}", self.sender, token);
+        RequestProxy::builder(&self.connection).path(path)?.build().await?.receive_response().await
+    }
+
+    /// Path of the session created with the `session_handle_token` `token`
+    pub fn session(&self, token: &str) -> zbus::Result<ObjectPath<'static>> {
+        Ok(ObjectPath::try_from(format!("/org/freedesktop/portal/desktop/session/{}/{}", self.sender, token))?)
+    }
+}
+
+/// Waits for the outcome of a request, returning its results. `what` names the request in errors.
+pub async fn await_response(
+    mut responses: ResponseStream<'static>,
+    what: &str,
+) -> Result<HashMap<String, OwnedValue>, Box<dyn std::error::Error>> {
+    let response = responses.next().await.ok_or("The portal closed the request")?;
+    let args = response.args()?;
+    match *args.response() {
+        0 => Ok(args.results().clone()),
+        1 => Err(format!("{} was declined", what).into()),
+        _ => Err(format!("{} failed", what).into()),
+    }
+}
+
+/// Whether running inside a Flatpak or Snap sandbox, where devices go through the portal
+pub fn confined() -> bool {
+    Path::new("/.flatpak-info").exists() || std::env::var_os("SNAP").is_some()
+}
+
+/// Asks for camera access and opens a PipeWire connection that only sees the cameras
+pub async fn open_camera() -> Result<OwnedFd, Box<dyn std::error::Error>> {
+    let portal = Portal::connect().await?;
+    let camera = CameraProxy::new(&portal.connection).await?;
+    if !camera.is_camera_present().await? {
+        return Err("No camera available through the portal".into());
+    }
+
+    let token = "healthy_monitor_camera";
+    let access = portal.request(token).await?;
+    camera.access_camera(HashMap::from([("handle_token", Value::from(token))])).await?;
+    await_response(access, "Camera access").await?;
+
+    Ok(camera.open_pipe_wire_remote(HashMap::new()).await?.into())
+}
+
+/// A running screen cast of one monitor. The cast ends when this is dropped.
+pub struct ScreenCapture {
+    _portal: Portal,
+    pub fd: OwnedFd,
+    pub node: u32,
+    /// Token that restores this permission next time without asking
+    pub restore_token: Option<String>,
+}
+
+/// Starts casting a monitor, reusing the permission behind `restore_token` when given.
+/// Without a valid token the desktop asks which monitor to share.
+pub async fn open_screen(restore_token: Option<&str>) -> Result<ScreenCapture, Box<dyn std::error::Error>> {
+    let portal = Portal::connect().await?;
+    let screencast = ScreenCastProxy::new(&portal.connection).await?;
+    let session = portal.session("healthy_monitor_screen")?;
+
+    let create = portal.request("healthy_monitor_screen_create").await?;
+    screencast
+        .create_session(HashMap::from([
+            ("handle_token", Value::from("healthy_monitor_screen_create")),
+            ("session_handle_token", Value::from("healthy_monitor_screen")),
+        ]))
+        .await?;
+    await_response(create, "Creating a screen cast session").await?;
+
+    let mut options = HashMap::from([
+        ("handle_token", Value::from("healthy_monitor_screen_select")),
+        ("types", Value::from(MONITOR)),
+        ("persist_mode", Value::from(PERSIST_UNTIL_REVOKED)),
+    ]);
+    if let Some(token) = restore_token {
+        options.insert("restore_token", Value::from(token));
+    }
+    let select = portal.request("healthy_monitor_screen_select").await?;
+    screencast.select_sources(&session, options).await?;
+    await_response(select, "Choosing the screen to capture").await?;
+
+    let start = portal.request("healthy_monitor_screen_start").await?;
+    screencast
+        .start(&session, "", HashMap::from([("handle_token", Value::from("healthy_monitor_screen_start"))]))
+        .await?;
+    let mut results = await_response(start, "Screen capture").await?;
+
+    let streams = results.remove("streams").ok_or("The portal started no screen cast stream")?;
+    let streams = Vec::<(u32, HashMap<String, OwnedValue>)>::try_from(streams)?;
+    let node = streams.first().ok_or("The portal started no screen cast stream")?.0;
+    let restore_token = results.remove("restore_token").map(String::try_from).transpose()?;
+
+    let fd = screencast.open_pipe_wire_remote(&session, HashMap::new()).await?.into();
+    Ok(ScreenCapture { _portal: portal, fd, node, restore_token })
+}
@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+
+use chrono::Utc;
+use rustface::ImageData;
+use serde::{Deserialize, Serialize};
+
+use crate::notify::{self, Urgency};
+use crate::state::State;
+use crate::webcam::GrayFrame;
+
+/// `[posture]` config section enabling viewing-distance warnings
+///
+/// Faces are detected locally in the frame already captured for the brightness
+/// reading; no image is stored or sent anywhere.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PostureConfig {
+    /// SeetaFace frontal face model (seeta_fd_frontal_v1.0.bin)
+    pub model: PathBuf,
+    /// Face width as a fraction of the frame width above which you're too close
+    #[serde(default = "default_max_face_width")]
+    pub max_face_width: f64,
+    /// Minutes spent too close before warning
+    #[serde(default = "default_minutes")]
+    pub minutes: f64,
+}
+
+fn default_max_face_width() -> f64 {
+    0.35
+}
+
+fn default_minutes() -> f64 {
+    5.0
+}
+
+/// Width of the largest face in the frame as a fraction of the frame width
+pub fn face_width(config: &PostureConfig, frame: &GrayFrame) -> Result<Option<f64>, Box<dyn std::error::Error>> {
+    let model = config.model.to_str().ok_or("Face model path is not valid UTF-8")?;
+    let mut detector = rustface::create_detector(model)
+        .map_err(|e| format!("Failed to load face model {}: {}", config.model.display(), e))?;
+    detector.set_min_face_size(40);
+    detector.set_score_thresh(2.0);
+    detector.set_pyramid_scale_factor(0.8);
+    detector.set_slide_window_step(4, 4);
+
+    let image = ImageData::new(&frame.pixels, frame.width, frame.height);
+    let widest = detector
+        .detect(&image)
+        .iter()
+        .map(|face| face.bbox().width())
+        .max();
+
+    Ok(widest.map(|width| width as f64 / frame.width as f64))
+}
+
+/// Tracks how long the user has been too close and warns once per episode
+pub fn update(config: &PostureConfig, state: &mut State, face_width: Option<f64>) {
+    let now = Utc::now().timestamp();
+
+    // No face in view says nothing about distance, so leave the episode as it is
+    let Some(width) = face_width else {
+        return;
+    };
+
+    if width <= config.max_face_width {
+        state.too_close_since = None;
+        state.posture_warned = false;
+        return;
+    }
+
+    let since = *state.too_close_since.get_or_insert(now);
+    let minutes = (now - since) as f64 / 60.0;
+
+    if minutes >= config.minutes && !state.posture_warned {
+        notify::send(
+            "You're sitting close to the screen",
+            "Lean back a little to give your eyes some distance",
+            Urgency::Normal,
+        );
+        state.posture_warned = true;
+    }
+}
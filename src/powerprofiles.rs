@@ -0,0 +1,65 @@
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use zbus::{proxy, Connection};
+
+/// `[power_saver]` config section: behavior while power-profiles-daemon is in power-saver mode
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PowerSaverConfig {
+    /// Highest automatic brightness [0.0..1.0]
+    pub max_brightness: f64,
+    /// The daemon's update interval is multiplied by this
+    pub interval_factor: f64,
+}
+
+impl Default for PowerSaverConfig {
+    fn default() -> Self {
+        PowerSaverConfig { max_brightness: 0.7, interval_factor: 2.0 }
+    }
+}
+
+#[proxy(
+    interface = "net.hadess.PowerProfiles",
+    default_service = "net.hadess.PowerProfiles",
+    default_path = "/net/hadess/PowerProfiles"
+)]
+trait PowerProfiles {
+    /// "power-saver", "balanced" or "performance"
+    #[zbus(property)]
+    fn active_profile(&self) -> zbus::Result<String>;
+}
+
+/// Whether the power-saver profile is active; false without power-profiles-daemon
+pub async fn power_saver() -> bool {
+    async {
+        let connection = Connection::system().await?;
+        PowerProfilesProxy::new(&connection).await?.active_profile().await
+    }
+    .await
+    .map_or(false, |profile| profile == "power-saver")
+}
+
+/// Follows profile switches in the background through the property's change signal
+pub async fn watch() -> watch::Receiver<bool> {
+    let (sender, receiver) = watch::channel(power_saver().await);
+
+    tokio::spawn(async move {
+        let result: zbus::Result<()> = async {
+            let connection = Connection::system().await?;
+            let profiles = PowerProfilesProxy::new(&connection).await?;
+            let mut changes = profiles.receive_active_profile_changed().await;
+            while let Some(change) = changes.next().await {
+                sender.send_replace(change.get().await? == "power-saver");
+            }
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            eprintln!("Power profile unavailable: {}", e);
+        }
+    });
+
+    receiver
+}
@@ -0,0 +1,87 @@
+use std::error::Error;
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// Failures with a known remedy, shown along with a hint on how to fix them
+#[derive(Debug)]
+pub enum Problem {
+    /// A program healthy-monitor runs isn't installed
+    MissingProgram(&'static str),
+    /// The webcam device exists but may not be opened
+    CameraPermission(PathBuf),
+    /// Neither an X11 nor a Wayland display is reachable
+    NoDisplay,
+    /// xrandr lists no monitors
+    NoMonitors,
+    /// Only the primary output was asked for, but none is marked primary
+    NoPrimary,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::MissingProgram(program) => write!(f, "{} not found", program),
+            Problem::CameraPermission(device) => write!(f, "No permission to open {}", device.display()),
+            Problem::NoDisplay => write!(f, "No display to talk to, DISPLAY is not set"),
+            Problem::NoMonitors => write!(f, "No monitors detected"),
+            Problem::NoPrimary => write!(f, "No output is marked primary"),
+        }
+    }
+}
+
+impl Error for Problem {}
+
+impl Problem {
+    /// What to do about it
+    pub fn hint(&self) -> String {
+        match self {
+            Problem::MissingProgram("xrandr") => {
+                "install x11-xserver-utils (Debian, Ubuntu), xorg-xrandr (Arch) or xrandr (Fedora); \
+                 on Wayland it only reaches XWayland outputs"
+                    .to_string()
+            }
+            Problem::MissingProgram(program) => format!("install {} and make sure it is on PATH", program),
+            Problem::CameraPermission(device) => format!(
+                "{} belongs to the video group: run `sudo usermod -aG video $USER` and log in again",
+                device.display()
+            ),
+            Problem::NoDisplay => {
+                "set DISPLAY (usually DISPLAY=:0) when running from cron or a systemd service".to_string()
+            }
+            Problem::NoMonitors => "check that a screen is connected, or name outputs with --monitors".to_string(),
+            Problem::NoPrimary => {
+                "mark one with `xrandr --output NAME --primary` or in the display settings, or name it with --monitors"
+                    .to_string()
+            }
+        }
+    }
+}
+
+/// The error from spawning `program`, telling a missing program apart
+pub fn spawn_failed(program: &'static str, error: std::io::Error) -> Box<dyn Error> {
+    match error.kind() {
+        ErrorKind::NotFound => Box::new(Problem::MissingProgram(program)),
+        _ => format!("Failed to run {}: {}", program, error).into(),
+    }
+}
+
+/// What to do about `error`, when a known problem is behind it
+pub fn hint(error: &(dyn Error + 'static)) -> Option<String> {
+    let mut cause = Some(error);
+    while let Some(e) = cause {
+        if let Some(problem) = e.downcast_ref::<Problem>() {
+            return Some(problem.hint());
+        }
+        cause = e.source();
+    }
+    None
+}
+
+/// The message for `error`, followed by a hint on how to fix it where one is known
+pub fn present(error: &(dyn Error + 'static)) -> String {
+    match hint(error) {
+        Some(hint) => format!("{}\n  hint: {}", error, hint),
+        None => error.to_string(),
+    }
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::focus::Focus;
+use crate::state::State;
+use crate::Args;
+
+/// `[profile.<name>]` config section overriding settings while the profile is active
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProfileConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_brightness: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub night_min_brightness: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_brightness: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness_exponent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub day_temp: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub night_temp: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transition_hours: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_step: Option<f64>,
+
+    /// Fixed brightness, skipping ambient light sensing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness: Option<f64>,
+    /// Fixed color temperature, ignoring the schedule (Kelvin)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temp: Option<f64>,
+
+    /// Enable or disable break reminders while this profile is active
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breaks: Option<bool>,
+}
+
+/// The profile in effect: one mapped to the focused app wins over one activated manually
+pub fn active<'a>(config: &'a Config, focus: &Focus, state: &State) -> Option<&'a ProfileConfig> {
+    focus
+        .profile
+        .as_deref()
+        .or(state.profile.as_deref())
+        .and_then(|name| config.profile.get(name))
+}
+
+impl ProfileConfig {
+    /// Copy of `args` with this profile's overrides applied
+    pub fn apply(&self, args: &Args) -> Args {
+        let mut args = args.clone();
+
+        macro_rules! set {
+            ($($field:ident),*) => {
+                $(
+                    if let Some(value) = self.$field {
+                        args.$field = value.into();
+                    }
+                )*
+            };
+        }
+
+        set!(
+            min_brightness,
+            night_min_brightness,
+            max_brightness,
+            brightness_exponent,
+            day_temp,
+            night_temp,
+            transition_hours,
+            max_step
+        );
+
+        args
+    }
+}
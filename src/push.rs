@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+/// `[push]` config section: deliver reminders to a phone as well
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PushConfig {
+    /// ntfy topic name on ntfy.sh, or the full URL of a topic on another server
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ntfy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub telegram: Option<TelegramConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TelegramConfig {
+    pub bot_token: String,
+    pub chat_id: String,
+}
+
+/// Pushes a message to every configured service, logging failures
+pub async fn send(config: &PushConfig, title: &str, body: &str) {
+    let client = reqwest::Client::new();
+
+    if let Some(topic) = &config.ntfy {
+        let url = if topic.starts_with("http://") || topic.starts_with("https://") {
+            topic.clone()
+        } else {
+            format!("https://ntfy.sh/{}", topic)
+        };
+
+        let result = client.post(&url).header("Title", title).body(body.to_string()).send().await;
+        if let Err(e) = result.and_then(|r| r.error_for_status()) {
+            eprintln!("Failed to push to ntfy: {}", e);
+        }
+    }
+
+    if let Some(telegram) = &config.telegram {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", telegram.bot_token);
+        let message = serde_json::json!({
+            "chat_id": telegram.chat_id,
+            "text": format!("{}\n{}", title, body),
+        });
+
+        let result = client.post(&url).json(&message).send().await;
+        if let Err(e) = result.and_then(|r| r.error_for_status()) {
+            // The URL holds the bot token, keep it out of the log
+            eprintln!("Failed to push to Telegram: {}", e.without_url());
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::fmt;
+use std::process::Command;
+
+use crate::problem::{self, Problem};
+
+/// An output as listed by `xrandr --query`
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputInfo {
+    pub name: String,
+    /// A screen is plugged in
+    pub connected: bool,
+    pub primary: bool,
+    /// Where the output shows part of the screen; `None` while it's switched off
+    pub geometry: Option<Geometry>,
+}
+
+/// Size and position of an output within the screen (pixels)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Geometry {
+    pub width: u32,
+    pub height: u32,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl fmt::Display for Geometry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{}{:+}{:+}", self.width, self.height, self.x, self.y)
+    }
+}
+
+impl OutputInfo {
+    /// Connected and showing part of the screen, so gamma can be set on it
+    pub fn active(&self) -> bool {
+        self.connected && self.geometry.is_some()
+    }
+}
+
+/// Every output xrandr knows of, connected or not
+pub fn query() -> Result<Vec<OutputInfo>, Box<dyn std::error::Error>> {
+    let output = Command::new("xrandr")
+        .arg("--query")
+        .output()
+        .map_err(|e| problem::spawn_failed("xrandr", e))?;
+
+    if !output.status.success() {
+        if std::env::var_os("DISPLAY").is_none() {
+            return Err(Problem::NoDisplay.into());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Failed to execute xrandr --query: {}", stderr.trim()).into());
+    }
+
+    Ok(parse(&String::from_utf8(output.stdout)?))
+}
+
+/// Output lines of `xrandr --query`, such as
+/// `DP-1 connected primary 2560x1440+0+0 (normal left inverted right x axis y axis) 597mm x 336mm`.
+/// The indented mode lines below each output and the `Screen 0:` line are skipped.
+fn parse(query: &str) -> Vec<OutputInfo> {
+    query
+        .lines()
+        .filter(|line| !line.starts_with(char::is_whitespace))
+        .filter_map(|line| {
+            let mut tokens = line.split_whitespace().peekable();
+            let name = tokens.next()?.to_string();
+            let connected = match tokens.next()? {
+                "connected" => true,
+                "disconnected" => false,
+                // Some drivers can't tell: "unknown connection"
+                "unknown" if tokens.next() == Some("connection") => true,
+                _ => return None,
+            };
+            let primary = tokens.next_if_eq(&"primary").is_some();
+            let geometry = tokens.next().and_then(geometry);
+            Some(OutputInfo { name, connected, primary, geometry })
+        })
+        .collect()
+}
+
+/// A `WIDTHxHEIGHT+X+Y` token
+fn geometry(token: &str) -> Option<Geometry> {
+    let (size, offsets) = token.split_at(token.find(['+', '-'])?);
+    let (width, height) = size.split_once('x')?;
+
+    // Offsets carry their own sign: "+0+0", "+1920-200"
+    let (x, y) = offsets.split_at(offsets[1..].find(['+', '-'])? + 1);
+
+    Some(Geometry {
+        width: width.parse().ok()?,
+        height: height.parse().ok()?,
+        x: x.trim_start_matches('+').parse().ok()?,
+        y: y.trim_start_matches('+').parse().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUERY: &str = "\
+Screen 0: minimum 8 x 8, current 4480 x 1440, maximum 32767 x 32767
+DP-1 connected primary 2560x1440+0+0 (normal left inverted right x axis y axis) 597mm x 336mm
+   2560x1440     59.95*+
+HDMI-1 connected 1920x1080+2560-200 (normal left inverted right x axis y axis) 527mm x 296mm
+   1920x1080     60.00*+
+DP-2 disconnected (normal left inverted right x axis y axis)
+VGA-1 unknown connection 1024x768+0+1440 (normal left inverted right x axis y axis) 0mm x 0mm
+eDP-1 connected (normal left inverted right x axis y axis)
+";
+
+    #[test]
+    fn parses_outputs_and_skips_modes() {
+        let outputs = parse(QUERY);
+        let names: Vec<&str> = outputs.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(names, ["DP-1", "HDMI-1", "DP-2", "VGA-1", "eDP-1"]);
+
+        assert!(outputs[0].primary && outputs[0].active());
+        assert_eq!(outputs[0].geometry, Some(Geometry { width: 2560, height: 1440, x: 0, y: 0 }));
+        assert!(!outputs[1].primary);
+    }
+
+    #[test]
+    fn negative_offsets() {
+        let outputs = parse(QUERY);
+        assert_eq!(outputs[1].geometry, Some(Geometry { width: 1920, height: 1080, x: 2560, y: -200 }));
+        assert_eq!(geometry("800x600-1920-1080"), Some(Geometry { width: 800, height: 600, x: -1920, y: -1080 }));
+        assert_eq!(outputs[1].geometry.unwrap().to_string(), "1920x1080+2560-200");
+    }
+
+    #[test]
+    fn unknown_connection_counts_as_connected() {
+        let vga = &parse(QUERY)[3];
+        assert!(vga.connected);
+        assert_eq!(vga.geometry, Some(Geometry { width: 1024, height: 768, x: 0, y: 1440 }));
+    }
+
+    #[test]
+    fn switched_off_and_disconnected_outputs_are_inactive() {
+        let outputs = parse(QUERY);
+        assert!(!outputs[2].connected && outputs[2].geometry.is_none());
+        assert!(outputs[4].connected && !outputs[4].active());
+    }
+
+    #[test]
+    fn rejects_malformed_geometry() {
+        assert_eq!(geometry("(normal"), None);
+        assert_eq!(geometry("1920x1080"), None);
+        assert_eq!(geometry("1920+0+0"), None);
+        assert_eq!(geometry("axb+0+0"), None);
+    }
+}
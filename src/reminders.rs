@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::breaks::BreakTimer;
+use crate::notify::{self, QuietConfig, Urgency};
+use crate::schedule::{self, TimeOfDay};
+use crate::state::State;
+
+/// How late a reminder at a fixed time may still be shown, e.g. after quiet hours (hours)
+const AT_GRACE_HOURS: f64 = 1.0;
+
+/// A `[[reminders]]` entry: a custom reminder after a stretch of active use, or at a time of day
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReminderConfig {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub body: String,
+    /// Minutes of active screen time between reminders
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub every_active_minutes: Option<f64>,
+    /// Time of day of a daily reminder
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub at: Option<TimeOfDay>,
+}
+
+impl ReminderConfig {
+    /// Key of a daily reminder in the saved state; two reminders may share a title
+    fn key(&self, at: TimeOfDay) -> String {
+        format!("{}@{}", self.title, at)
+    }
+
+    fn send(&self) {
+        notify::send(&self.title, &self.body, Urgency::Normal);
+    }
+}
+
+/// Keeps track of when each custom reminder is due
+#[derive(Debug)]
+pub struct Reminders {
+    timers: Vec<BreakTimer>,
+}
+
+impl Reminders {
+    pub fn new(configs: &[ReminderConfig]) -> Self {
+        Reminders { timers: configs.iter().map(|_| BreakTimer::default()).collect() }
+    }
+
+    /// Advances the active-time reminders by `elapsed` and shows those due,
+    /// along with daily reminders whose time has come
+    pub fn tick(&mut self, configs: &[ReminderConfig], elapsed: Duration, idle_for: Duration, quiet: &QuietConfig) {
+        for (config, timer) in configs.iter().zip(&mut self.timers) {
+            let Some(minutes) = config.every_active_minutes else {
+                continue;
+            };
+
+            let interval = Duration::from_secs_f64(minutes.max(0.0) * 60.0);
+            if timer.tick(interval, elapsed, idle_for, Duration::MAX) {
+                timer.reset();
+                if !notify::quiet(quiet) {
+                    config.send();
+                }
+            }
+        }
+
+        // Daily reminders with the day they fell due on, which is yesterday for one at 23:30
+        // still inside its grace period after midnight
+        let now = Local::now();
+        let hour = schedule::hour_of(now);
+        let due: Vec<(&ReminderConfig, TimeOfDay, NaiveDate)> = configs
+            .iter()
+            .filter_map(|c| {
+                let at = c.at?;
+                let since = (hour - at.0).rem_euclid(24.0);
+                let day = (now - chrono::Duration::seconds((since * 3600.0) as i64)).date_naive();
+                (since < AT_GRACE_HOURS).then_some((c, at, day))
+            })
+            .collect();
+        if due.is_empty() || notify::quiet(quiet) {
+            return;
+        }
+
+        let mut state = State::load();
+        let mut changed = false;
+        for (config, at, day) in due {
+            let key = config.key(at);
+            if state.reminders_sent.get(&key) != Some(&day) {
+                config.send();
+                state.reminders_sent.insert(key, day);
+                changed = true;
+            }
+        }
+
+        if changed {
+            if let Err(e) = state.save() {
+                eprintln!("Failed to save state: {}", e);
+            }
+        }
+    }
+}
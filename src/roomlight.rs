@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::hue::{HueConfig, HueLight};
+use crate::lifx::LifxConfig;
+use crate::tuya::TuyaConfig;
+
+/// Smallest changes worth sending to the lights again
+const TEMP_TOLERANCE: f64 = 50.0;
+const BRIGHTNESS_TOLERANCE: f64 = 0.02;
+
+/// `[room_lights]` config section: smart bulbs following the screen's color temperature
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RoomLightsConfig {
+    /// Room brightness during the day and at night [0.0..1.0], blended like the temperature
+    pub day_brightness: f64,
+    pub night_brightness: f64,
+    /// Fade time of each change, where the bulb supports it
+    pub transition_seconds: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hue: Option<HueConfig>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub lifx: Vec<LifxConfig>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tuya: Vec<TuyaConfig>,
+}
+
+impl Default for RoomLightsConfig {
+    fn default() -> Self {
+        RoomLightsConfig {
+            day_brightness: 1.0,
+            night_brightness: 0.4,
+            transition_seconds: 2.0,
+            hue: None,
+            lifx: Vec::new(),
+            tuya: Vec::new(),
+        }
+    }
+}
+
+/// How the screen's values translate to one bulb. Each bulb's config has these fields of its own
+/// rather than a flattened `Mapping`, so `config check` still sees typos in them.
+#[derive(Debug, Clone, Copy)]
+pub struct Mapping {
+    /// Color temperature range the bulb supports; the screen's is clamped into it
+    pub min_kelvin: f64,
+    pub max_kelvin: f64,
+    /// Added to the screen temperature, e.g. -500 for a room a little warmer than the screen
+    pub temp_offset: f64,
+    /// Bulb brightness at full room brightness [0.0..1.0]
+    pub max_brightness: f64,
+}
+
+impl Default for Mapping {
+    fn default() -> Self {
+        Mapping { min_kelvin: 2000.0, max_kelvin: 6500.0, temp_offset: 0.0, max_brightness: 1.0 }
+    }
+}
+
+impl Mapping {
+    /// Bulb temperature and brightness for the screen temperature and room brightness
+    pub fn apply(&self, color_temp: f64, brightness: f64) -> (f64, f64) {
+        let kelvin = (color_temp + self.temp_offset).clamp(self.min_kelvin, self.max_kelvin);
+        (kelvin, brightness.clamp(0.0, 1.0) * self.max_brightness)
+    }
+}
+
+/// A bulb that can take a white color temperature and brightness
+pub trait RoomLight {
+    /// Identifies the bulb in error messages
+    fn describe(&self) -> String;
+
+    fn mapping(&self) -> Mapping;
+
+    /// Sets the bulb without switching it on when it is off
+    async fn set(&self, kelvin: f64, brightness: f64, transition: Duration) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// Sends `color_temp` and `brightness` to every configured bulb through its mapping. Returns
+/// whether anything was sent, which only happens when the values moved noticeably from
+/// `last_sent`; a bulb failing doesn't keep the others from being set.
+pub async fn sync(config: &RoomLightsConfig, color_temp: f64, brightness: f64, last_sent: Option<[f64; 2]>) -> bool {
+    if let Some([temp, bri]) = last_sent {
+        if (temp - color_temp).abs() < TEMP_TOLERANCE && (bri - brightness).abs() < BRIGHTNESS_TOLERANCE {
+            return false;
+        }
+    }
+
+    let transition = Duration::from_secs_f64(config.transition_seconds.max(0.0));
+    if let Some(hue) = &config.hue {
+        for id in &hue.lights {
+            set(&HueLight { config: hue, id }, color_temp, brightness, transition).await;
+        }
+    }
+    for bulb in &config.lifx {
+        set(bulb, color_temp, brightness, transition).await;
+    }
+    for bulb in &config.tuya {
+        set(bulb, color_temp, brightness, transition).await;
+    }
+    true
+}
+
+async fn set(light: &impl RoomLight, color_temp: f64, brightness: f64, transition: Duration) {
+    let (kelvin, brightness) = light.mapping().apply(color_temp, brightness);
+    if let Err(e) = light.set(kelvin, brightness, transition).await {
+        eprintln!("Failed to update {}: {}", light.describe(), e);
+    }
+}
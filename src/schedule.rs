@@ -0,0 +1,344 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Local, Timelike, Weekday};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Hour of the day at which night begins unless configured otherwise
+const NIGHT_START: f64 = 18.0;
+/// Hour of the day at which night ends unless configured otherwise
+const NIGHT_END: f64 = 6.0;
+/// Melatonin starts rising roughly this long before sleep, so when only a bedtime is
+/// configured, night (and fully warm light) starts this many hours earlier
+pub const SLEEP_LEAD_HOURS: f64 = 1.5;
+
+/// Period of the day, named like redshift's so its hooks keep working
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Daytime,
+    Transition,
+    Night,
+}
+
+impl Period {
+    pub fn name(self) -> &'static str {
+        match self {
+            Period::Daytime => "daytime",
+            Period::Transition => "transition",
+            Period::Night => "night",
+        }
+    }
+}
+
+/// Where a point in time falls in the day/night schedule
+#[derive(Debug, Clone, Copy)]
+pub struct SchedulePoint {
+    pub period: Period,
+    /// 1.0 during the day, 0.0 at night and in between while transitioning
+    pub day_weight: f64,
+}
+
+impl SchedulePoint {
+    /// Interpolates between the night and day values of a setting
+    pub fn blend(&self, day: f64, night: f64) -> f64 {
+        day * self.day_weight + night * (1.0 - self.day_weight)
+    }
+}
+
+/// Night boundaries in effect on a particular day
+#[derive(Debug, Clone, Copy)]
+pub struct DayTimes {
+    pub night_start: f64,
+    pub night_end: f64,
+    /// Hours before `night_start` over which to transition
+    pub transition_hours: f64,
+}
+
+/// Locates `now` in the schedule described by `times`
+pub fn at(now: DateTime<Local>, times: &DayTimes) -> SchedulePoint {
+    let hour = hour_of(now);
+
+    let night = if times.night_start > times.night_end {
+        hour >= times.night_start || hour <= times.night_end
+    } else {
+        hour >= times.night_start && hour <= times.night_end
+    };
+    let until_night = (times.night_start - hour).rem_euclid(24.0);
+
+    if night {
+        SchedulePoint { period: Period::Night, day_weight: 0.0 }
+    } else if until_night > 0.0 && until_night <= times.transition_hours {
+        let progress = until_night / times.transition_hours;
+        SchedulePoint { period: Period::Transition, day_weight: progress }
+    } else {
+        SchedulePoint { period: Period::Daytime, day_weight: 1.0 }
+    }
+}
+
+/// The next period boundary after `hour`: its hour and the period it starts
+pub fn next_change(hour: f64, times: &DayTimes) -> (f64, Period) {
+    let transition_start = (times.night_start - times.transition_hours).rem_euclid(24.0);
+    let mut changes = vec![(times.night_start, Period::Night), (times.night_end, Period::Daytime)];
+    if times.transition_hours > 0.0 {
+        changes.push((transition_start, Period::Transition));
+    }
+
+    changes
+        .into_iter()
+        .min_by(|(a, _), (b, _)| {
+            let until = |h: f64| {
+                let d = (h - hour).rem_euclid(24.0);
+                if d == 0.0 { 24.0 } else { d }
+            };
+            until(*a).total_cmp(&until(*b))
+        })
+        .unwrap()
+}
+
+/// `[schedule]` config section, with optional per-day overrides
+///
+/// The most specific entry wins: a named day, then `weekday`/`weekend`, then the base values.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ScheduleConfig {
+    // The base values are spelled out rather than a flattened `DaySchedule`, which would hide
+    // typos in them from `config check`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub night_start: Option<TimeOfDay>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub night_end: Option<TimeOfDay>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transition_hours: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bedtime: Option<TimeOfDay>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wake: Option<TimeOfDay>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weekday: Option<DaySchedule>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weekend: Option<DaySchedule>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monday: Option<DaySchedule>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tuesday: Option<DaySchedule>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wednesday: Option<DaySchedule>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thursday: Option<DaySchedule>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub friday: Option<DaySchedule>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saturday: Option<DaySchedule>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sunday: Option<DaySchedule>,
+}
+
+/// Schedule values that can be set for all days or overridden per day
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DaySchedule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub night_start: Option<TimeOfDay>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub night_end: Option<TimeOfDay>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transition_hours: Option<f64>,
+    /// Bedtime the wind-down leads up to, see `[bedtime]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bedtime: Option<TimeOfDay>,
+    /// Usual wake-up time; night ends then unless `night_end` is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wake: Option<TimeOfDay>,
+}
+
+impl ScheduleConfig {
+    /// The values set for all days
+    fn base(&self) -> DaySchedule {
+        DaySchedule {
+            night_start: self.night_start,
+            night_end: self.night_end,
+            transition_hours: self.transition_hours,
+            bedtime: self.bedtime,
+            wake: self.wake,
+        }
+    }
+
+    /// Entries that apply to `day`, most specific first
+    fn layers(&self, day: Weekday) -> [Option<DaySchedule>; 3] {
+        let named = match day {
+            Weekday::Mon => &self.monday,
+            Weekday::Tue => &self.tuesday,
+            Weekday::Wed => &self.wednesday,
+            Weekday::Thu => &self.thursday,
+            Weekday::Fri => &self.friday,
+            Weekday::Sat => &self.saturday,
+            Weekday::Sun => &self.sunday,
+        };
+        let group = match day {
+            Weekday::Sat | Weekday::Sun => &self.weekend,
+            _ => &self.weekday,
+        };
+
+        [*named, *group, Some(self.base())]
+    }
+
+    /// First value set for `day`, looking from the most specific entry down
+    pub fn lookup<T>(&self, day: Weekday, field: impl Fn(&DaySchedule) -> Option<T>) -> Option<T> {
+        self.layers(day).iter().flatten().find_map(field)
+    }
+
+    /// Night boundaries for the day of `now`. Boundaries that aren't set explicitly
+    /// follow the sleep schedule: night starts `SLEEP_LEAD_HOURS` before bedtime and ends at wake-up.
+    /// Either way the most specific entry wins, so a weekend bedtime beats a base `night_start`.
+    pub fn times(&self, now: DateTime<Local>, transition_hours: f64) -> DayTimes {
+        let day = now.weekday();
+        let from_bedtime = |bedtime: TimeOfDay| (bedtime.0 - SLEEP_LEAD_HOURS).rem_euclid(24.0);
+        DayTimes {
+            night_start: self
+                .lookup(day, |d| d.night_start.map(|t| t.0).or(d.bedtime.map(from_bedtime)))
+                .unwrap_or(NIGHT_START),
+            night_end: self
+                .lookup(day, |d| d.night_end.or(d.wake))
+                .map_or(NIGHT_END, |t| t.0),
+            transition_hours: self.lookup(day, |d| d.transition_hours).unwrap_or(transition_hours),
+        }
+    }
+
+    /// Bedtime on `day` as hours after that day's midnight, past 24 for bedtimes after midnight
+    pub fn bedtime_hours(&self, day: Weekday) -> Option<f64> {
+        self.lookup(day, |d| d.bedtime).map(|b| if b.0 < 12.0 { b.0 + 24.0 } else { b.0 })
+    }
+}
+
+/// Local time as fractional hours since midnight
+pub fn hour_of(now: DateTime<Local>) -> f64 {
+    now.hour() as f64 + (now.minute() as f64 / 60.0)
+}
+
+/// Time of day written as `HH:MM` in the config, stored as fractional hours
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct TimeOfDay(pub f64);
+
+impl TimeOfDay {
+    /// Whether `hour` falls in the window from `start` up to `end`, which may wrap past midnight
+    pub fn window_contains(start: TimeOfDay, end: TimeOfDay, hour: f64) -> bool {
+        if start.0 <= end.0 {
+            hour >= start.0 && hour < end.0
+        } else {
+            hour >= start.0 || hour < end.0
+        }
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid time '{}', expected HH:MM", s);
+        let (h, m) = s.trim().split_once(':').ok_or_else(invalid)?;
+        let h: u32 = h.parse().map_err(|_| invalid())?;
+        let m: u32 = m.parse().map_err(|_| invalid())?;
+
+        if h > 23 || m > 59 {
+            return Err(invalid());
+        }
+
+        Ok(TimeOfDay(h as f64 + m as f64 / 60.0))
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let minutes = (self.0 * 60.0).round() as u32;
+        write!(f, "{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeOfDay {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for TimeOfDay {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    const DEFAULT: DayTimes = DayTimes { night_start: NIGHT_START, night_end: NIGHT_END, transition_hours: 2.0 };
+
+    fn point(hour: u32, minute: u32, times: &DayTimes) -> (Period, f64) {
+        let now = Local.with_ymd_and_hms(2026, 6, 10, hour, minute, 0).unwrap();
+        let point = at(now, times);
+        (point.period, point.day_weight)
+    }
+
+    #[test]
+    fn night_across_midnight() {
+        assert_eq!(point(12, 0, &DEFAULT), (Period::Daytime, 1.0));
+        assert_eq!(point(17, 0, &DEFAULT), (Period::Transition, 0.5));
+        assert_eq!(point(18, 0, &DEFAULT), (Period::Night, 0.0));
+        assert_eq!(point(3, 0, &DEFAULT), (Period::Night, 0.0));
+        assert_eq!(point(6, 0, &DEFAULT), (Period::Night, 0.0));
+        assert_eq!(point(6, 30, &DEFAULT), (Period::Daytime, 1.0));
+    }
+
+    #[test]
+    fn transition_boundaries() {
+        assert_eq!(point(15, 59, &DEFAULT).0, Period::Daytime);
+        assert_eq!(point(16, 0, &DEFAULT), (Period::Transition, 1.0));
+        assert_eq!(point(17, 59, &DEFAULT).0, Period::Transition);
+
+        let instant = DayTimes { transition_hours: 0.0, ..DEFAULT };
+        assert_eq!(point(17, 59, &instant), (Period::Daytime, 1.0));
+        assert_eq!(point(18, 0, &instant), (Period::Night, 0.0));
+    }
+
+    #[test]
+    fn night_after_midnight() {
+        // Bedtime past midnight: night and the transition before it both wrap the day
+        let late = DayTimes { night_start: 0.5, night_end: 8.0, transition_hours: 2.0 };
+        assert_eq!(point(23, 30, &late), (Period::Transition, 0.5));
+        assert_eq!(point(0, 30, &late), (Period::Night, 0.0));
+        assert_eq!(point(7, 0, &late), (Period::Night, 0.0));
+        assert_eq!(point(12, 0, &late), (Period::Daytime, 1.0));
+    }
+
+    #[test]
+    fn blend() {
+        let halfway = SchedulePoint { period: Period::Transition, day_weight: 0.5 };
+        assert_eq!(halfway.blend(6500.0, 3500.0), 5000.0);
+    }
+
+    #[test]
+    fn next_period_change() {
+        assert_eq!(next_change(12.0, &DEFAULT), (16.0, Period::Transition));
+        assert_eq!(next_change(16.0, &DEFAULT), (18.0, Period::Night));
+        assert_eq!(next_change(20.0, &DEFAULT), (6.0, Period::Daytime));
+        let instant = DayTimes { transition_hours: 0.0, ..DEFAULT };
+        assert_eq!(next_change(12.0, &instant), (18.0, Period::Night));
+    }
+
+    #[test]
+    fn time_of_day() {
+        assert_eq!("07:30".parse::<TimeOfDay>().unwrap().0, 7.5);
+        assert_eq!(TimeOfDay(23.75).to_string(), "23:45");
+        assert!("24:00".parse::<TimeOfDay>().is_err());
+        assert!("7".parse::<TimeOfDay>().is_err());
+
+        let (start, end) = (TimeOfDay(22.0), TimeOfDay(6.0));
+        assert!(TimeOfDay::window_contains(start, end, 23.0));
+        assert!(TimeOfDay::window_contains(start, end, 1.0));
+        assert!(!TimeOfDay::window_contains(start, end, 6.0));
+    }
+}
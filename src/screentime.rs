@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate, Timelike};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::{history, state};
+
+/// Input within this many seconds counts as active use
+pub const ACTIVE_TIMEOUT_SECONDS: f64 = 60.0;
+/// A pause in input at least this long counts as a break
+const BREAK_SECONDS: f64 = 20.0;
+/// How often accumulated screen time is written to the history database
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+/// Blue light from this hour on counts toward the evening dose
+const EVENING_HOUR: f64 = 18.0;
+/// Activity before this hour still belongs to the previous day's evening
+const DAY_BOUNDARY_HOUR: i64 = 4;
+
+/// Screen time recorded for one day
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DayStats {
+    pub active_seconds: f64,
+    pub longest_stretch_seconds: f64,
+    pub breaks: u32,
+    /// Active time spent in bright ambient light
+    pub bright_seconds: f64,
+    /// Part of `bright_seconds` before noon
+    pub morning_bright_seconds: f64,
+    /// Blue light dose after `EVENING_HOUR`: seconds at the blue output of a neutral screen at full brightness
+    pub evening_blue_light: f64,
+    /// Hour of the first active use, see `activity_day`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_active_hour: Option<f64>,
+    /// Hour of the last active use, past 24 for use after midnight
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_active_hour: Option<f64>,
+}
+
+/// Screen time per day, keyed by date
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct ScreenTime {
+    pub days: BTreeMap<NaiveDate, DayStats>,
+}
+
+impl ScreenTime {
+    /// Screen time from the history database, after moving over any from the old JSON file
+    pub fn load() -> Self {
+        migrate();
+        match history::days() {
+            Ok(days) => ScreenTime { days },
+            Err(e) => {
+                eprintln!("Failed to read screen time: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn day(&self, date: NaiveDate) -> DayStats {
+        self.days.get(&date).cloned().unwrap_or_default()
+    }
+}
+
+/// Light conditions while the screen is in use
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Exposure {
+    /// The room is brightly lit
+    pub bright: bool,
+    /// Blue light emitted by the screen, see `light::blue_light`
+    pub blue_light: f64,
+}
+
+/// Accumulates screen time in the daemon
+#[derive(Debug)]
+pub struct Tracker {
+    history: ScreenTime,
+    /// Days of screen time kept in the history database, see `history_days`
+    retention_days: u32,
+    /// Current uninterrupted stretch of active use (seconds)
+    stretch: f64,
+    /// Whether the current pause in input already counted as a break
+    on_break: bool,
+    unsaved: Duration,
+}
+
+impl Tracker {
+    pub fn load(config: &Config) -> Self {
+        Tracker {
+            history: ScreenTime::load(),
+            retention_days: history::retention_days(config),
+            stretch: 0.0,
+            on_break: false,
+            unsaved: Duration::ZERO,
+        }
+    }
+
+    /// Records `elapsed` time given the seconds since the last input, or only
+    /// whether the session is idle when input times aren't available
+    pub fn tick(&mut self, elapsed: Duration, idle_seconds: Option<f64>, session_idle: bool, exposure: Exposure) {
+        let (active, paused) = match idle_seconds {
+            Some(seconds) => (seconds < ACTIVE_TIMEOUT_SECONDS, seconds >= BREAK_SECONDS),
+            None => (!session_idle, session_idle),
+        };
+
+        let now = Local::now();
+        let today = self.history.days.entry(now.date_naive()).or_default();
+
+        if paused && !self.on_break {
+            today.breaks += 1;
+            self.stretch = 0.0;
+        }
+        self.on_break = paused;
+
+        if active {
+            today.active_seconds += elapsed.as_secs_f64();
+            self.stretch += elapsed.as_secs_f64();
+            today.longest_stretch_seconds = today.longest_stretch_seconds.max(self.stretch);
+
+            if exposure.bright {
+                today.bright_seconds += elapsed.as_secs_f64();
+                if now.hour() < 12 {
+                    today.morning_bright_seconds += elapsed.as_secs_f64();
+                }
+            }
+
+            let (day, hour) = activity_day(now);
+            let stats = self.history.days.entry(day).or_default();
+            stats.first_active_hour.get_or_insert(hour);
+            stats.last_active_hour = Some(hour);
+
+            if hour >= EVENING_HOUR {
+                stats.evening_blue_light += exposure.blue_light * elapsed.as_secs_f64();
+            }
+        }
+
+        self.unsaved += elapsed;
+        if self.unsaved >= SAVE_INTERVAL {
+            self.save();
+        }
+    }
+
+    /// Current uninterrupted stretch of active use (seconds)
+    pub fn stretch_seconds(&self) -> f64 {
+        self.stretch
+    }
+
+    /// Writes the days `tick` may still change: today, and yesterday until `DAY_BOUNDARY_HOUR`
+    pub fn save(&mut self) {
+        let yesterday = Local::now().date_naive() - chrono::Duration::days(1);
+        if let Err(e) = history::record_days(self.retention_days, self.history.days.range(yesterday..)) {
+            eprintln!("Failed to save screen time: {}", e);
+        }
+        self.unsaved = Duration::ZERO;
+    }
+}
+
+/// Prints screen time for `date`
+pub fn report(history: &ScreenTime, date: NaiveDate) -> Result<(), Box<dyn std::error::Error>> {
+    let stats = history.day(date);
+
+    println!("Screen time on {}", date);
+    println!("  Active:          {}", format_duration(stats.active_seconds));
+    println!("  Longest stretch: {}", format_duration(stats.longest_stretch_seconds));
+    println!("  Breaks taken:    {}", stats.breaks);
+    println!("  Bright light:    {}", format_duration(stats.bright_seconds));
+    println!("    before noon:   {}", format_duration(stats.morning_bright_seconds));
+
+    Ok(())
+}
+
+/// The day a moment of activity belongs to, and its hour counted from that day's midnight.
+/// Days end at `DAY_BOUNDARY_HOUR`, so 01:30 is hour 25.5 of the previous day.
+fn activity_day(now: DateTime<Local>) -> (NaiveDate, f64) {
+    let day = (now - chrono::Duration::hours(DAY_BOUNDARY_HOUR)).date_naive();
+    let hours = (now.naive_local() - day.and_hms_opt(0, 0, 0).unwrap()).num_seconds() as f64 / 3600.0;
+    (day, hours)
+}
+
+/// Formats seconds as e.g. "2h 05m"
+pub fn format_duration(seconds: f64) -> String {
+    let minutes = (seconds / 60.0).round() as u64;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+/// Moves screen time from the JSON file it used to be kept in into the history database
+fn migrate() {
+    let Some(path) = legacy_path() else {
+        return;
+    };
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return;
+    };
+
+    let imported = serde_json::from_str::<ScreenTime>(&contents)
+        .map_err(|e| e.to_string())
+        .and_then(|legacy| history::import_days(&legacy.days).map_err(|e| e.to_string()));
+    match imported {
+        Ok(()) => {
+            if let Err(e) = std::fs::remove_file(&path) {
+                eprintln!("Failed to remove {} after moving it to the history database: {}", path.display(), e);
+            }
+        }
+        Err(e) => eprintln!("Failed to move screen time from {}: {}", path.display(), e),
+    }
+}
+
+/// `$XDG_STATE_HOME/healthy-monitor/screen-time.json`, where screen time used to be kept
+fn legacy_path() -> Option<PathBuf> {
+    Some(state::state_dir()?.join("screen-time.json"))
+}
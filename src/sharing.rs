@@ -0,0 +1,66 @@
+use std::process::Command;
+
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::content;
+
+/// Substrings of PipeWire node names created for xdg-desktop-portal screencasts
+const PORTAL_NODE_NAMES: [&str; 4] = ["xdpw", "screencast", "xdg-desktop-portal", "gnome-shell"];
+
+/// Whether the screen is being shared or recorded: a running portal screencast
+/// stream, or one of the configured sharing processes
+pub fn active(config: &Config) -> bool {
+    portal_screencast_running() || process_running(&config.sharing_processes)
+}
+
+/// Looks for a running screencast video source in the PipeWire graph, other than the one
+/// measuring the screen content
+fn portal_screencast_running() -> bool {
+    let Ok(output) = Command::new("pw-dump").output() else {
+        return false;
+    };
+    let Ok(Value::Array(objects)) = serde_json::from_slice::<Value>(&output.stdout) else {
+        return false;
+    };
+
+    let own = content::own_node();
+    objects.iter().any(|object| {
+        let Some(info) = object.get("info") else {
+            return false;
+        };
+        if own.is_some() && object.get("id").and_then(Value::as_u64) == own.map(u64::from) {
+            return false;
+        }
+        let prop = |key: &str| info.pointer(&format!("/props/{}", key)).and_then(Value::as_str);
+
+        let video_source = prop("media.class") == Some("Video/Source");
+        let running = info.get("state").and_then(Value::as_str) == Some("running");
+        let portal = [prop("node.name"), prop("media.name")]
+            .into_iter()
+            .flatten()
+            .any(|name| {
+                let name = name.to_lowercase();
+                PORTAL_NODE_NAMES.iter().any(|portal| name.contains(portal))
+            });
+
+        video_source && running && portal
+    })
+}
+
+/// Whether a process with one of the given command names is running
+fn process_running(names: &[String]) -> bool {
+    if names.is_empty() {
+        return false;
+    }
+
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return false;
+    };
+
+    entries.filter_map(|entry| entry.ok()).any(|entry| {
+        std::fs::read_to_string(entry.path().join("comm"))
+            .map(|comm| names.iter().any(|name| comm.trim() == name))
+            .unwrap_or(false)
+    })
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::history::Source;
+use crate::schedule::Period;
+
+/// Result of applying settings to one output
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Output {
+    pub name: String,
+    /// Why the output couldn't be set, `None` when it was
+    pub error: Option<String>,
+    /// Left alone because its screen was powered down
+    #[serde(default)]
+    pub powered_off: bool,
+}
+
+/// Information remembered between runs
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct State {
+    /// Period of the day at the last run
+    pub period: Option<Period>,
+    /// Brightness applied at the last run
+    pub brightness: Option<f64>,
+    /// Color temperature applied at the last run
+    pub color_temp: Option<f64>,
+    /// Last ambient light level read from the webcam
+    pub ambient: Option<f64>,
+    /// Unix timestamp of the last webcam reading
+    pub ambient_at: Option<i64>,
+    /// Ambient level the last brightness was based on, after any fallback or blending
+    pub ambient_level: Option<f64>,
+    /// Where the last brightness came from
+    pub source: Option<Source>,
+    /// Whether another application held the webcam at the last run
+    pub camera_busy: bool,
+    /// Trust in the remembered reading used while the webcam was busy [0.0..1.0]
+    pub ambient_confidence: Option<f64>,
+    /// Whether the last webcam capture failed
+    pub webcam_failed: bool,
+    /// Unix timestamp since which the face has been too close to the screen
+    pub too_close_since: Option<i64>,
+    /// Whether the current too-close episode was already warned about
+    pub posture_warned: bool,
+    /// Unix timestamp since which the screen has been much brighter than the room
+    pub glare_since: Option<i64>,
+    /// Whether the current bright-screen episode was already warned about
+    pub glare_warned: bool,
+    /// Unix timestamp since which the room has read dark during the daylight nudge window
+    pub dark_room_since: Option<i64>,
+    /// Day the "open the blinds" nudge was last shown
+    pub daylight_nudged_on: Option<NaiveDate>,
+    /// Day the weekly summary was last sent
+    pub weekly_summary_sent: Option<NaiveDate>,
+    /// Day each daily custom reminder was last due and shown, keyed by title and time (`Water@14:00`)
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub reminders_sent: HashMap<String, NaiveDate>,
+    /// Active profile, `None` when under automatic control
+    pub profile: Option<String>,
+    /// Unix timestamp until which break reminders are snoozed
+    pub breaks_snoozed_until: Option<i64>,
+    /// Whether automatic adjustments are paused
+    pub paused: bool,
+    /// Unix timestamp at which a timed pause ends
+    pub paused_until: Option<i64>,
+    /// Whether darkroom mode is switched on
+    pub darkroom: bool,
+    /// Whether the grayscale command was last switched on
+    pub grayscale: bool,
+    /// Unix timestamp until which brightness or temperature set by hand are held
+    pub manual_until: Option<i64>,
+    /// Unix timestamp until which bedtime enforcement is lifted
+    pub bedtime_override_until: Option<i64>,
+    /// Whether the low-battery brightness cap is in effect (and was announced)
+    pub low_battery_capped: bool,
+    /// Color temperature and brightness last sent to the room lights
+    pub room_light: Option<[f64; 2]>,
+    /// Ambient level last estimated from the weather [0.0..1.0]
+    pub weather: Option<f64>,
+    /// When the weather was last fetched
+    pub weather_at: Option<i64>,
+    /// Latitude and longitude from IP geolocation, until the network changes
+    pub ip_location: Option<[f64; 2]>,
+    /// When the IP-based location was looked up
+    pub ip_location_at: Option<i64>,
+    /// Busy calendar events ahead, as start and end timestamps
+    pub calendar_busy: Vec<[i64; 2]>,
+    /// When the calendar was last loaded
+    pub calendar_checked_at: Option<i64>,
+    /// ScreenCast portal token that restores the screen capture permission
+    pub screencast_token: Option<String>,
+    /// Minutes-before-bedtime threshold of the last reminder shown tonight
+    pub bedtime_notice: Option<f64>,
+    /// How each output fared at the last run
+    pub outputs: Vec<Output>,
+}
+
+impl State {
+    /// Loads the saved state, starting fresh if there is none or it can't be read
+    pub fn load() -> Self {
+        let Some(path) = path() else {
+            return Self::default();
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("Ignoring invalid state file {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = path().ok_or("Cannot determine the state file location")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        write_atomic(&path, &serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it into place, so a crash or
+/// a concurrent reader never sees the file half written
+pub fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+    std::fs::write(&temp, contents)?;
+    std::fs::rename(&temp, path).map_err(|e| {
+        let _ = std::fs::remove_file(&temp);
+        e
+    })
+}
+
+/// `$XDG_STATE_HOME/healthy-monitor/state.json`
+fn path() -> Option<PathBuf> {
+    Some(state_dir()?.join("state.json"))
+}
+
+/// `$XDG_STATE_HOME/healthy-monitor`, or `~/.local/state/healthy-monitor` when unset
+pub fn state_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state")))?;
+
+    Some(base.join("healthy-monitor"))
+}
@@ -0,0 +1,104 @@
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::goals::{self, Streak};
+use crate::light;
+use crate::schedule::Period;
+use crate::state::{Output, State};
+
+/// Snapshot of the most recently applied settings
+#[derive(Debug, Clone, Serialize)]
+pub struct Status {
+    pub period: Option<Period>,
+    pub brightness: Option<f64>,
+    pub color_temp: Option<f64>,
+    /// Estimated blue light relative to a neutral screen at full brightness [0.0..1.0]
+    pub blue_light: Option<f64>,
+    /// Estimated melanopic equivalent daylight illuminance at the eye (lux)
+    pub melanopic_edi: Option<f64>,
+    pub darkroom: bool,
+    /// Another application held the webcam at the last update
+    pub camera_busy: bool,
+    /// Trust in the remembered webcam reading used meanwhile [0.0..1.0]
+    pub ambient_confidence: Option<f64>,
+    /// Progress on the daily goal, when one is configured
+    pub goal_streak: Option<Streak>,
+    /// How each output fared at the last update
+    pub outputs: Vec<Output>,
+}
+
+impl Status {
+    pub fn from_state(state: &State, config: &Config) -> Self {
+        let screen_lux = config.screen_lux.unwrap_or(light::DEFAULT_SCREEN_LUX);
+        let applied = state.brightness.zip(state.color_temp);
+
+        let (blue_light, melanopic_edi) = if state.darkroom {
+            (Some(0.0), Some(0.0))
+        } else {
+            (
+                applied.map(|(brightness, temp)| light::blue_light(brightness, temp)),
+                applied.map(|(brightness, temp)| light::melanopic_edi(brightness, temp, screen_lux)),
+            )
+        };
+
+        Status {
+            period: state.period,
+            brightness: state.brightness,
+            color_temp: state.color_temp,
+            blue_light,
+            melanopic_edi,
+            darkroom: state.darkroom,
+            camera_busy: state.camera_busy,
+            ambient_confidence: state.ambient_confidence,
+            outputs: state.outputs.clone(),
+            goal_streak: config.goals.as_ref().and_then(|goals_config| match goals::streak(goals_config) {
+                Ok(streak) => Some(streak),
+                Err(e) => {
+                    eprintln!("Failed to read goals: {}", e);
+                    None
+                }
+            }),
+        }
+    }
+}
+
+/// Prints the status as text, or as JSON for status bars and scripts
+pub fn print(json: bool, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let status = Status::from_state(&State::load(), config);
+
+    if json {
+        println!("{}", serde_json::to_string(&status)?);
+        return Ok(());
+    }
+
+    let unknown = || "unknown".to_string();
+    println!("Period:      {}", status.period.map_or_else(unknown, |p| p.name().to_string()));
+    println!("Brightness:  {}", status.brightness.map_or_else(unknown, |b| format!("{:.2}", b)));
+    println!("Temperature: {}", status.color_temp.map_or_else(unknown, |t| format!("{:.0}K", t)));
+    println!("Blue light:  {}", status.blue_light.map_or_else(unknown, |b| format!("{:.0}%", b * 100.0)));
+    println!("Melanopic:   {}", status.melanopic_edi.map_or_else(unknown, |m| format!("{:.0} lux EDI", m)));
+    if let Some(streak) = status.goal_streak {
+        println!("Goal streak: {}", goals::describe(streak));
+    }
+    for output in &status.outputs {
+        if let Some(error) = &output.error {
+            println!("{} not set: {}", output.name, error);
+        } else if output.powered_off {
+            println!("{} is powered off, left alone", output.name);
+        }
+    }
+    if status.darkroom {
+        println!("Darkroom mode is on");
+    }
+    if status.camera_busy {
+        match status.ambient_confidence {
+            Some(confidence) => println!(
+                "Webcam in use by another application, using the last reading ({:.0}% confidence)",
+                confidence * 100.0
+            ),
+            None => println!("Webcam in use by another application"),
+        }
+    }
+
+    Ok(())
+}
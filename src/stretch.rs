@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::notify::{self, Urgency};
+
+/// `[stretch]` config section enabling stand-up reminders in daemon mode
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StretchConfig {
+    /// Minutes of continuous activity before a reminder
+    pub interval_minutes: f64,
+    /// Minutes away from the desk that end the sitting stretch
+    pub reset_after_minutes: f64,
+}
+
+impl Default for StretchConfig {
+    fn default() -> Self {
+        StretchConfig { interval_minutes: 50.0, reset_after_minutes: 5.0 }
+    }
+}
+
+impl StretchConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs_f64(self.interval_minutes.max(0.0) * 60.0)
+    }
+
+    pub fn reset_after(&self) -> Duration {
+        Duration::from_secs_f64(self.reset_after_minutes.max(0.0) * 60.0)
+    }
+}
+
+/// Posts the stand-up reminder
+pub fn remind(config: &StretchConfig) {
+    let body = format!(
+        "You've been sitting for {:.0} minutes, stand up and stretch",
+        config.interval_minutes
+    );
+    notify::send("Time to move", &body, Urgency::Normal);
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// `[sunrise]` config section enabling the artificial dawn after night ends
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SunriseConfig {
+    /// Minutes over which to ramp up from the night values
+    pub duration_minutes: f64,
+}
+
+impl Default for SunriseConfig {
+    fn default() -> Self {
+        SunriseConfig { duration_minutes: 30.0 }
+    }
+}
+
+/// Progress of the dawn ramp at `hour`, or `None` outside the morning window
+pub fn progress(config: &SunriseConfig, night_end: f64, hour: f64) -> Option<f64> {
+    let duration_hours = config.duration_minutes / 60.0;
+    let since_night = (hour - night_end).rem_euclid(24.0);
+
+    (since_night < duration_hours).then(|| since_night / duration_hours)
+}
+
+/// Eases from the night value to the daytime value, starting slowly
+pub fn ramp(progress: f64, night: f64, day: f64) -> f64 {
+    let eased = progress * progress;
+    night + (day - night) * eased
+}
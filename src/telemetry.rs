@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+/// `[telemetry]` config section: OTLP export of traces and metrics (needs the `otel` feature)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    /// OTLP gRPC endpoint of the collector
+    pub endpoint: String,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        TelemetryConfig { endpoint: "http://localhost:4317".into() }
+    }
+}
+
+/// Installs the OTLP exporters as the global tracer and meter providers. Until this is
+/// called, spans and measurements go to the no-op default providers.
+#[cfg(feature = "otel")]
+pub fn init(config: &TelemetryConfig) -> Result<(), Box<dyn std::error::Error>> {
+    use opentelemetry::{global, KeyValue};
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::{runtime, trace, Resource};
+
+    let resource = Resource::new(vec![KeyValue::new("service.name", "healthy-monitor")]);
+
+    // Also becomes the global tracer provider
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(&config.endpoint))
+        .with_trace_config(trace::config().with_resource(resource.clone()))
+        .install_batch(runtime::Tokio)?;
+
+    let meter_provider = opentelemetry_otlp::new_pipeline()
+        .metrics(runtime::Tokio)
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(&config.endpoint))
+        .with_resource(resource)
+        .build()?;
+    global::set_meter_provider(meter_provider);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "otel"))]
+pub fn init(_config: &TelemetryConfig) -> Result<(), Box<dyn std::error::Error>> {
+    Err("healthy-monitor was built without the `otel` feature".into())
+}
+
+/// Flushes spans and measurements still waiting to be exported
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+/// The `update` span of one update cycle, parent of its sense → compute → apply spans.
+/// Without the `otel` feature nothing is recorded.
+pub struct Trace {
+    #[cfg(feature = "otel")]
+    tracer: opentelemetry::global::BoxedTracer,
+    #[cfg(feature = "otel")]
+    update: opentelemetry::Context,
+}
+
+impl Trace {
+    pub fn update() -> Self {
+        #[cfg(feature = "otel")]
+        {
+            use opentelemetry::trace::{TraceContextExt, Tracer};
+
+            let tracer = opentelemetry::global::tracer("healthy-monitor");
+            let update = opentelemetry::Context::current_with_span(tracer.start("update"));
+            Trace { tracer, update }
+        }
+        #[cfg(not(feature = "otel"))]
+        Trace {}
+    }
+
+    /// Starts a child span of the update, ended with `Span::end`
+    pub fn span(&self, name: &'static str) -> Span {
+        #[cfg(feature = "otel")]
+        {
+            use opentelemetry::trace::Tracer;
+
+            Span { span: self.tracer.start_with_context(name, &self.update) }
+        }
+        #[cfg(not(feature = "otel"))]
+        {
+            let _ = name;
+            Span {}
+        }
+    }
+}
+
+/// One step of an update cycle
+pub struct Span {
+    #[cfg(feature = "otel")]
+    span: opentelemetry::global::BoxedSpan,
+}
+
+impl Span {
+    #[cfg(feature = "otel")]
+    pub fn set(&mut self, key: &'static str, value: impl Into<opentelemetry::Value>) {
+        use opentelemetry::trace::Span as _;
+
+        self.span.set_attribute(opentelemetry::KeyValue::new(key, value));
+    }
+
+    #[cfg(not(feature = "otel"))]
+    pub fn set<V>(&mut self, _key: &'static str, _value: V) {}
+
+    pub fn end(&mut self) {
+        #[cfg(feature = "otel")]
+        {
+            use opentelemetry::trace::Span as _;
+
+            self.span.end();
+        }
+    }
+}
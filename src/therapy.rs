@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::time::{sleep, Instant};
+
+use crate::config::Config;
+use crate::daemon::Command;
+use crate::ipc;
+use crate::notify::{self, Urgency};
+use crate::state::State;
+use crate::Args;
+
+/// Neutral white; higher temperatures only cut red and green, giving less light overall
+const THERAPY_TEMP: f64 = 6500.0;
+/// How often the countdown notification is refreshed
+const COUNTDOWN_STEP: Duration = Duration::from_secs(60);
+/// Tag that makes each countdown notification replace the previous one
+const COUNTDOWN_TAG: &str = "healthy-monitor-light-therapy";
+
+/// Runs the screen at full brightness and neutral white for `duration`, on `monitor` only
+/// when given, then returns to automatic control. Ctrl-C ends the session early. A running
+/// daemon holds off for the session, as it does for values set by hand.
+pub async fn run(duration: Duration, monitor: Option<&str>, args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let mut session_args = args.clone();
+    if let Some(monitor) = monitor {
+        session_args.monitors = Some(vec![monitor.to_string()]);
+    }
+
+    hold(Some(Utc::now().timestamp() + duration.as_secs() as i64))?;
+    if let Err(e) = crate::set_monitor_brightness(1.0, THERAPY_TEMP, &session_args, config) {
+        hold(None)?;
+        return Err(e);
+    }
+    println!("Light therapy for {} minutes, Ctrl-C to stop early", duration.as_secs() / 60);
+
+    tokio::select! {
+        _ = countdown(duration) => {
+            notify::send_tagged("Light therapy done", "Back to automatic settings", Urgency::Normal, COUNTDOWN_TAG);
+        }
+        _ = tokio::signal::ctrl_c() => {}
+    }
+
+    hold(None)?;
+    // The daemon applies automatic settings on its own; otherwise measure once here
+    if ipc::send(&[Command::Refresh]).await.unwrap_or(false) {
+        return Ok(());
+    }
+    crate::run(args, config).await
+}
+
+/// Sets or clears the manual hold that keeps automatic updates off the screen
+fn hold(until: Option<i64>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = State::load();
+    state.manual_until = until;
+    state.save()
+}
+
+async fn countdown(duration: Duration) {
+    let end = Instant::now() + duration;
+    while Instant::now() < end {
+        let left = end - Instant::now();
+        let body = format!("{} minutes left, sit facing the screen", (left.as_secs() + 59) / 60);
+        notify::send_tagged("Light therapy", &body, Urgency::Low, COUNTDOWN_TAG);
+        sleep(COUNTDOWN_STEP.min(left)).await;
+    }
+}
@@ -0,0 +1,205 @@
+use crate::config::Config;
+use crate::Args;
+
+/// Shows a StatusNotifierItem tray icon until interrupted
+#[cfg(feature = "tray")]
+pub async fn run(args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    icon::run(args, config).await
+}
+
+#[cfg(not(feature = "tray"))]
+pub async fn run(_args: &Args, _config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    Err("healthy-monitor was built without the `tray` feature".into())
+}
+
+#[cfg(feature = "tray")]
+mod icon {
+    use std::time::Duration;
+
+    use ksni::menu::{MenuItem, RadioGroup, RadioItem, StandardItem, SubMenu};
+    use ksni::{ToolTip, TrayService};
+    use tokio::sync::mpsc::{self, UnboundedSender};
+    use tokio::time::sleep;
+
+    use crate::config::Config;
+    use crate::ipc::Subscription;
+    use crate::state::State;
+    use crate::status::Status;
+    use crate::{Args, Toggle};
+
+    /// How often the icon is refreshed when no daemon announces changes
+    const REFRESH: Duration = Duration::from_secs(5);
+    /// Length of a pause from the menu
+    const PAUSE: Duration = Duration::from_secs(60 * 60);
+    /// Brightness choices offered in the menu (percent)
+    const BRIGHTNESS_STEPS: [u32; 10] = [10, 20, 30, 40, 50, 60, 70, 80, 90, 100];
+
+    /// Menu choices, carried out by the main loop where the config is at hand
+    #[derive(Debug)]
+    enum Action {
+        Pause,
+        Resume,
+        Profile(String),
+        Brightness(f64),
+        Dashboard,
+    }
+
+    struct Tray {
+        status: Status,
+        state: State,
+        /// "auto" followed by the configured profiles
+        profiles: Vec<String>,
+        actions: UnboundedSender<Action>,
+    }
+
+    impl Tray {
+        fn send(&self, action: Action) {
+            let _ = self.actions.send(action);
+        }
+    }
+
+    impl ksni::Tray for Tray {
+        fn id(&self) -> String {
+            "healthy-monitor".into()
+        }
+
+        fn title(&self) -> String {
+            "healthy-monitor".into()
+        }
+
+        fn icon_name(&self) -> String {
+            if self.state.paused {
+                "media-playback-pause"
+            } else if self.status.period.map_or(false, |p| p != crate::schedule::Period::Daytime) {
+                "weather-clear-night"
+            } else {
+                "weather-clear"
+            }
+            .into()
+        }
+
+        fn tool_tip(&self) -> ToolTip {
+            let mode = if self.state.paused {
+                "paused"
+            } else {
+                self.state.profile.as_deref().unwrap_or_else(|| self.status.period.map_or("automatic", |p| p.name()))
+            };
+            let settings = match (self.status.brightness, self.status.color_temp) {
+                (Some(brightness), Some(color_temp)) => format!("{:.0}% at {:.0}K", brightness * 100.0, color_temp),
+                _ => "Nothing applied yet".into(),
+            };
+            ToolTip { title: format!("healthy-monitor: {}", mode), description: settings, ..Default::default() }
+        }
+
+        fn menu(&self) -> Vec<MenuItem<Self>> {
+            let pause = if self.state.paused {
+                StandardItem { label: "Resume".into(), activate: Box::new(|t: &mut Self| t.send(Action::Resume)), ..Default::default() }
+            } else {
+                StandardItem {
+                    label: "Pause for 1 hour".into(),
+                    activate: Box::new(|t: &mut Self| t.send(Action::Pause)),
+                    ..Default::default()
+                }
+            };
+
+            let active = self.state.profile.as_deref().unwrap_or("auto");
+            let profiles = RadioGroup {
+                selected: self.profiles.iter().position(|p| p == active).unwrap_or(0),
+                select: Box::new(|t: &mut Self, i| {
+                    let name = t.profiles[i].clone();
+                    t.send(Action::Profile(name));
+                }),
+                options: self.profiles.iter().map(|p| RadioItem { label: p.clone(), ..Default::default() }).collect(),
+            };
+
+            let current = self.status.brightness.map_or(100, |b| (b * 10.0).round() as u32 * 10);
+            let brightness = RadioGroup {
+                selected: BRIGHTNESS_STEPS.iter().position(|&s| s == current).unwrap_or(BRIGHTNESS_STEPS.len() - 1),
+                select: Box::new(|t: &mut Self, i| t.send(Action::Brightness(BRIGHTNESS_STEPS[i] as f64 / 100.0))),
+                options: BRIGHTNESS_STEPS
+                    .iter()
+                    .map(|s| RadioItem { label: format!("{}%", s), ..Default::default() })
+                    .collect(),
+            };
+
+            vec![
+                pause.into(),
+                SubMenu { label: "Profile".into(), submenu: vec![profiles.into()], ..Default::default() }.into(),
+                SubMenu { label: "Brightness".into(), submenu: vec![brightness.into()], ..Default::default() }.into(),
+                MenuItem::Separator,
+                StandardItem {
+                    label: "Open dashboard".into(),
+                    activate: Box::new(|t: &mut Self| t.send(Action::Dashboard)),
+                    ..Default::default()
+                }
+                .into(),
+            ]
+        }
+    }
+
+    pub async fn run(args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+        let mut profiles: Vec<String> = config.profile.keys().cloned().collect();
+        profiles.sort();
+        profiles.insert(0, "auto".into());
+
+        let (actions, mut requested) = mpsc::unbounded_channel();
+        let state = State::load();
+        let tray = Tray { status: Status::from_state(&state, config), state, profiles, actions };
+        let service = TrayService::new(tray);
+        let handle = service.handle();
+        service.spawn();
+
+        let mut subscription = Subscription::connect().await;
+        loop {
+            tokio::select! {
+                Some(action) = requested.recv() => {
+                    if let Err(e) = carry_out(&action, args, config).await {
+                        eprintln!("{:?} failed: {}", action, e);
+                    }
+                }
+                alive = async { subscription.as_mut().unwrap().changed().await }, if subscription.is_some() => {
+                    if !alive {
+                        subscription = None;
+                    }
+                }
+                _ = sleep(REFRESH) => {
+                    if subscription.is_none() {
+                        subscription = Subscription::connect().await;
+                    }
+                }
+            }
+
+            let state = State::load();
+            let status = Status::from_state(&state, config);
+            handle.update(|tray: &mut Tray| {
+                tray.state = state;
+                tray.status = status;
+            });
+        }
+    }
+
+    async fn carry_out(action: &Action, args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+        match action {
+            Action::Pause => crate::pause(Toggle::On, Some(PAUSE), args, config).await,
+            Action::Resume => crate::pause(Toggle::Off, None, args, config).await,
+            Action::Profile(name) => crate::switch_profile(name, args, config).await,
+            Action::Brightness(brightness) => crate::set(Some(*brightness), None, args, config).await,
+            Action::Dashboard => open_dashboard(config),
+        }
+    }
+
+    /// The web dashboard when the daemon serves one, the terminal dashboard otherwise
+    fn open_dashboard(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+        match &config.http {
+            Some(http) => {
+                let query = http.token.as_ref().map_or_else(String::new, |token| format!("?token={}", token));
+                std::process::Command::new("xdg-open").arg(format!("http://{}/{}", http.listen, query)).spawn()?;
+            }
+            None => {
+                let terminal = std::env::var("TERMINAL").unwrap_or_else(|_| "x-terminal-emulator".into());
+                std::process::Command::new(terminal).arg("-e").arg(std::env::current_exe()?).arg("tui").spawn()?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,209 @@
+use std::time::Duration;
+
+use chrono::{Local, Utc};
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph, Sparkline};
+use ratatui::{Frame, Terminal};
+
+use crate::config::Config;
+use crate::history::{self, Cycle};
+use crate::schedule::{self, TimeOfDay};
+use crate::state::State;
+use crate::{bedtime, Args};
+
+/// How far back the sparklines reach
+const GRAPH_HOURS: i64 = 6;
+/// How often the dashboard is redrawn when no key is pressed
+const REFRESH: Duration = Duration::from_secs(1);
+
+/// Everything shown on one frame
+struct Snapshot {
+    state: State,
+    monitors: Vec<(String, f64)>,
+    cycles: Vec<Cycle>,
+    next_change: String,
+    message: String,
+}
+
+impl Snapshot {
+    fn take(args: &Args, config: &Config, message: String) -> Self {
+        let state = State::load();
+        let brightness = state.brightness.unwrap_or(1.0);
+
+        let monitors = match &args.monitors {
+            Some(m) => m.clone(),
+            None => crate::detect_monitors().unwrap_or_default(),
+        };
+        let monitors = monitors
+            .into_iter()
+            .map(|name| {
+                let cap = config.monitor(&name).max_brightness.unwrap_or(args.max_brightness);
+                (name, brightness.min(cap))
+            })
+            .collect();
+
+        let now = Utc::now().timestamp();
+        let cycles = history::cycles(now - GRAPH_HOURS * 3600, now).unwrap_or_default();
+
+        let local = Local::now();
+        let times = args.day_times(config, local);
+        let (hour, period) = schedule::next_change(schedule::hour_of(local), &times);
+        let next_change = format!("{} at {}", period.name(), TimeOfDay(hour));
+
+        Snapshot { state, monitors, cycles, next_change, message }
+    }
+}
+
+/// Raw mode and the alternate screen, left again when dropped so an error or a panic
+/// doesn't leave the terminal unusable
+struct RawMode;
+
+impl RawMode {
+    fn enter() -> std::io::Result<Self> {
+        enable_raw_mode()?;
+        let guard = RawMode;
+        execute!(std::io::stdout(), EnterAlternateScreen)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(std::io::stdout(), LeaveAlternateScreen);
+    }
+}
+
+/// Shows a live dashboard until `q` or Esc is pressed
+pub async fn run(args: &Args, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let _raw_mode = RawMode::enter()?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(std::io::stdout()))?;
+
+    dashboard(&mut terminal, args, config).await
+}
+
+/// The next key event within `REFRESH`, waited for off the async runtime
+async fn next_event() -> Result<Option<Event>, Box<dyn std::error::Error>> {
+    let event = tokio::task::spawn_blocking(|| -> std::io::Result<Option<Event>> {
+        if event::poll(REFRESH)? {
+            event::read().map(Some)
+        } else {
+            Ok(None)
+        }
+    })
+    .await??;
+    Ok(event)
+}
+
+async fn dashboard(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    args: &Args,
+    config: &Config,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut message = String::new();
+
+    loop {
+        let snapshot = Snapshot::take(args, config, message.clone());
+        terminal.draw(|frame| draw(frame, &snapshot))?;
+
+        let Some(Event::Key(key)) = next_event().await? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('p') => {
+                let mut state = State::load();
+                state.paused = !state.paused;
+                state.save()?;
+                message = if state.paused { "Paused".into() } else { "Resumed".into() };
+                if !state.paused {
+                    if let Err(e) = crate::run(args, config).await {
+                        message = format!("Update failed: {}", e);
+                    }
+                }
+            }
+            KeyCode::Char('o') => {
+                let mut state = State::load();
+                let until = bedtime::override_tonight(config, args.transition_hours, &mut state);
+                state.save()?;
+                message = format!("Bedtime enforcement lifted until {}", until);
+            }
+            KeyCode::Char('r') => {
+                message = match crate::run(args, config).await {
+                    Ok(()) => "Updated".into(),
+                    Err(e) => format!("Update failed: {}", e),
+                };
+            }
+            _ => {}
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, snapshot: &Snapshot) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(snapshot.monitors.len() as u16 + 5),
+            Constraint::Length(6),
+            Constraint::Length(6),
+            Constraint::Min(1),
+        ])
+        .split(frame.size());
+
+    let state = &snapshot.state;
+    let mut lines = vec![
+        Line::from(format!(
+            "Temperature: {}",
+            state.color_temp.map_or("-".to_string(), |t| format!("{:.0}K", t))
+        )),
+        Line::from(format!("Next change: {}", snapshot.next_change)),
+        Line::from(format!(
+            "Mode: {}",
+            if state.paused {
+                "paused"
+            } else if state.darkroom {
+                "darkroom"
+            } else {
+                state.profile.as_deref().unwrap_or("automatic")
+            }
+        )),
+    ];
+    for (name, brightness) in &snapshot.monitors {
+        lines.push(Line::from(format!("{}: brightness {:.2}", name, brightness)));
+    }
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("healthy-monitor")),
+        rows[0],
+    );
+
+    let percent = |value: f64| (value.clamp(0.0, 1.0) * 100.0).round() as u64;
+    let ambient: Vec<u64> = snapshot.cycles.iter().filter_map(|c| c.fused).map(percent).collect();
+    let brightness: Vec<u64> = snapshot.cycles.iter().map(|c| percent(c.brightness)).collect();
+
+    frame.render_widget(
+        Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title(format!("Ambient, last {}h", GRAPH_HOURS)))
+            .data(&ambient)
+            .max(100),
+        rows[1],
+    );
+    frame.render_widget(
+        Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title(format!("Brightness, last {}h", GRAPH_HOURS)))
+            .data(&brightness)
+            .max(100),
+        rows[2],
+    );
+
+    let help = format!("[p] pause/resume  [o] override bedtime tonight  [r] update now  [q] quit   {}", snapshot.message);
+    frame.render_widget(Paragraph::new(help), rows[3]);
+}
@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::Aes128;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+use crate::roomlight::{Mapping, RoomLight};
+
+/// TCP port of the Tuya local API
+const PORT: u16 = 6668;
+/// Command number of a data point update
+const CONTROL: u32 = 7;
+const PREFIX: u32 = 0x0000_55aa;
+const SUFFIX: u32 = 0x0000_aa55;
+/// How long to wait for the bulb to accept the connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A `[[room_lights.tuya]]` bulb, driven over the Tuya local API (protocol 3.3)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TuyaConfig {
+    /// IP address of the bulb
+    pub address: String,
+    pub device_id: String,
+    /// 16 character key of the bulb, e.g. from `tinytuya wizard`
+    pub local_key: String,
+    /// Data point of the brightness (10 to 1000)
+    pub brightness_dp: u32,
+    /// Data point of the color temperature (0 warmest to 1000 coolest)
+    pub temp_dp: u32,
+    /// Color temperature range the bulb supports; the screen's is clamped into it
+    pub min_kelvin: f64,
+    pub max_kelvin: f64,
+    /// Added to the screen temperature, e.g. -500 for a room a little warmer than the screen
+    pub temp_offset: f64,
+    /// Bulb brightness at full room brightness [0.0..1.0]
+    pub max_brightness: f64,
+}
+
+impl Default for TuyaConfig {
+    fn default() -> Self {
+        let mapping = Mapping::default();
+        TuyaConfig {
+            address: String::new(),
+            device_id: String::new(),
+            local_key: String::new(),
+            brightness_dp: 22,
+            temp_dp: 23,
+            min_kelvin: 2700.0,
+            max_kelvin: mapping.max_kelvin,
+            temp_offset: mapping.temp_offset,
+            max_brightness: mapping.max_brightness,
+        }
+    }
+}
+
+impl RoomLight for TuyaConfig {
+    fn describe(&self) -> String {
+        format!("Tuya bulb {}", self.address)
+    }
+
+    fn mapping(&self) -> Mapping {
+        Mapping {
+            min_kelvin: self.min_kelvin,
+            max_kelvin: self.max_kelvin,
+            temp_offset: self.temp_offset,
+            max_brightness: self.max_brightness,
+        }
+    }
+
+    // Only the brightness and temperature points are written, never the switch
+    async fn set(&self, kelvin: f64, brightness: f64, _transition: Duration) -> Result<(), Box<dyn std::error::Error>> {
+        let range = (self.max_kelvin - self.min_kelvin).max(1.0);
+        let temp = ((kelvin - self.min_kelvin) / range * 1000.0).round().clamp(0.0, 1000.0);
+        let brightness = (brightness * 1000.0).round().clamp(10.0, 1000.0);
+
+        let mut dps = serde_json::Map::new();
+        dps.insert(self.brightness_dp.to_string(), json!(brightness as u32));
+        dps.insert(self.temp_dp.to_string(), json!(temp as u32));
+        let payload = json!({
+            "devId": self.device_id,
+            "uid": self.device_id,
+            "t": Utc::now().timestamp().to_string(),
+            "dps": dps,
+        });
+        let message = control_message(self.local_key.as_bytes(), payload.to_string().as_bytes())?;
+
+        let mut stream = timeout(CONNECT_TIMEOUT, TcpStream::connect((self.address.as_str(), PORT))).await??;
+        stream.write_all(&message).await?;
+        Ok(())
+    }
+}
+
+/// A CONTROL frame: header, version marker, AES-128-ECB encrypted JSON, CRC and suffix
+fn control_message(key: &[u8], json: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let cipher = Aes128::new_from_slice(key).map_err(|_| "The Tuya local key must be 16 characters")?;
+
+    // PKCS#7 padding, then each block on its own
+    let padding = 16 - json.len() % 16;
+    let mut encrypted = json.to_vec();
+    encrypted.resize(json.len() + padding, padding as u8);
+    for block in encrypted.chunks_exact_mut(16) {
+        cipher.encrypt_block(block.into());
+    }
+
+    let mut payload = b"3.3".to_vec();
+    payload.extend_from_slice(&[0; 12]);
+    payload.extend_from_slice(&encrypted);
+
+    let mut message = Vec::with_capacity(payload.len() + 24);
+    message.extend_from_slice(&PREFIX.to_be_bytes());
+    message.extend_from_slice(&1u32.to_be_bytes());
+    message.extend_from_slice(&CONTROL.to_be_bytes());
+    // Length counts the payload, CRC and suffix
+    message.extend_from_slice(&(payload.len() as u32 + 8).to_be_bytes());
+    message.extend_from_slice(&payload);
+    let crc = crc32fast::hash(&message);
+    message.extend_from_slice(&crc.to_be_bytes());
+    message.extend_from_slice(&SUFFIX.to_be_bytes());
+
+    Ok(message)
+}
@@ -0,0 +1,661 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::config::{self, Config};
+use crate::exit::{self, Failure};
+
+/// Parses the config at `path` (or the default location), reporting parse errors, keys that
+/// would be ignored and values out of range. Fails when anything was found.
+pub fn check(path: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(path) = path.map(Path::to_path_buf).or_else(config::default_path) else {
+        println!("No config location (neither XDG_CONFIG_HOME nor HOME is set), defaults apply");
+        return Ok(());
+    };
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            println!("{} doesn't exist, defaults apply", path.display());
+            return Ok(());
+        }
+        Err(e) => return Err(exit::fail(Failure::Config, format!("Failed to read config {}: {}", path.display(), e))),
+    };
+
+    let mut ignored = Vec::new();
+    let config: Config = serde_ignored::deserialize(toml::Deserializer::new(&contents), |key| {
+        let mut segments = Vec::new();
+        key_segments(&key, &mut segments);
+        ignored.push(segments);
+    })
+    .map_err(|e| exit::fail(Failure::Config, format!("Invalid config {}: {}", path.display(), e)))?;
+
+    let mut problems: Vec<String> = Vec::new();
+    let known: HashMap<&str, &[&str]> = SECTIONS.iter().copied().collect();
+    for segments in &ignored {
+        let key = segments.join(".");
+        let suggestion = parent_fields(&known, segments).and_then(|fields| closest(segments.last()?, fields));
+        problems.push(match suggestion {
+            Some(field) => format!("unknown key `{}`, did you mean `{}`?", key, field),
+            None => format!("unknown key `{}`", key),
+        });
+    }
+    if config.hue.is_some() {
+        problems.push("`[hue]` is now `[room_lights.hue]`, with its brightness and transition settings in `[room_lights]`".to_string());
+    }
+    problems.extend(out_of_range(&config));
+
+    for problem in &problems {
+        println!("{}: {}", path.display(), problem);
+    }
+    if !problems.is_empty() {
+        return Err(exit::fail(Failure::Config, format!("{} problems in {}", problems.len(), path.display())));
+    }
+    println!("{}: no problems found", path.display());
+    Ok(())
+}
+
+/// Values that parse but make no sense, such as a brightness above 1, as one message each
+pub fn out_of_range(config: &Config) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut brightness = vec![
+        ("min_brightness".to_string(), config.min_brightness),
+        ("night_min_brightness".to_string(), config.night_min_brightness),
+        ("max_brightness".to_string(), config.max_brightness),
+        ("darkroom_brightness".to_string(), config.darkroom_brightness),
+    ];
+    let mut temps = vec![("day_temp".to_string(), config.day_temp), ("night_temp".to_string(), config.night_temp)];
+    let mut exponents = vec![("brightness_exponent".to_string(), config.brightness_exponent)];
+    let mut steps = vec![("max_step".to_string(), config.max_step)];
+    let mut transitions = vec![
+        ("transition_hours".to_string(), config.transition_hours),
+        ("schedule.transition_hours".to_string(), config.schedule.transition_hours),
+    ];
+
+    for (name, monitor) in &config.monitor {
+        brightness.push((format!("monitor.{}.max_brightness", name), monitor.max_brightness));
+        if let Some(gamma) = monitor.gamma.filter(|gamma| gamma.iter().any(|v| !(0.0..=1.0).contains(v))) {
+            problems.push(format!("`monitor.{}.gamma` is {:?}, but each channel must be between 0 and 1", name, gamma));
+        }
+    }
+    for (name, profile) in &config.profile {
+        let key = |field: &str| format!("profile.{}.{}", name, field);
+        brightness.extend([
+            (key("min_brightness"), profile.min_brightness),
+            (key("night_min_brightness"), profile.night_min_brightness),
+            (key("max_brightness"), profile.max_brightness),
+            (key("brightness"), profile.brightness),
+        ]);
+        temps.extend([(key("day_temp"), profile.day_temp), (key("night_temp"), profile.night_temp), (key("temp"), profile.temp)]);
+        exponents.push((key("brightness_exponent"), profile.brightness_exponent));
+        steps.push((key("max_step"), profile.max_step));
+        transitions.push((key("transition_hours"), profile.transition_hours));
+    }
+    let schedule = &config.schedule;
+    let days = [
+        ("weekday", &schedule.weekday),
+        ("weekend", &schedule.weekend),
+        ("monday", &schedule.monday),
+        ("tuesday", &schedule.tuesday),
+        ("wednesday", &schedule.wednesday),
+        ("thursday", &schedule.thursday),
+        ("friday", &schedule.friday),
+        ("saturday", &schedule.saturday),
+        ("sunday", &schedule.sunday),
+    ];
+    for (day, entry) in days {
+        transitions.push((format!("schedule.{}.transition_hours", day), entry.and_then(|d| d.transition_hours)));
+    }
+    if let Some(battery) = &config.battery {
+        brightness.extend([
+            ("battery.min_brightness".to_string(), battery.min_brightness),
+            ("battery.night_min_brightness".to_string(), battery.night_min_brightness),
+            ("battery.max_brightness".to_string(), battery.max_brightness),
+            ("battery.low_max_brightness".to_string(), battery.low_max_brightness),
+        ]);
+        exponents.push(("battery.brightness_exponent".to_string(), battery.brightness_exponent));
+    }
+    brightness.extend([
+        ("power_saver.max_brightness".to_string(), config.power_saver.as_ref().map(|p| p.max_brightness)),
+        ("bedtime.brightness".to_string(), Some(config.bedtime.brightness)),
+        ("bedtime.enforce_brightness".to_string(), Some(config.bedtime.enforce_brightness)),
+        ("breaks.dim_brightness".to_string(), config.breaks.as_ref().map(|breaks| breaks.dim_brightness)),
+        ("pomodoro.break_brightness".to_string(), config.pomodoro.as_ref().map(|pomodoro| pomodoro.break_brightness)),
+    ]);
+    if let Some(room_lights) = &config.room_lights {
+        brightness.extend([
+            ("room_lights.day_brightness".to_string(), Some(room_lights.day_brightness)),
+            ("room_lights.night_brightness".to_string(), Some(room_lights.night_brightness)),
+            ("room_lights.hue.max_brightness".to_string(), room_lights.hue.as_ref().map(|hue| hue.max_brightness)),
+        ]);
+        for (i, lifx) in room_lights.lifx.iter().enumerate() {
+            brightness.push((format!("room_lights.lifx.{}.max_brightness", i), Some(lifx.max_brightness)));
+        }
+        for (i, tuya) in room_lights.tuya.iter().enumerate() {
+            brightness.push((format!("room_lights.tuya.{}.max_brightness", i), Some(tuya.max_brightness)));
+        }
+    }
+    temps.extend([
+        ("bedtime.temp".to_string(), Some(config.bedtime.temp)),
+        ("bedtime.enforce_temp".to_string(), Some(config.bedtime.enforce_temp)),
+        ("morning_boost.temp".to_string(), config.morning_boost.as_ref().map(|boost| boost.temp)),
+        ("pomodoro.break_temp".to_string(), config.pomodoro.as_ref().map(|pomodoro| pomodoro.break_temp)),
+        ("midi.min_temp".to_string(), config.midi.as_ref().map(|midi| midi.min_temp)),
+        ("midi.max_temp".to_string(), config.midi.as_ref().map(|midi| midi.max_temp)),
+    ]);
+
+    for (key, value) in brightness {
+        if let Some(value) = value.filter(|v| !(0.0..=1.0).contains(v)) {
+            problems.push(format!("`{}` is {}, but must be between 0 and 1", key, value));
+        }
+    }
+    if let (Some(min), Some(max)) = (config.min_brightness, config.max_brightness) {
+        if min > max {
+            problems.push(format!("`min_brightness` {} is above `max_brightness` {}", min, max));
+        }
+    }
+    for (key, value) in temps {
+        if let Some(value) = value.filter(|v| !(1000.0..=10000.0).contains(v)) {
+            problems.push(format!("`{}` is {}K, but must be between 1000K and 10000K", key, value));
+        }
+    }
+    for (key, value) in exponents {
+        if let Some(value) = value.filter(|v| !(v.is_finite() && *v > 0.0)) {
+            problems.push(format!("`{}` is {}, but must be above 0", key, value));
+        }
+    }
+    for (key, value) in steps {
+        if let Some(step) = value.filter(|step| !crate::valid_step(*step)) {
+            problems.push(format!("`{}` is {}, but must be above 0", key, step));
+        }
+    }
+    if let Some(bits) = config.panel_bits.filter(|bits| !(1..=16).contains(bits)) {
+        problems.push(format!("`panel_bits` is {}, but must be between 1 and 16", bits));
+    }
+    for (key, value) in transitions {
+        if let Some(hours) = value.filter(|h| *h < 0.0) {
+            problems.push(format!("`{}` is {}, but can't be negative", key, hours));
+        }
+    }
+    problems
+}
+
+fn key_segments(key: &serde_ignored::Path, segments: &mut Vec<String>) {
+    match key {
+        serde_ignored::Path::Root => {}
+        serde_ignored::Path::Seq { parent, index } => {
+            key_segments(parent, segments);
+            segments.push(index.to_string());
+        }
+        serde_ignored::Path::Map { parent, key } => {
+            key_segments(parent, segments);
+            segments.push(key.clone());
+        }
+        serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => key_segments(parent, segments),
+    }
+}
+
+/// The fields of the struct holding the last of `segments`. Struct fields are looked up by
+/// name, while map keys and list indices of the user's own choosing match `*`.
+fn parent_fields(known: &HashMap<&str, &'static [&'static str]>, segments: &[String]) -> Option<&'static [&'static str]> {
+    let (_, parents) = segments.split_last()?;
+    known.get(section(known, parents).as_str()).copied()
+}
+
+/// The `SECTIONS` path of the value at `segments`
+fn section(known: &HashMap<&str, &'static [&'static str]>, segments: &[String]) -> String {
+    let mut path = String::new();
+    for segment in segments {
+        let name = if known.contains_key(path.as_str()) { segment.as_str() } else { "*" };
+        path = join(&path, name);
+    }
+    path
+}
+
+/// The field closest to a misspelled `key`, if it's close enough to be a likely typo
+fn closest(key: &str, fields: &[&'static str]) -> Option<&'static str> {
+    fields
+        .iter()
+        .map(|field| (distance(key, field), *field))
+        .filter(|(edits, field)| *edits <= (field.len() / 3).max(2))
+        .min_by_key(|(edits, _)| *edits)
+        .map(|(_, field)| field)
+}
+
+/// Levenshtein distance
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = previous[j] + usize::from(ca != *cb);
+            current.push(substitute.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+/// Fields of a per-day schedule entry, also the base values of `[schedule]`
+const DAY_FIELDS: &[&str] = &["night_start", "night_end", "transition_hours", "bedtime", "wake"];
+
+/// Field names of every config section by dotted path, `""` being the top level and `*` standing
+/// for map keys and list entries of the user's own choosing. Only used for suggestions on unknown
+/// keys, which serde reports by itself; a test checks it against the config structs.
+const SECTIONS: &[(&str, &[&str])] = &[
+    (
+        "",
+        &[
+            "api_key", "min_brightness", "night_min_brightness", "brightness_exponent", "max_brightness",
+            "day_temp", "night_temp", "transition_hours", "max_step", "monitors", "primary_only",
+            "curve_anchors", "fade_seconds", "capture_resolution", "panel_bits", "screen_lux",
+            "bright_ambient", "history_days", "manual_hold_minutes", "darkroom_brightness", "exceptions",
+            "pause_during_playback", "dim_on_lock", "pause_during_games", "neutral_while_sharing",
+            "sharing_processes", "idle_minutes", "location", "hooks_dir", "event_hooks", "schedule",
+            "bedtime", "sunrise", "morning_boost", "breaks", "announce", "quiet", "push", "reminders",
+            "weekly_summary", "eye_exercises", "goals", "stretch", "blink", "pomodoro", "contrast_warning",
+            "daylight_nudge", "posture", "grayscale", "battery", "power_saver", "activitywatch", "calendar",
+            "screen_content", "http", "midi", "mqtt", "telemetry", "room_lights", "hotkeys", "profile",
+            "app_profiles", "monitor",
+        ],
+    ),
+    ("activitywatch", &["url", "screen_time", "publish"]),
+    ("announce", &["night", "overrides", "sensors"]),
+    (
+        "battery",
+        &["min_brightness", "night_min_brightness", "max_brightness", "brightness_exponent", "low_percent", "low_max_brightness"],
+    ),
+    (
+        "bedtime",
+        &["wind_down_minutes", "temp", "brightness", "notify", "enforce", "enforce_minutes", "enforce_brightness", "enforce_temp"],
+    ),
+    ("blink", &["after_minutes", "every_minutes", "style", "pulse_dim"]),
+    ("breaks", &["interval_minutes", "break_seconds", "dim", "dim_brightness", "reset_after_minutes"]),
+    ("calendar", &["source", "username", "password", "refresh_minutes"]),
+    ("contrast_warning", &["difference", "minutes"]),
+    ("daylight_nudge", &["start", "end", "min_sun_elevation", "dark_below", "minutes"]),
+    ("event_hooks.*", &["on", "command", "args"]),
+    ("eye_exercises", &["enabled", "with_breaks", "steps"]),
+    ("eye_exercises.steps.*", &["text", "seconds"]),
+    ("goals", &["breaks", "max_screen_hours"]),
+    ("grayscale", &["start", "end", "on_command", "off_command"]),
+    (
+        "hotkeys",
+        &["brightness_up", "brightness_down", "temp_up", "temp_down", "toggle_night", "brightness_step", "temp_step"],
+    ),
+    ("http", &["listen", "token"]),
+    ("location", &["lat", "lon"]),
+    (
+        "midi",
+        &["port", "channel", "brightness_cc", "temp_cc", "relative", "brightness_step", "temp_step", "min_temp", "max_temp"],
+    ),
+    ("monitor.*", &["gamma", "max_brightness"]),
+    ("morning_boost", &["start", "end", "months", "temp"]),
+    (
+        "mqtt",
+        &["host", "port", "username", "password", "client_id", "topic_prefix", "home_assistant", "discovery_prefix"],
+    ),
+    ("pomodoro", &["break_brightness", "break_temp"]),
+    ("posture", &["model", "max_face_width", "minutes"]),
+    ("power_saver", &["max_brightness", "interval_factor"]),
+    (
+        "profile.*",
+        &[
+            "min_brightness", "night_min_brightness", "max_brightness", "brightness_exponent", "day_temp",
+            "night_temp", "transition_hours", "max_step", "brightness", "temp", "breaks",
+        ],
+    ),
+    ("push", &["ntfy", "telegram"]),
+    ("push.telegram", &["bot_token", "chat_id"]),
+    ("quiet", &["start", "end", "dnd"]),
+    ("reminders.*", &["title", "body", "every_active_minutes", "at"]),
+    ("room_lights", &["day_brightness", "night_brightness", "transition_seconds", "hue", "lifx", "tuya"]),
+    (
+        "room_lights.hue",
+        &["bridge", "username", "lights", "min_kelvin", "max_kelvin", "temp_offset", "max_brightness"],
+    ),
+    ("room_lights.lifx.*", &["address", "min_kelvin", "max_kelvin", "temp_offset", "max_brightness"]),
+    (
+        "room_lights.tuya.*",
+        &[
+            "address", "device_id", "local_key", "brightness_dp", "temp_dp", "min_kelvin", "max_kelvin",
+            "temp_offset", "max_brightness",
+        ],
+    ),
+    (
+        "schedule",
+        &[
+            "night_start", "night_end", "transition_hours", "bedtime", "wake", "weekday", "weekend", "monday",
+            "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday",
+        ],
+    ),
+    ("schedule.weekday", DAY_FIELDS),
+    ("schedule.weekend", DAY_FIELDS),
+    ("schedule.monday", DAY_FIELDS),
+    ("schedule.tuesday", DAY_FIELDS),
+    ("schedule.wednesday", DAY_FIELDS),
+    ("schedule.thursday", DAY_FIELDS),
+    ("schedule.friday", DAY_FIELDS),
+    ("schedule.saturday", DAY_FIELDS),
+    ("schedule.sunday", DAY_FIELDS),
+    ("screen_content", &["threshold", "strength"]),
+    ("stretch", &["interval_minutes", "reset_after_minutes"]),
+    ("sunrise", &["duration_minutes"]),
+    ("telemetry", &["endpoint"]),
+    ("weekly_summary", &["day", "time"]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(key: &str) -> Vec<String> {
+        key.split('.').map(str::to_string).collect()
+    }
+
+    fn suggestion(key: &str) -> Option<&'static str> {
+        let known: HashMap<&str, &[&str]> = SECTIONS.iter().copied().collect();
+        let segments = segments(key);
+        parent_fields(&known, &segments).and_then(|fields| closest(segments.last()?, fields))
+    }
+
+    #[test]
+    fn edit_distance() {
+        assert_eq!(distance("gamma", "gamma"), 0);
+        assert_eq!(distance("gama", "gamma"), 1);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("abc", ""), 3);
+        assert_eq!(distance("é", "e"), 1);
+    }
+
+    #[test]
+    fn closest_field() {
+        let fields = ["night_start", "night_end", "transition_hours"];
+        assert_eq!(closest("night_strat", &fields), Some("night_start"));
+        assert_eq!(closest("transition_hour", &fields), Some("transition_hours"));
+        assert_eq!(closest("sunset", &fields), None);
+        assert_eq!(closest("night", &[]), None);
+    }
+
+    #[test]
+    fn suggestions_follow_sections() {
+        assert_eq!(suggestion("min_brightnes"), Some("min_brightness"));
+        assert_eq!(suggestion("monitor.DP-1.gama"), Some("gamma"));
+        assert_eq!(suggestion("room_lights.lifx.0.adress"), Some("address"));
+        assert_eq!(suggestion("schedule.saturday.bedtmie"), Some("bedtime"));
+        assert_eq!(suggestion("zzzzzzzz"), None);
+    }
+
+    /// Every section enabled and every optional value set; the per-day schedule entries are added
+    /// by `full_config`
+    const FULL_CONFIG: &str = r#"
+api_key = "key"
+min_brightness = 0.1
+night_min_brightness = 0.05
+brightness_exponent = 1.5
+max_brightness = 0.9
+day_temp = 6500.0
+night_temp = 3500.0
+transition_hours = 1.0
+max_step = 0.1
+monitors = ["DP-1"]
+primary_only = true
+curve_anchors = [[0.0, 0.1], [1.0, 1.0]]
+fade_seconds = 2.0
+capture_resolution = "smallest"
+panel_bits = 8
+screen_lux = 300.0
+bright_ambient = 0.5
+history_days = 30
+manual_hold_minutes = 60.0
+darkroom_brightness = 0.3
+exceptions = ["gimp"]
+pause_during_playback = true
+dim_on_lock = true
+pause_during_games = true
+neutral_while_sharing = true
+sharing_processes = ["zoom"]
+idle_minutes = 5.0
+hooks_dir = "/tmp/hooks"
+app_profiles = { firefox = "reading" }
+
+[location]
+lat = 50.0
+lon = 14.0
+
+[[event_hooks]]
+on = ["applied"]
+command = "/bin/true"
+args = ["--quiet"]
+
+[schedule]
+night_start = "21:00"
+night_end = "07:00"
+transition_hours = 1.0
+bedtime = "23:00"
+wake = "07:00"
+
+[bedtime]
+[sunrise]
+
+[morning_boost]
+start = "07:00"
+end = "09:00"
+months = [11, 12, 1]
+
+[breaks]
+[announce]
+
+[quiet]
+start = "22:00"
+end = "07:00"
+
+[push]
+ntfy = "topic"
+
+[push.telegram]
+bot_token = "token"
+chat_id = "chat"
+
+[[reminders]]
+title = "Water"
+body = "Drink some"
+every_active_minutes = 60.0
+at = "12:00"
+
+[weekly_summary]
+
+[eye_exercises]
+[[eye_exercises.steps]]
+text = "Look far away"
+seconds = 20.0
+
+[goals]
+breaks = 8
+max_screen_hours = 8.0
+
+[stretch]
+[blink]
+[pomodoro]
+[contrast_warning]
+[daylight_nudge]
+
+[posture]
+model = "/tmp/model.bin"
+
+[grayscale]
+start = "22:00"
+end = "07:00"
+on_command = "on"
+off_command = "off"
+
+[battery]
+min_brightness = 0.1
+night_min_brightness = 0.05
+max_brightness = 0.8
+brightness_exponent = 1.2
+low_percent = 20.0
+low_max_brightness = 0.5
+
+[power_saver]
+[activitywatch]
+
+[calendar]
+username = "user"
+password = "secret"
+
+[screen_content]
+
+[http]
+token = "secret"
+
+[midi]
+channel = 1
+brightness_cc = 7
+temp_cc = 8
+
+[mqtt]
+username = "user"
+password = "secret"
+
+[telemetry]
+
+[room_lights]
+[room_lights.hue]
+[[room_lights.lifx]]
+[[room_lights.tuya]]
+
+[hotkeys]
+
+[profile.reading]
+min_brightness = 0.2
+night_min_brightness = 0.1
+max_brightness = 0.8
+brightness_exponent = 1.0
+day_temp = 5500.0
+night_temp = 3000.0
+transition_hours = 0.5
+max_step = 0.05
+brightness = 0.6
+temp = 4500.0
+breaks = false
+
+[monitor.DP-1]
+gamma = [1.0, 0.9, 0.8]
+max_brightness = 0.9
+"#;
+
+    fn full_config() -> String {
+        let mut contents = FULL_CONFIG.to_string();
+        for day in ["weekday", "weekend", "monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"] {
+            contents.push_str(&format!(
+                "\n[schedule.{}]\nnight_start = \"21:00\"\nnight_end = \"07:00\"\ntransition_hours = 1.0\nbedtime = \"23:00\"\nwake = \"07:00\"\n",
+                day
+            ));
+        }
+        contents
+    }
+
+    /// The `SECTIONS` path and name of every field in `value`, skipping map keys and list indices
+    fn collect_fields(
+        known: &HashMap<&str, &'static [&'static str]>,
+        value: &toml::Value,
+        segments: &mut Vec<String>,
+        found: &mut Vec<(String, String)>,
+    ) {
+        let children: Vec<(String, &toml::Value)> = match value {
+            toml::Value::Table(table) => table.iter().map(|(key, value)| (key.clone(), value)).collect(),
+            toml::Value::Array(items) => items.iter().enumerate().map(|(i, item)| (i.to_string(), item)).collect(),
+            _ => return,
+        };
+        for (name, child) in children {
+            let path = section(known, segments);
+            if known.contains_key(path.as_str()) {
+                found.push((path, name.clone()));
+            }
+            segments.push(name);
+            collect_fields(known, child, segments, found);
+            segments.pop();
+        }
+    }
+
+    #[test]
+    fn sections_match_config() {
+        let config: Config = toml::from_str(&full_config()).unwrap();
+        let serialized: toml::Value = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+
+        let known: HashMap<&str, &[&str]> = SECTIONS.iter().copied().collect();
+        let mut found = Vec::new();
+        collect_fields(&known, &serialized, &mut Vec::new(), &mut found);
+
+        let missing: Vec<String> = found
+            .iter()
+            .filter(|(section, name)| !known[section.as_str()].contains(&name.as_str()))
+            .map(|(section, name)| join(section, name))
+            .collect();
+        assert!(missing.is_empty(), "config keys missing from SECTIONS: {:?}", missing);
+
+        let stale: Vec<String> = SECTIONS
+            .iter()
+            .flat_map(|(section, names)| names.iter().map(move |name| (*section, *name)))
+            .filter(|(section, name)| !found.iter().any(|(s, n)| s.as_str() == *section && n.as_str() == *name))
+            .map(|(section, name)| join(section, name))
+            .collect();
+        assert!(stale.is_empty(), "SECTIONS keys not in the config: {:?}", stale);
+    }
+
+    #[test]
+    fn ranges() {
+        let config: Config = toml::from_str(&full_config()).unwrap();
+        assert_eq!(out_of_range(&config), Vec::<String>::new());
+
+        let config: Config = toml::from_str(
+            r#"
+            [battery]
+            low_max_brightness = 1.5
+            [power_saver]
+            max_brightness = -0.1
+            [bedtime]
+            brightness = 2.0
+            [breaks]
+            dim_brightness = 1.1
+            [pomodoro]
+            break_brightness = -1.0
+            [room_lights]
+            night_brightness = 3.0
+            [[room_lights.lifx]]
+            max_brightness = 1.2
+            [profile.reading]
+            transition_hours = -1.0
+            [schedule.weekend]
+            transition_hours = -0.5
+            "#,
+        )
+        .unwrap();
+        let problems = out_of_range(&config);
+        for key in [
+            "battery.low_max_brightness",
+            "power_saver.max_brightness",
+            "bedtime.brightness",
+            "breaks.dim_brightness",
+            "pomodoro.break_brightness",
+            "room_lights.night_brightness",
+            "room_lights.lifx.0.max_brightness",
+            "profile.reading.transition_hours",
+            "schedule.weekend.transition_hours",
+        ] {
+            assert!(problems.iter().any(|p| p.starts_with(&format!("`{}`", key))), "no problem for {}: {:?}", key, problems);
+        }
+        assert_eq!(problems.len(), 9, "{:?}", problems);
+    }
+}
@@ -0,0 +1,186 @@
+use std::os::fd::OwnedFd;
+
+use crate::webcam::GrayFrame;
+
+/// Grabs one frame from a PipeWire remote handed out by a portal, after dropping the
+/// first `skip` frames. Connects to `node`, or to whatever the remote offers when `None`.
+#[cfg(feature = "portal")]
+pub fn capture_frame(fd: OwnedFd, node: Option<u32>, skip: usize) -> Result<GrayFrame, Box<dyn std::error::Error>> {
+    stream::capture_frame(fd, node, skip)
+}
+
+#[cfg(not(feature = "portal"))]
+pub fn capture_frame(_fd: OwnedFd, _node: Option<u32>, _skip: usize) -> Result<GrayFrame, Box<dyn std::error::Error>> {
+    Err("healthy-monitor was built without the `portal` feature".into())
+}
+
+#[cfg(feature = "portal")]
+mod stream {
+    use std::cell::RefCell;
+    use std::io::Cursor;
+    use std::os::fd::OwnedFd;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    use pipewire as pw;
+    use pw::properties::properties;
+    use pw::spa::param::format::{FormatProperties, MediaSubtype, MediaType};
+    use pw::spa::param::format_utils;
+    use pw::spa::param::video::{VideoFormat, VideoInfoRaw};
+    use pw::spa::param::ParamType;
+    use pw::spa::pod::serialize::PodSerializer;
+    use pw::spa::pod::{self, Pod};
+    use pw::spa::utils::{Direction, Rectangle, SpaTypes};
+    use pw::stream::{Stream, StreamFlags};
+
+    use crate::webcam::GrayFrame;
+
+    /// How long to wait for a usable frame
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    struct Capture {
+        format: Option<VideoInfoRaw>,
+        /// Frames still to be dropped while the sensor settles
+        skip: usize,
+    }
+
+    pub fn capture_frame(fd: OwnedFd, node: Option<u32>, skip: usize) -> Result<GrayFrame, Box<dyn std::error::Error>> {
+        pw::init();
+        let mainloop = pw::main_loop::MainLoop::new(None)?;
+        let context = pw::context::Context::new(&mainloop)?;
+        let core = context.connect_fd(fd, None)?;
+
+        let stream = Stream::new(
+            &core,
+            "healthy-monitor",
+            properties! {
+                *pw::keys::MEDIA_TYPE => "Video",
+                *pw::keys::MEDIA_CATEGORY => "Capture",
+            },
+        )?;
+
+        let frame: Rc<RefCell<Option<GrayFrame>>> = Rc::new(RefCell::new(None));
+        let _listener = stream
+            .add_local_listener_with_user_data(Capture { format: None, skip })
+            .param_changed(|_, capture, id, param| {
+                let Some(param) = param else { return };
+                if id != ParamType::Format.as_raw() {
+                    return;
+                }
+                if let Ok((MediaType::Video, MediaSubtype::Raw)) = format_utils::parse_format(param) {
+                    let mut info = VideoInfoRaw::new();
+                    if info.parse(param).is_ok() {
+                        capture.format = Some(info);
+                    }
+                }
+            })
+            .process({
+                let frame = frame.clone();
+                let mainloop = mainloop.clone();
+                move |stream, capture| {
+                    let Some(mut buffer) = stream.dequeue_buffer() else { return };
+                    let Some((format, size)) = capture.format.as_ref().map(|info| (info.format(), info.size())) else { return };
+                    if capture.skip > 0 {
+                        capture.skip -= 1;
+                        return;
+                    }
+
+                    let data = &mut buffer.datas_mut()[0];
+                    let (offset, size, stride) = {
+                        let chunk = data.chunk();
+                        (chunk.offset() as usize, chunk.size() as usize, chunk.stride() as usize)
+                    };
+                    let Some(bytes) = data.data() else { return };
+                    let Some(bytes) = bytes.get(offset..offset + size) else { return };
+
+                    let Rectangle { width, height } = size;
+                    if let Some(pixels) = luma(format, bytes, width as usize, height as usize, stride) {
+                        *frame.borrow_mut() = Some(GrayFrame { pixels, width, height });
+                        mainloop.quit();
+                    }
+                }
+            })
+            .register()?;
+
+        let format = pod::object!(
+            SpaTypes::ObjectParamFormat,
+            ParamType::EnumFormat,
+            pod::property!(FormatProperties::MediaType, Id, MediaType::Video),
+            pod::property!(FormatProperties::MediaSubtype, Id, MediaSubtype::Raw),
+            pod::property!(
+                FormatProperties::VideoFormat,
+                Choice,
+                Enum,
+                Id,
+                VideoFormat::YUY2,
+                VideoFormat::YUY2,
+                VideoFormat::RGB,
+                VideoFormat::RGBx,
+                VideoFormat::BGRx
+            ),
+            pod::property!(
+                FormatProperties::VideoSize,
+                Choice,
+                Range,
+                Rectangle,
+                Rectangle { width: 640, height: 480 },
+                Rectangle { width: 1, height: 1 },
+                Rectangle { width: 8192, height: 8192 }
+            ),
+        );
+        let format = PodSerializer::serialize(Cursor::new(Vec::new()), &pod::Value::Object(format))
+            .map_err(|_| "Couldn't describe the video formats")?
+            .0
+            .into_inner();
+        let mut params = [Pod::from_bytes(&format).ok_or("Couldn't describe the video formats")?];
+
+        stream.connect(Direction::Input, node, StreamFlags::AUTOCONNECT | StreamFlags::MAP_BUFFERS, &mut params)?;
+
+        let timer = mainloop.loop_().add_timer({
+            let mainloop = mainloop.clone();
+            move |_| mainloop.quit()
+        });
+        timer.update_timers(Some(TIMEOUT), None).into_result()?;
+
+        mainloop.run();
+        let _ = stream.disconnect();
+
+        let frame = frame.borrow_mut().take();
+        frame.ok_or_else(|| "No video frame arrived from PipeWire".into())
+    }
+
+    /// 8-bit luma of a frame in one of the negotiated formats
+    fn luma(format: VideoFormat, bytes: &[u8], width: usize, height: usize, stride: usize) -> Option<Vec<u8>> {
+        let bytes_per_pixel = match format {
+            VideoFormat::YUY2 => 2,
+            VideoFormat::RGB => 3,
+            VideoFormat::RGBx | VideoFormat::BGRx => 4,
+            _ => return None,
+        };
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let stride = if stride == 0 { width * bytes_per_pixel } else { stride };
+        if bytes.len() < stride * (height - 1) + width * bytes_per_pixel {
+            return None;
+        }
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for row in bytes.chunks(stride).take(height) {
+            let row = &row[..width * bytes_per_pixel];
+            match format {
+                // Y of every pixel, in limited range
+                VideoFormat::YUY2 => pixels.extend(row.iter().step_by(2).map(|&y| {
+                    ((y.saturating_sub(16) as f64) * 255.0 / 219.0).round().min(255.0) as u8
+                })),
+                VideoFormat::BGRx => pixels.extend(row.chunks_exact(4).map(|p| rec709(p[2], p[1], p[0]))),
+                _ => pixels.extend(row.chunks_exact(bytes_per_pixel).map(|p| rec709(p[0], p[1], p[2]))),
+            }
+        }
+        Some(pixels)
+    }
+
+    fn rec709(r: u8, g: u8, b: u8) -> u8 {
+        (0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64).round() as u8
+    }
+}
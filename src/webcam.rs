@@ -0,0 +1,245 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::problem::Problem;
+use crate::{portal, video};
+use nokhwa::{
+    Buffer,
+    Camera,
+    utils::{CameraFormat, CameraIndex, FrameFormat, RequestedFormat, RequestedFormatType},
+    pixel_format::{LumaFormat, RgbFormat}
+};
+use zune_jpeg::zune_core::colorspace::ColorSpace;
+use zune_jpeg::zune_core::options::DecoderOptions;
+use zune_jpeg::JpegDecoder;
+
+/// Device node opened for camera index 0
+const DEVICE: &str = "/dev/video0";
+
+/// Frames dropped while the camera adjusts its exposure
+const WARMUP_FRAMES: usize = 5;
+
+/// Longest a capture may take before the camera is closed and the capture fails
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Frame size asked of the camera, `capture_resolution` in the config
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    /// The smallest mode the camera offers
+    Smallest,
+    Size(u32, u32),
+}
+
+impl Default for Resolution {
+    fn default() -> Self {
+        Resolution::Size(640, 480)
+    }
+}
+
+impl FromStr for Resolution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case("smallest") {
+            return Ok(Resolution::Smallest);
+        }
+
+        let invalid = || format!("Invalid resolution '{}', expected WIDTHxHEIGHT or \"smallest\"", s);
+        let (width, height) = s.trim().split_once('x').ok_or_else(invalid)?;
+        let width: u32 = width.parse().map_err(|_| invalid())?;
+        let height: u32 = height.parse().map_err(|_| invalid())?;
+        if width == 0 || height == 0 {
+            return Err(invalid());
+        }
+        Ok(Resolution::Size(width, height))
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resolution::Smallest => write!(f, "smallest"),
+            Resolution::Size(width, height) => write!(f, "{}x{}", width, height),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Resolution {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for Resolution {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// A single webcam capture
+pub struct Reading {
+    /// Average brightness [0.0..1.0]
+    pub ambient: f64,
+    /// Grayscale copy of the frame, only kept when requested
+    pub gray: Option<GrayFrame>,
+}
+
+/// 8-bit luma of every pixel, row by row
+pub struct GrayFrame {
+    pub pixels: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Captures an image from webcam and computes its average brightness, keeping a grayscale
+/// copy of the frame when `keep_gray` is set. Sandboxed installs go through the camera portal.
+pub async fn capture(resolution: Resolution, keep_gray: bool) -> Result<Reading, Box<dyn std::error::Error>> {
+    if portal::confined() {
+        return capture_portal(keep_gray).await;
+    }
+
+    // nokhwa's own message for this doesn't say what's wrong
+    if let Err(e) = std::fs::OpenOptions::new().read(true).write(true).open(DEVICE) {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            return Err(Problem::CameraPermission(DEVICE.into()).into());
+        }
+    }
+
+    // Opening the camera and waiting for frames blocks, so it runs off the async workers. A frame
+    // that never comes would block the thread indefinitely, so the wait for it is bounded out here
+    // and the thread is left behind; its own deadline closes the device once a frame returns.
+    let deadline = Instant::now() + TIMEOUT;
+    let capture = tokio::task::spawn_blocking(move || {
+        capture_device(resolution, keep_gray, deadline).map_err(|e| e.to_string())
+    });
+    let reading = tokio::time::timeout(TIMEOUT, capture)
+        .await
+        .map_err(|_| format!("no frame within {}s", TIMEOUT.as_secs()))???;
+    Ok(reading)
+}
+
+fn capture_device(resolution: Resolution, keep_gray: bool, deadline: Instant) -> Result<Reading, Box<dyn std::error::Error>> {
+    // Sizes are matched as closely as the camera allows; 1x1 is closest to its smallest mode
+    let (width, height) = match resolution {
+        Resolution::Smallest => (1, 1),
+        Resolution::Size(width, height) => (width, height),
+    };
+    let format = RequestedFormatType::Closest(CameraFormat::new_from(width, height, FrameFormat::MJPEG, 30));
+    let mut camera = Camera::new(CameraIndex::Index(0), RequestedFormat::new::<RgbFormat>(format))?;
+
+    camera.open_stream()?;
+    let overdue = || format!("no frame within {}s", TIMEOUT.as_secs()).into();
+
+    for _ in 0..WARMUP_FRAMES {
+        let _ = camera.frame()?;
+        if Instant::now() >= deadline {
+            camera.stop_stream()?;
+            return Err(overdue());
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    let frame = camera.frame()?;
+    camera.stop_stream()?;
+    if Instant::now() >= deadline {
+        return Err(overdue());
+    }
+
+    let (total, count, gray) = luma(&frame, keep_gray)?;
+    Ok(Reading {
+        ambient: (total as f64 / count.max(1) as f64 / 255.0).clamp(0.0, 1.0),
+        gray,
+    })
+}
+
+/// Sum and count of the 8-bit luma values of a frame, read straight from the luma plane where
+/// the format has one rather than from a decoded RGB image. Pixels are only kept for `keep_gray`.
+fn luma(frame: &Buffer, keep_gray: bool) -> Result<(u64, u64, Option<GrayFrame>), Box<dyn std::error::Error>> {
+    let resolution = frame.resolution();
+    let (width, height) = (resolution.width(), resolution.height());
+    let pixels = width as usize * height as usize;
+    let bytes = frame.buffer();
+
+    // Formats with a luma plane are summed as they're read; the rest are decoded to one first
+    let plane = match frame.source_frame_format() {
+        FrameFormat::YUYV => {
+            let luma = bytes.iter().step_by(2).take(pixels).map(|&y| full_range(y));
+            return Ok(accumulate(luma, keep_gray, width, height));
+        }
+        FrameFormat::NV12 => {
+            let luma = bytes.iter().take(pixels).map(|&y| full_range(y));
+            return Ok(accumulate(luma, keep_gray, width, height));
+        }
+        FrameFormat::GRAY => return Ok(accumulate(bytes.iter().take(pixels).copied(), keep_gray, width, height)),
+        FrameFormat::MJPEG => {
+            let options = DecoderOptions::default().jpeg_set_out_colorspace(ColorSpace::Luma);
+            JpegDecoder::new_with_options(bytes, options)
+                .decode()
+                .map_err(|e| format!("Failed to decode the frame: {:?}", e))?
+        }
+        _ => frame.decode_image::<LumaFormat>()?.into_raw(),
+    };
+
+    let total = plane.iter().map(|&y| y as u64).sum();
+    let count = plane.len() as u64;
+    Ok((total, count, keep_gray.then(|| GrayFrame { pixels: plane, width, height })))
+}
+
+fn accumulate(luma: impl Iterator<Item = u8>, keep_gray: bool, width: u32, height: u32) -> (u64, u64, Option<GrayFrame>) {
+    let mut gray = keep_gray.then(|| Vec::with_capacity(width as usize * height as usize));
+    let (mut total, mut count) = (0, 0);
+    for y in luma {
+        total += y as u64;
+        count += 1;
+        if let Some(gray) = gray.as_mut() {
+            gray.push(y);
+        }
+    }
+    (total, count, gray.map(|pixels| GrayFrame { pixels, width, height }))
+}
+
+/// Video luma (16 to 235) stretched to the full 0 to 255
+fn full_range(y: u8) -> u8 {
+    ((y.saturating_sub(16) as f64) * 255.0 / 219.0).round().min(255.0) as u8
+}
+
+/// Captures through the camera portal instead of opening the device directly,
+/// for sandboxed installs that can't see `/dev/video*`
+async fn capture_portal(keep_gray: bool) -> Result<Reading, Box<dyn std::error::Error>> {
+    let fd = portal::open_camera().await?;
+    let frame = tokio::task::spawn_blocking(move || {
+        video::capture_frame(fd, None, WARMUP_FRAMES).map_err(|e| e.to_string())
+    })
+    .await??;
+
+    let total: u64 = frame.pixels.iter().map(|&luma| luma as u64).sum();
+    let ambient = total as f64 / frame.pixels.len().max(1) as f64 / 255.0;
+
+    Ok(Reading {
+        ambient: ambient.clamp(0.0, 1.0),
+        gray: keep_gray.then_some(frame),
+    })
+}
+
+/// Whether another process currently has the webcam open (e.g. a video call).
+/// Only processes of the same user are visible, which covers the usual case.
+pub fn in_use_by_other_process() -> bool {
+    let own_pid = std::process::id().to_string();
+    let Ok(processes) = std::fs::read_dir("/proc") else {
+        return false;
+    };
+
+    processes
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name() != own_pid.as_str())
+        .filter_map(|entry| std::fs::read_dir(entry.path().join("fd")).ok())
+        .flatten()
+        .filter_map(|fd| fd.ok())
+        .any(|fd| std::fs::read_link(fd.path()).map_or(false, |target| target == Path::new(DEVICE)))
+}
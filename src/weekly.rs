@@ -0,0 +1,126 @@
+use chrono::{Datelike, Duration, Local, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::notify::{self, QuietConfig, Urgency};
+use crate::schedule::{self, ScheduleConfig, TimeOfDay};
+use crate::screentime::{self, ScreenTime};
+use crate::state::State;
+
+/// Without a bedtime in the schedule, activity past midnight makes a late-night session
+const LATE_NIGHT_HOUR: f64 = 24.0;
+
+/// `[weekly_summary]` config section: weekly notification in daemon mode
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WeeklyConfig {
+    pub day: Weekday,
+    pub time: TimeOfDay,
+}
+
+impl Default for WeeklyConfig {
+    fn default() -> Self {
+        WeeklyConfig { day: Weekday::Sun, time: TimeOfDay(19.0) }
+    }
+}
+
+/// Totals for the seven days up to and including a date
+#[derive(Debug)]
+pub struct WeekSummary {
+    pub active_seconds: f64,
+    pub breaks_per_day: f64,
+    /// Average evening blue light dose per day, see `DayStats::evening_blue_light`
+    pub evening_blue_light: f64,
+    /// The same average for the week before
+    pub previous_evening_blue_light: f64,
+    pub late_nights: usize,
+}
+
+impl WeekSummary {
+    /// Evening blue light change from the week before, e.g. "down 12%"
+    pub fn blue_light_trend(&self) -> String {
+        if self.previous_evening_blue_light <= 0.0 {
+            return "no data for the week before".to_string();
+        }
+
+        let change = (self.evening_blue_light / self.previous_evening_blue_light - 1.0) * 100.0;
+        match change.round() as i64 {
+            0 => "unchanged from last week".to_string(),
+            c if c < 0 => format!("down {}% from last week", -c),
+            c => format!("up {}% from last week", c),
+        }
+    }
+}
+
+/// Late-night sessions are counted against each day's bedtime from `schedule`
+pub fn summarize(history: &ScreenTime, end: NaiveDate, schedule: &ScheduleConfig) -> WeekSummary {
+    let week = |end: NaiveDate| {
+        let start = end - Duration::days(6);
+        history.days.range(start..=end).collect::<Vec<_>>()
+    };
+
+    let days = week(end);
+    let used = days.iter().filter(|(_, d)| d.active_seconds > 0.0).count().max(1) as f64;
+    let previous = week(end - Duration::days(7));
+    let previous_used = previous.iter().filter(|(_, d)| d.active_seconds > 0.0).count().max(1) as f64;
+
+    WeekSummary {
+        active_seconds: days.iter().map(|(_, d)| d.active_seconds).sum(),
+        breaks_per_day: days.iter().map(|(_, d)| d.breaks as f64).sum::<f64>() / used,
+        evening_blue_light: days.iter().map(|(_, d)| d.evening_blue_light).sum::<f64>() / used,
+        previous_evening_blue_light: previous.iter().map(|(_, d)| d.evening_blue_light).sum::<f64>() / previous_used,
+        late_nights: days
+            .iter()
+            .filter(|(date, d)| {
+                let late = schedule.bedtime_hours(date.weekday()).unwrap_or(LATE_NIGHT_HOUR);
+                d.last_active_hour.map_or(false, |h| h >= late)
+            })
+            .count(),
+    }
+}
+
+/// Prints the summary of the week ending on `end`
+pub fn report(history: &ScreenTime, end: NaiveDate, schedule: &ScheduleConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let summary = summarize(history, end, schedule);
+
+    println!("Week ending {}", end);
+    println!("  Screen time:         {}", screentime::format_duration(summary.active_seconds));
+    println!("  Breaks per day:      {:.1}", summary.breaks_per_day);
+    println!(
+        "  Evening blue light:  {} per day ({})",
+        screentime::format_duration(summary.evening_blue_light),
+        summary.blue_light_trend()
+    );
+    println!("  Late-night sessions: {}", summary.late_nights);
+
+    Ok(())
+}
+
+/// Sends the weekly notification once its configured time has come,
+/// waiting for quiet hours or Do-Not-Disturb to end
+pub fn check(config: &WeeklyConfig, quiet: &QuietConfig, schedule: &ScheduleConfig) {
+    let now = Local::now();
+    if now.weekday() != config.day || schedule::hour_of(now) < config.time.0 {
+        return;
+    }
+
+    let today = now.date_naive();
+    let mut state = State::load();
+    if state.weekly_summary_sent == Some(today) || notify::quiet(quiet) {
+        return;
+    }
+
+    let summary = summarize(&ScreenTime::load(), today, schedule);
+    let body = format!(
+        "Screen time {}, {:.1} breaks a day, {} late night(s). Evening blue light {}.\nFull report: healthy-monitor report --week",
+        screentime::format_duration(summary.active_seconds),
+        summary.breaks_per_day,
+        summary.late_nights,
+        summary.blue_light_trend(),
+    );
+    notify::send("Your week in front of the screen", &body, Urgency::Low);
+
+    state.weekly_summary_sent = Some(today);
+    if let Err(e) = state.save() {
+        eprintln!("Failed to save state: {}", e);
+    }
+}